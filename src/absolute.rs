@@ -1,21 +1,31 @@
+use std::borrow::Borrow;
+use std::borrow::Cow;
+use std::ffi::OsStr;
 use std::ops::Deref;
+use std::path::Component;
 use std::path::Path;
 use std::path::PathBuf;
+use std::path::PrefixComponent;
+use std::path::StripPrefixError;
 use std::str::FromStr;
 
-use itertools::EitherOrBoth;
-use itertools::Itertools;
 use ref_cast::RefCast;
 
 use crate::AbsoluteJoinError;
 use crate::AbsolutePathBufNewError;
 use crate::AbsolutePathNewError;
+use crate::AnonymousTempFile;
+use crate::CacheKey;
+use crate::CacheKeyOptions;
+use crate::InvalidEnvValue;
+use crate::InvalidFileName;
 use crate::JoinedAbsolute;
 use crate::NormalizationFailed;
 use crate::NotAbsolute;
 use crate::RelativePath;
 use crate::RelativePathBuf;
 use crate::RelativeToError;
+use crate::UniqueName;
 use crate::WasNotNormalized;
 
 /// An absolute path. This must be normalized to begin with.
@@ -41,6 +51,18 @@ impl AbsolutePath {
         }
     }
 
+    /// Like [`AbsolutePath::try_new`], but normalizes the path if needed rather than failing.
+    ///
+    /// The path is only copied if it had to be normalized.
+    pub fn try_new_normalized<P: AsRef<Path> + ?Sized>(
+        path: &P,
+    ) -> Result<CowAbsolutePath<'_>, AbsolutePathBufNewError> {
+        match Self::try_new(path) {
+            Ok(path) => Ok(Cow::Borrowed(path)),
+            Err(_) => Ok(Cow::Owned(AbsolutePathBuf::try_new(path.as_ref())?)),
+        }
+    }
+
     /// Create an [`AbsolutePath`] per [`AbsolutePath::try_new`] that panics on an invalid path.
     ///
     /// This is mostly used for paths that are known ahead of time (e.g. static strings) to be
@@ -89,11 +111,102 @@ impl AbsolutePath {
         self.0.parent().map(AbsolutePath::new_unchecked)
     }
 
+    /// Like `Path::ancestors()`, but yields [`AbsolutePath`]s.
+    ///
+    /// This starts with the path itself, and ends with the filesystem root.
+    pub fn ancestors(&self) -> impl Iterator<Item = &AbsolutePath> {
+        self.0.ancestors().map(AbsolutePath::new_unchecked)
+    }
+
+    /// Like `Path::starts_with()`, but only accepts an [`AbsolutePath`], so it cannot be mistaken
+    /// for a string prefix check. Only whole components are compared, so `/foo/bar` starts with
+    /// `/foo`, but `/foo/barbell` does not start with `/foo/bar`.
+    pub fn starts_with(&self, base: &AbsolutePath) -> bool {
+        self.0.starts_with(&base.0)
+    }
+
+    /// Like `Path::ends_with()`, but only accepts a [`RelativePath`]. Only whole components are
+    /// compared.
+    pub fn ends_with(&self, child: &RelativePath) -> bool {
+        self.0.ends_with(child.as_path())
+    }
+
+    /// The longest path that both this path and `other` are beneath (or equal to).
+    ///
+    /// This compares whole components, so `/foo/bar` and `/foo/baz` have `/foo` in common. It is
+    /// only `None` if the paths have different roots, e.g. different drives on windows.
+    pub fn common_ancestor(&self, other: &AbsolutePath) -> Option<&AbsolutePath> {
+        self.ancestors()
+            .find(|ancestor| other.0.starts_with(&ancestor.0))
+    }
+
+    /// Split this path and `other` where they diverge, into their [common
+    /// ancestor](AbsolutePath::common_ancestor), and what is left of each of them beneath it.
+    ///
+    /// e.g. `/foo/bar/baz` and `/foo/quz` are split into `/foo`, `bar/baz` and `quz`. The
+    /// remainders are empty if the paths are equal, and one is empty if that path is an ancestor
+    /// of the other. Like [`AbsolutePath::common_ancestor`], this is only `None` if the paths
+    /// have different roots.
+    pub fn diverging_components<'a, 'b>(
+        &'a self,
+        other: &'b AbsolutePath,
+    ) -> Option<(&'a AbsolutePath, &'a RelativePath, &'b RelativePath)> {
+        let common = self.common_ancestor(other)?;
+        let ours = self
+            .strip_prefix(common)
+            .expect("the common ancestor is a prefix");
+        let theirs = other
+            .strip_prefix(common)
+            .expect("the common ancestor is a prefix");
+        Some((common, ours, theirs))
+    }
+
+    /// Like `Path::components()`, but yields [`AbsoluteComponent`]s, which cannot be `.` or `..`
+    /// as the path is normalized.
+    pub fn components(&self) -> impl DoubleEndedIterator<Item = AbsoluteComponent<'_>> {
+        self.0.components().map(|c| match c {
+            Component::Prefix(p) => AbsoluteComponent::Prefix(p),
+            Component::RootDir => AbsoluteComponent::RootDir,
+            Component::Normal(name) => AbsoluteComponent::Normal(name),
+            Component::CurDir | Component::ParentDir => {
+                unreachable!("absolute paths are normalized")
+            }
+        })
+    }
+
+    /// Like `Path::file_name()`, but returns a [`RelativePath`], so it can be joined to other
+    /// paths without re-validating it.
+    ///
+    /// This is `None` for the filesystem root.
+    pub fn file_name(&self) -> Option<&RelativePath> {
+        self.0.file_name().map(RelativePath::new_unchecked)
+    }
+
+    /// Like `Path::strip_prefix()`, but returns a [`RelativePath`].
+    ///
+    /// If `base` is the same as this path, the result is empty.
+    pub fn strip_prefix(&self, base: &AbsolutePath) -> Result<&RelativePath, StripPrefixError> {
+        self.0
+            .strip_prefix(&base.0)
+            .map(RelativePath::new_unchecked)
+    }
+
     /// Like `Path::to_string_lossy()`, but returns an owned string.
     pub fn to_lossy_string(&self) -> String {
         self.0.to_string_lossy().to_string()
     }
 
+    /// Encode this path as a UTF-8 string that [`AbsolutePathBuf::from_env_value`] decodes
+    /// exactly, even if the path is not valid UTF-8, e.g. to pass it to a child process in an
+    /// environment variable.
+    ///
+    /// UTF-8 paths are unchanged, except that `%` is escaped as `%25`, and anything else is
+    /// escaped with `%`. Like paths themselves, the encoding of anything else is platform
+    /// specific.
+    pub fn to_env_value(&self) -> String {
+        crate::env_value::encode(self.0.as_os_str())
+    }
+
     /// Ensures that the parent path, if there is one, exists.
     pub fn ensure_parent_exists(&self) -> std::io::Result<()> {
         crate::create_parent_dir(self)
@@ -102,34 +215,636 @@ impl AbsolutePath {
     /// Gets the relative path between two absolute paths.
     ///
     /// e.g. `/foo/bar/baz` relative to `/foo/baz/quz` would yield `../../bar/baz`
+    ///
+    /// Fails if the paths are identical, or have different roots, e.g. `C:\foo` and `D:\bar`.
     pub fn relative_to(&self, other: &AbsolutePath) -> Result<RelativePathBuf, RelativeToError> {
         if self == other {
             return Err(RelativeToError::PathsAreIdentical);
         }
-        // TODO: Check how this actually works on windows, especially on different roots
-        let mut diverged = false;
-        let mut upward_path = PathBuf::new();
-        let mut new_path = PathBuf::new();
-        for components in self.0.components().zip_longest(other.components()) {
-            match components {
-                EitherOrBoth::Both(l, r) => {
-                    if l != r || diverged {
-                        diverged = true;
-                        upward_path.push("..");
-                        new_path.push(l);
+        let (_, ours, theirs) = self
+            .diverging_components(other)
+            .ok_or(RelativeToError::DifferentRoots)?;
+        let mut upward_path: PathBuf = theirs.components().map(|_| "..").collect();
+        upward_path.push(ours.as_path());
+        // Leading `..`s followed by the normal components of a normalized path are already
+        // normalized.
+        Ok(RelativePathBuf::new_unchecked(upward_path))
+    }
+
+    /// Get this path with each component in the casing that is actually stored on disk.
+    ///
+    /// This is useful on case-insensitive filesystems (the default on windows and macOS), where
+    /// `/users/foo` and `/Users/Foo` refer to the same file, but do not compare equal. An exact
+    /// match for a component is preferred over a case-insensitive one.
+    ///
+    /// This queries the filesystem for each component, and fails with
+    /// [`std::io::ErrorKind::NotFound`] if any component does not exist.
+    pub fn true_case(&self) -> std::io::Result<AbsolutePathBuf> {
+        let mut resolved = PathBuf::new();
+        for component in self.0.components() {
+            let name = match component {
+                Component::Normal(name) => name,
+                c => {
+                    resolved.push(c);
+                    continue;
+                }
+            };
+            let on_disk = std::fs::read_dir(&resolved).ok().and_then(|entries| {
+                let names: Vec<_> = entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.file_name())
+                    .collect();
+                names
+                    .iter()
+                    .find(|n| n.as_os_str() == name)
+                    .or_else(|| names.iter().find(|n| eq_ignore_case(n, name)))
+                    .cloned()
+            });
+            match on_disk {
+                Some(on_disk) => resolved.push(on_disk),
+                // The directory might be traversable, but not listable.
+                None if resolved.join(name).exists() => resolved.push(name),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("`{}` does not exist", resolved.join(name).display()),
+                    ));
+                }
+            }
+        }
+        Ok(AbsolutePathBuf(resolved))
+    }
+
+    /// Get a string form of this path for use as a stable cache key, so that different
+    /// spellings of the same file share cache entries.
+    ///
+    /// The steps that normalize the path are described and configured by `options`. By default,
+    /// symlinks are resolved, windows' verbatim prefixes are removed, separators become `/`, and
+    /// the key is lowercased on windows and macOS.
+    pub fn cache_key(&self, options: &CacheKeyOptions) -> std::io::Result<CacheKey> {
+        options.key(self)
+    }
+
+    /// Get the path to `name` in the same directory as this path, like
+    /// `Path::with_file_name()`.
+    ///
+    /// `name` must be a single file name, without any separators, `.` or `..`.
+    pub fn sibling<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        name: &S,
+    ) -> Result<AbsolutePathBuf, InvalidFileName> {
+        let name = name.as_ref();
+        crate::check_file_name(name)?;
+        Ok(AbsolutePathBuf(self.0.with_file_name(name)))
+    }
+
+    /// Like `Path::with_file_name()`, but returns an [`AbsolutePathBuf`]. This is the same as
+    /// [`AbsolutePath::sibling`].
+    pub fn with_file_name<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        name: &S,
+    ) -> Result<AbsolutePathBuf, InvalidFileName> {
+        self.sibling(name)
+    }
+
+    /// Like `Path::with_extension()`, but returns an [`AbsolutePathBuf`].
+    ///
+    /// Fails if this path has no file name, or the extension contains a separator.
+    pub fn with_extension<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        extension: &S,
+    ) -> Result<AbsolutePathBuf, InvalidFileName> {
+        let name = crate::file_name_with_extension(&self.0, extension.as_ref())?;
+        Ok(AbsolutePathBuf(self.0.with_file_name(name)))
+    }
+
+    /// Get the path to a child of this directory named `prefix` followed by the current time
+    /// (in UTC) formatted with `format`, e.g. `backup-2024-06-01T12-30-00Z` for the prefix
+    /// `backup-` and the format `%Y-%m-%dT%H-%M-%SZ`.
+    ///
+    /// `format` supports `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`, `%f` (milliseconds) and `%%`. If
+    /// something already exists at the path, `-1`, `-2` etc is appended until it does not.
+    ///
+    /// Fails if `format` has any other specifiers, or the name would not be a single file name
+    /// that is valid on every platform, e.g. because it contains `:`.
+    pub fn child_with_timestamp(
+        &self,
+        prefix: &str,
+        format: &str,
+    ) -> Result<AbsolutePathBuf, InvalidFileName> {
+        let name = format!(
+            "{}{}",
+            prefix,
+            format_timestamp(format, std::time::SystemTime::now())?
+        );
+        crate::check_portable_file_name(&name)?;
+
+        let mut child = AbsolutePathBuf(self.0.join(&name));
+        let mut suffix = 0;
+        while child.symlink_metadata().is_ok() {
+            suffix += 1;
+            child = AbsolutePathBuf(self.0.join(format!("{}-{}", name, suffix)));
+        }
+        Ok(child)
+    }
+
+    /// Write `contents` to this file, first copying any existing file to a backup beside it.
+    ///
+    /// Returns the path of the backup, or `None` if there was no file to back up. The backup is
+    /// made with [`crate::fs::reflink`], and the new contents are written with
+    /// [`crate::fs::write_atomic`], so the file keeps its permissions, and is never left partly
+    /// written.
+    pub fn write_with_backup(
+        &self,
+        contents: impl AsRef<[u8]>,
+        style: BackupStyle,
+    ) -> std::io::Result<Option<AbsolutePathBuf>> {
+        let backup = if self.is_file() {
+            let backup = self.backup_path(style)?;
+            crate::fs::reflink(self, &backup)?;
+            Some(backup)
+        } else {
+            None
+        };
+        crate::fs::write_atomic(self, contents)?;
+        Ok(backup)
+    }
+
+    fn backup_path(&self, style: BackupStyle) -> std::io::Result<AbsolutePathBuf> {
+        let name = self.file_name().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("`{}` has no file name to back up", self.0.display()),
+            )
+        })?;
+        let mut backup_name = name.as_os_str().to_os_string();
+        match style {
+            BackupStyle::Simple => backup_name.push(".bak"),
+            BackupStyle::Numbered => {
+                let mut prefix = name.as_os_str().to_os_string();
+                prefix.push(".~");
+                let prefix = prefix.to_string_lossy().into_owned();
+                let mut highest = 0;
+                if let Some(parent) = self.parent() {
+                    for entry in std::fs::read_dir(parent)? {
+                        let entry_name = entry?.file_name();
+                        let number = entry_name
+                            .to_str()
+                            .and_then(|n| n.strip_prefix(&prefix))
+                            .and_then(|n| n.strip_suffix('~'))
+                            .and_then(|n| n.parse::<u64>().ok());
+                        highest = highest.max(number.unwrap_or(0));
                     }
                 }
-                EitherOrBoth::Left(l) => {
-                    diverged = true;
-                    new_path.push(l);
+                backup_name.push(format!(".~{}~", highest + 1));
+            }
+        }
+        Ok(self
+            .sibling(&backup_name)
+            .expect("a file name with a suffix is still a file name"))
+    }
+
+    /// Create an empty file in this directory with a random name shaped by `name`, e.g.
+    /// `upload-3f9k2m0q8zj1x7cd.json`, and return its path.
+    ///
+    /// The file is created exclusively, so unlike checking that a generated path does not exist
+    /// before using it, no other process can claim the same name. Fails with
+    /// [`std::io::ErrorKind::InvalidInput`] if the names would not be portable file names.
+    pub fn child_unique(&self, name: &UniqueName) -> std::io::Result<AbsolutePathBuf> {
+        name.create_in(self)
+    }
+
+    /// Create a temporary file with no name in this directory.
+    ///
+    /// See [`AnonymousTempFile`] for details. This is useful for streaming large outputs that
+    /// should only appear at their destination once they are complete.
+    pub fn create_anonymous_temp(&self) -> std::io::Result<AnonymousTempFile> {
+        AnonymousTempFile::create_in(self)
+    }
+
+    /// Copy this file to `dest` as a copy-on-write clone if possible, otherwise as a plain copy.
+    ///
+    /// See [`crate::fs::reflink`].
+    pub fn reflink_to(&self, dest: &AbsolutePath) -> std::io::Result<crate::fs::CopyMethod> {
+        crate::fs::reflink(self, dest)
+    }
+
+    /// Get the number of bytes actually allocated on disk for this file.
+    ///
+    /// For sparse files, this can be much smaller than the logical size from
+    /// [`std::fs::Metadata::len`]. This is only available on unix; on other platforms it returns
+    /// the logical size.
+    pub fn allocated_size(&self) -> std::io::Result<u64> {
+        let metadata = std::fs::metadata(self)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            // `st_blocks` is always in units of 512 bytes, regardless of the filesystem.
+            Ok(metadata.blocks() * 512)
+        }
+        #[cfg(not(unix))]
+        {
+            Ok(metadata.len())
+        }
+    }
+
+    /// The number of bytes available to the current user on the volume that contains this path.
+    ///
+    /// This can be less than the free space, e.g. on unix filesystems that reserve space for
+    /// root. The path must exist.
+    pub fn available_space(&self) -> std::io::Result<u64> {
+        disk_space(&self.0).map(|(available, _)| available)
+    }
+
+    /// The total size in bytes of the volume that contains this path. The path must exist.
+    pub fn total_space(&self) -> std::io::Result<u64> {
+        disk_space(&self.0).map(|(_, total)| total)
+    }
+
+    /// Whether this path was modified more recently than `other`.
+    ///
+    /// Both paths must exist. Symlinks are followed.
+    pub fn is_newer_than(&self, other: &AbsolutePath) -> std::io::Result<bool> {
+        Ok(std::fs::metadata(self)?.modified()? > std::fs::metadata(other)?.modified()?)
+    }
+
+    /// Find the most recently modified entry in the tree rooted at this path (including this
+    /// path itself), and its modification time.
+    ///
+    /// Directories are included, as adding or removing an entry updates their modification
+    /// time. Symlinks are not followed. If several entries share the newest time, the first
+    /// one found is returned.
+    pub fn newest_in_tree(&self) -> std::io::Result<(AbsolutePathBuf, std::time::SystemTime)> {
+        let metadata = std::fs::symlink_metadata(self)?;
+        let mut newest = (AbsolutePathBuf::from(self), metadata.modified()?);
+        let mut to_visit = vec![];
+        if metadata.is_dir() {
+            to_visit.push(newest.0.clone());
+        }
+        while let Some(dir) = to_visit.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                let path = AbsolutePathBuf::new_unchecked(entry.path());
+                let modified = metadata.modified()?;
+                if modified > newest.1 {
+                    newest = (path.clone(), modified);
                 }
-                EitherOrBoth::Right(_) => {
-                    diverged = true;
-                    upward_path.push("..");
+                if metadata.is_dir() {
+                    to_visit.push(path);
                 }
             }
         }
-        Ok(RelativePathBuf::try_new(upward_path.join(new_path)).unwrap())
+        Ok(newest)
+    }
+
+    /// Find the entries in this directory whose names are near misses for `file_name`, e.g. so
+    /// that a CLI can suggest `config.toml` when given `confg.toml`.
+    ///
+    /// Names are compared by Levenshtein distance (in characters), and only those at most
+    /// `max_distance` edits away are returned, closest first, then sorted by name. An entry
+    /// named exactly `file_name` is not a near miss, so is left out, as are names that are not
+    /// UTF-8.
+    pub fn suggest_similar(
+        &self,
+        file_name: &str,
+        max_distance: usize,
+    ) -> std::io::Result<Vec<AbsolutePathBuf>> {
+        let mut suggestions = vec![];
+        for entry in std::fs::read_dir(self)? {
+            let name = entry?.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let distance = edit_distance(file_name, name);
+            if name != file_name && distance <= max_distance {
+                suggestions.push((distance, name.to_owned()));
+            }
+        }
+        suggestions.sort();
+        Ok(suggestions
+            .into_iter()
+            .map(|(_, name)| AbsolutePathBuf(self.0.join(name)))
+            .collect())
+    }
+
+    /// Set the last modification time of this path.
+    #[cfg(feature = "filetime")]
+    pub fn set_mtime(&self, mtime: std::time::SystemTime) -> std::io::Result<()> {
+        filetime::set_file_mtime(self, filetime::FileTime::from_system_time(mtime))
+    }
+
+    /// Set the last access time of this path.
+    #[cfg(feature = "filetime")]
+    pub fn set_atime(&self, atime: std::time::SystemTime) -> std::io::Result<()> {
+        filetime::set_file_atime(self, filetime::FileTime::from_system_time(atime))
+    }
+
+    /// Like the `touch` command, create this file if it does not exist, and set its access and
+    /// modification times to now.
+    #[cfg(feature = "filetime")]
+    pub fn touch(&self) -> std::io::Result<()> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self)?;
+        let now = filetime::FileTime::now();
+        filetime::set_file_times(self, now, now)
+    }
+
+    /// Expand any windows `8.3` short names (e.g. `PROGRA~1`) into their long forms.
+    ///
+    /// The path must exist. On platforms other than windows there are no short names, so this
+    /// returns the path unchanged.
+    #[cfg(feature = "short-names")]
+    pub fn expand_short_names(&self) -> std::io::Result<AbsolutePathBuf> {
+        #[cfg(windows)]
+        {
+            AbsolutePathBuf::try_new(crate::windows::long_path_name(&self.0)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        }
+        #[cfg(not(windows))]
+        {
+            Ok(self.into())
+        }
+    }
+
+    /// Whether this path is a windows reparse point, e.g. a symlink, a junction, or a placeholder
+    /// for a file in cloud storage.
+    ///
+    /// The path itself is checked, rather than what it links to. This is `false` if the path does
+    /// not exist, and always `false` on other platforms.
+    pub fn is_reparse_point(&self) -> bool {
+        #[cfg(windows)]
+        {
+            crate::windows::is_reparse_point(&self.0)
+        }
+        #[cfg(not(windows))]
+        {
+            false
+        }
+    }
+
+    /// Whether this path is a windows junction (a mount point), which behaves like a symlink to
+    /// a directory, but is neither a symlink nor a directory according to its metadata.
+    ///
+    /// This is `false` if the path does not exist, and always `false` on other platforms.
+    #[cfg(feature = "junctions")]
+    pub fn is_junction(&self) -> bool {
+        #[cfg(windows)]
+        {
+            crate::windows::is_junction(&self.0)
+        }
+        #[cfg(not(windows))]
+        {
+            false
+        }
+    }
+
+    /// Create a windows junction at this path, which refers to the directory `target`.
+    ///
+    /// Unlike symlinks, junctions can be created without any special privileges, but only refer
+    /// to directories on local volumes. `target` does not need to exist yet. This fails with
+    /// [`std::io::ErrorKind::Unsupported`] on other platforms.
+    #[cfg(feature = "junctions")]
+    pub fn create_junction(&self, target: &AbsolutePath) -> std::io::Result<()> {
+        #[cfg(windows)]
+        {
+            crate::windows::create_junction(&self.0, &target.0)
+        }
+        #[cfg(not(windows))]
+        {
+            let _ = target;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "junctions are only supported on windows",
+            ))
+        }
+    }
+
+    /// Whether the current process can read, write and execute this path, e.g. for checks before
+    /// starting a long operation.
+    ///
+    /// On windows, this checks the path's ACL against the current process's token, which
+    /// `metadata().permissions()` does not, and files with the readonly attribute are never
+    /// writable. On unix, this uses `faccessat(2)` with the effective user and group ids. The
+    /// path must exist.
+    #[cfg(feature = "acl")]
+    pub fn effective_access(&self) -> std::io::Result<EffectiveAccess> {
+        #[cfg(windows)]
+        {
+            let (read, write, execute) = crate::windows::effective_access(&self.0)?;
+            let readonly =
+                std::fs::metadata(&self.0).map(|m| m.is_file() && m.permissions().readonly())?;
+            Ok(EffectiveAccess {
+                read,
+                write: write && !readonly,
+                execute,
+            })
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            let path = std::ffi::CString::new(self.0.as_os_str().as_bytes())?;
+            let check = |mode: libc::c_int| -> std::io::Result<bool> {
+                // SAFETY: `path` is nul terminated.
+                if unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), mode, libc::AT_EACCESS) }
+                    == 0
+                {
+                    return Ok(true);
+                }
+                let e = std::io::Error::last_os_error();
+                match e.raw_os_error() {
+                    Some(libc::EACCES) | Some(libc::EPERM) | Some(libc::EROFS)
+                    | Some(libc::ETXTBSY) => Ok(false),
+                    _ => Err(e),
+                }
+            };
+            Ok(EffectiveAccess {
+                read: check(libc::R_OK)?,
+                write: check(libc::W_OK)?,
+                execute: check(libc::X_OK)?,
+            })
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "effective access is not supported on this platform",
+            ))
+        }
+    }
+}
+
+/// A component of an [`AbsolutePath`], from [`AbsolutePath::components`].
+///
+/// This is like `std::path::Component`, without `CurDir` and `ParentDir`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum AbsoluteComponent<'a> {
+    /// A windows prefix, e.g. `C:`. This is never present on unix.
+    Prefix(PrefixComponent<'a>),
+    /// The root directory, e.g. the leading `/` or `\`.
+    RootDir,
+    /// A directory or file name.
+    Normal(&'a OsStr),
+}
+
+impl<'a> AbsoluteComponent<'a> {
+    /// Get the component as it appears in the path.
+    pub fn as_os_str(self) -> &'a OsStr {
+        Component::from(self).as_os_str()
+    }
+}
+
+impl<'a> From<AbsoluteComponent<'a>> for Component<'a> {
+    fn from(c: AbsoluteComponent<'a>) -> Self {
+        match c {
+            AbsoluteComponent::Prefix(p) => Component::Prefix(p),
+            AbsoluteComponent::RootDir => Component::RootDir,
+            AbsoluteComponent::Normal(name) => Component::Normal(name),
+        }
+    }
+}
+
+impl AsRef<Path> for AbsoluteComponent<'_> {
+    fn as_ref(&self) -> &Path {
+        self.as_os_str().as_ref()
+    }
+}
+
+/// How [`AbsolutePath::write_with_backup`] names backups, following the styles of GNU `cp
+/// --backup`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BackupStyle {
+    /// `file.bak`, replacing any previous backup.
+    Simple,
+    /// `file.~1~`, `file.~2~`, etc, keeping every previous backup.
+    Numbered,
+}
+
+/// What the current process can do with a path, from [`AbsolutePath::effective_access`].
+#[cfg(feature = "acl")]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct EffectiveAccess {
+    /// Whether the file can be read, or the directory listed.
+    pub read: bool,
+    /// Whether the file can be written, or entries created in the directory.
+    pub write: bool,
+    /// Whether the file can be executed, or the directory traversed.
+    pub execute: bool,
+}
+
+/// The available and total bytes on the volume that contains `path`.
+fn disk_space(path: &Path) -> std::io::Result<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+        // SAFETY: `statvfs` is plain data, so zeroes are a valid value.
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is nul terminated, and `stats` is a valid out pointer.
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let block_size = stats.f_frsize as u64;
+        Ok((
+            stats.f_bavail as u64 * block_size,
+            stats.f_blocks as u64 * block_size,
+        ))
+    }
+    #[cfg(windows)]
+    {
+        crate::windows::disk_space(path)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "disk space is not supported on this platform",
+        ))
+    }
+}
+
+/// Format `time` in UTC for [`AbsolutePath::child_with_timestamp`].
+fn format_timestamp(format: &str, time: std::time::SystemTime) -> Result<String, InvalidFileName> {
+    let since_epoch = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let seconds_of_day = seconds % 86400;
+
+    let mut formatted = String::with_capacity(format.len() * 2);
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        let field = match chars.next() {
+            Some('Y') => format!("{:04}", year),
+            Some('m') => format!("{:02}", month),
+            Some('d') => format!("{:02}", day),
+            Some('H') => format!("{:02}", seconds_of_day / 3600),
+            Some('M') => format!("{:02}", seconds_of_day / 60 % 60),
+            Some('S') => format!("{:02}", seconds_of_day % 60),
+            Some('f') => format!("{:03}", since_epoch.subsec_millis()),
+            Some('%') => "%".to_owned(),
+            _ => return Err(InvalidFileName(format.to_owned())),
+        };
+        formatted.push_str(&field);
+    }
+    Ok(formatted)
+}
+
+/// The `(year, month, day)` of a number of days since the unix epoch, in the proleptic
+/// Gregorian calendar.
+///
+/// This is Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The Levenshtein distance between `left` and `right`, counting characters.
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    // The distances from a prefix of `left` to each prefix of `right`.
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, l) in left.chars().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, r) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(l != *r);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+fn eq_ignore_case(left: &OsStr, right: &OsStr) -> bool {
+    if left.eq_ignore_ascii_case(right) {
+        return true;
+    }
+    match (left.to_str(), right.to_str()) {
+        (Some(l), Some(r)) => l.to_lowercase() == r.to_lowercase(),
+        _ => false,
     }
 }
 
@@ -145,6 +860,14 @@ impl AsRef<AbsolutePath> for AbsolutePath {
     }
 }
 
+impl ToOwned for AbsolutePath {
+    type Owned = AbsolutePathBuf;
+
+    fn to_owned(&self) -> Self::Owned {
+        self.into()
+    }
+}
+
 impl Deref for AbsolutePath {
     type Target = Path;
 
@@ -166,7 +889,45 @@ impl serde::Serialize for AbsolutePath {
     where
         S: serde::Serializer,
     {
-        self.0.serialize(serializer)
+        crate::serde_path::serialize(&self.0, serializer)
+    }
+}
+
+/// Borrows the path from the input without allocating, e.g. for large manifests.
+///
+/// Unlike [`AbsolutePathBuf`], the path must already be normalized, and the input must allow
+/// borrowing, e.g. a JSON string without any escapes.
+#[cfg(feature = "serde")]
+impl<'de: 'a, 'a> serde::Deserialize<'de> for &'a AbsolutePath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let path = crate::serde_path::deserialize_borrowed(deserializer)?;
+        AbsolutePath::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+/// Either a borrowed [`AbsolutePath`] or an owned [`AbsolutePathBuf`], for code that only
+/// sometimes has to allocate a new path.
+pub type CowAbsolutePath<'a> = Cow<'a, AbsolutePath>;
+
+impl<'a> From<&'a AbsolutePath> for CowAbsolutePath<'a> {
+    fn from(path: &'a AbsolutePath) -> Self {
+        Cow::Borrowed(path)
+    }
+}
+
+impl<'a> From<&'a AbsolutePathBuf> for CowAbsolutePath<'a> {
+    fn from(path: &'a AbsolutePathBuf) -> Self {
+        Cow::Borrowed(path.as_absolute_path())
+    }
+}
+
+impl From<AbsolutePathBuf> for CowAbsolutePath<'_> {
+    fn from(path: AbsolutePathBuf) -> Self {
+        Cow::Owned(path)
     }
 }
 
@@ -184,7 +945,7 @@ impl AbsolutePathBuf {
     ///
     /// This will fail if the provided path is relative, or if, when normalizing, the path would
     /// traverse beyond the root of the filesystem.
-    pub fn try_new<P: Into<PathBuf> + ?Sized>(path: P) -> Result<Self, AbsolutePathBufNewError> {
+    pub fn try_new<P: Into<PathBuf>>(path: P) -> Result<Self, AbsolutePathBufNewError> {
         let p = path.into();
         if p.is_relative() {
             Err(NotAbsolute(p.display().to_string()).into())
@@ -222,7 +983,7 @@ impl AbsolutePathBuf {
     ///
     /// This is mostly used for paths that are known ahead of time (e.g. static strings) to be
     /// valid.
-    pub fn new_unchecked<P: Into<PathBuf> + ?Sized>(path: P) -> Self {
+    pub fn new_unchecked<P: Into<PathBuf>>(path: P) -> Self {
         Self::try_new(path).expect("an absolute path")
     }
 
@@ -281,6 +1042,76 @@ impl AbsolutePathBuf {
         self.0.parent().map(AbsolutePath::new_unchecked)
     }
 
+    /// Like [`AbsolutePathBuf::join`], but modifies this path in place rather than allocating a
+    /// new one.
+    ///
+    /// If this fails, the path is left unchanged.
+    pub fn push<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AbsoluteJoinError> {
+        let p = path.as_ref();
+        if p.is_absolute() {
+            return Err(
+                JoinedAbsolute(self.0.display().to_string(), p.display().to_string()).into(),
+            );
+        }
+        // Check that `..` never goes above the root before changing anything.
+        let mut depth = self
+            .0
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .count();
+        for c in p.components() {
+            match c {
+                Component::Normal(_) => depth += 1,
+                Component::ParentDir => match depth.checked_sub(1) {
+                    Some(d) => depth = d,
+                    None => {
+                        return Err(NormalizationFailed(self.0.join(p).display().to_string()).into())
+                    }
+                },
+                _ => {}
+            }
+        }
+        for c in p.components() {
+            match c {
+                Component::Normal(n) => self.0.push(n),
+                Component::ParentDir => {
+                    self.0.pop();
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Truncate this path to its parent, like `PathBuf::pop()`.
+    ///
+    /// Returns `false` and does nothing if this is the filesystem root.
+    pub fn pop(&mut self) -> bool {
+        self.0.pop()
+    }
+
+    /// Like `PathBuf::set_file_name()`, but fails if `name` is not a single file name.
+    pub fn set_file_name<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        name: &S,
+    ) -> Result<(), InvalidFileName> {
+        let name = name.as_ref();
+        crate::check_file_name(name)?;
+        self.0.set_file_name(name);
+        Ok(())
+    }
+
+    /// Like `PathBuf::set_extension()`, but fails if this path has no file name, or the
+    /// extension contains a separator.
+    pub fn set_extension<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        extension: &S,
+    ) -> Result<(), InvalidFileName> {
+        let name = crate::file_name_with_extension(&self.0, extension.as_ref())?;
+        self.0.set_file_name(name);
+        Ok(())
+    }
+
     /// Like `Path::to_string_lossy()`, but returns an owned string.
     pub fn to_lossy_string(&self) -> String {
         self.0.to_string_lossy().to_string()
@@ -290,6 +1121,19 @@ impl AbsolutePathBuf {
     pub fn ensure_parent_exists(&self) -> std::io::Result<()> {
         crate::create_parent_dir(self)
     }
+
+    /// The number of bytes allocated on the heap for this path.
+    pub fn heap_size(&self) -> usize {
+        self.0.capacity()
+    }
+
+    /// Decode a path from [`AbsolutePath::to_env_value`], e.g. from an environment variable set
+    /// by a parent process.
+    pub fn from_env_value(value: &str) -> Result<Self, InvalidEnvValue> {
+        let invalid = |reason: String| InvalidEnvValue(value.to_owned(), reason);
+        let path = crate::env_value::decode(value).map_err(|e| invalid(e.to_owned()))?;
+        AbsolutePathBuf::try_new(path).map_err(|e| invalid(e.to_string()))
+    }
 }
 
 impl From<&AbsolutePath> for AbsolutePathBuf {
@@ -326,6 +1170,12 @@ impl AsRef<AbsolutePath> for AbsolutePathBuf {
     }
 }
 
+impl Borrow<AbsolutePath> for AbsolutePathBuf {
+    fn borrow(&self) -> &AbsolutePath {
+        self.as_absolute_path()
+    }
+}
+
 impl Deref for AbsolutePathBuf {
     type Target = AbsolutePath;
 
@@ -347,7 +1197,7 @@ impl serde::Serialize for AbsolutePathBuf {
     where
         S: serde::Serializer,
     {
-        self.0.serialize(serializer)
+        crate::serde_path::serialize(&self.0, serializer)
     }
 }
 
@@ -358,7 +1208,7 @@ impl<'de> serde::Deserialize<'de> for AbsolutePathBuf {
         D: serde::Deserializer<'de>,
     {
         use serde::de::Error;
-        let path = PathBuf::deserialize(deserializer)?;
+        let path = crate::serde_path::deserialize(deserializer)?;
         AbsolutePathBuf::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
     }
 }
@@ -373,7 +1223,7 @@ where
         &'b self,
         out: &mut diesel::serialize::Output<'b, '_, DB>,
     ) -> diesel::serialize::Result {
-        self.0.to_str().expect("paths should be utf8").to_sql(out)
+        crate::path_to_str(&self.0)?.to_sql(out)
     }
 }
 
@@ -387,7 +1237,7 @@ where
         &'b self,
         out: &mut diesel::serialize::Output<'b, '_, DB>,
     ) -> diesel::serialize::Result {
-        self.0.to_str().expect("paths should be utf8").to_sql(out)
+        crate::path_to_str(&self.0)?.to_sql(out)
     }
 }
 
@@ -397,23 +1247,338 @@ where
     DB: diesel::backend::Backend,
     String: diesel::deserialize::FromSql<diesel::sql_types::Text, DB>,
 {
-    fn from_sql(bytes: diesel::backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+    fn from_sql(
+        bytes: <DB as diesel::backend::Backend>::RawValue<'_>,
+    ) -> diesel::deserialize::Result<Self> {
         String::from_sql(bytes).and_then(|s| Ok(AbsolutePathBuf::try_new(s)?))
     }
 }
 
+#[cfg(feature = "sqlx")]
+impl<DB> sqlx::Type<DB> for AbsolutePathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        String::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        String::compatible(ty)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'q, DB> sqlx::Encode<'q, DB> for AbsolutePathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        crate::path_to_str(&self.0)?.to_owned().encode(buf)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'r, DB> sqlx::Decode<'r, DB> for AbsolutePathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(AbsolutePathBuf::try_new(String::decode(value)?)?)
+    }
+}
+
+/// Fails if the path is not UTF-8, as only strings can be stored.
+#[cfg(feature = "sea-orm")]
+impl TryFrom<AbsolutePathBuf> for sea_orm::sea_query::Value {
+    type Error = crate::NotUtf8;
+
+    fn try_from(path: AbsolutePathBuf) -> Result<Self, Self::Error> {
+        let path = crate::path_to_str(path.as_path())?.to_owned();
+        Ok(sea_orm::sea_query::Value::String(Some(Box::new(path))))
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::TryGetable for AbsolutePathBuf {
+    fn try_get_by<I: sea_orm::ColIdx>(
+        res: &sea_orm::QueryResult,
+        index: I,
+    ) -> Result<Self, sea_orm::TryGetError> {
+        AbsolutePathBuf::try_new(String::try_get_by(res, index)?)
+            .map_err(|e| sea_orm::TryGetError::DbErr(sea_orm::DbErr::Type(e.to_string())))
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::sea_query::ValueType for AbsolutePathBuf {
+    fn try_from(v: sea_orm::sea_query::Value) -> Result<Self, sea_orm::sea_query::ValueTypeErr> {
+        match v {
+            sea_orm::sea_query::Value::String(Some(path)) => {
+                AbsolutePathBuf::try_new(*path).map_err(|_| sea_orm::sea_query::ValueTypeErr)
+            }
+            _ => Err(sea_orm::sea_query::ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "AbsolutePathBuf".to_owned()
+    }
+
+    fn array_type() -> sea_orm::sea_query::ArrayType {
+        sea_orm::sea_query::ArrayType::String
+    }
+
+    fn column_type() -> sea_orm::sea_query::ColumnType {
+        sea_orm::sea_query::ColumnType::String(None)
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::sea_query::Nullable for AbsolutePathBuf {
+    fn null() -> sea_orm::sea_query::Value {
+        sea_orm::sea_query::Value::String(None)
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::ToSql for AbsolutePath {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        crate::path_to_str(&self.0)
+            .map(Into::into)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::ToSql for AbsolutePathBuf {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        crate::path_to_str(&self.0)
+            .map(Into::into)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::types::FromSql for AbsolutePathBuf {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        AbsolutePathBuf::try_new(String::column_result(value)?)
+            .map_err(|e| rusqlite::types::FromSqlError::Other(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl postgres_types::ToSql for AbsolutePathBuf {
+    fn to_sql(
+        &self,
+        ty: &postgres_types::Type,
+        out: &mut bytes::BytesMut,
+    ) -> Result<postgres_types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        crate::path_to_str(&self.0)?.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &postgres_types::Type) -> bool {
+        <&str as postgres_types::ToSql>::accepts(ty)
+    }
+
+    postgres_types::to_sql_checked!();
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> postgres_types::FromSql<'a> for AbsolutePathBuf {
+    fn from_sql(
+        ty: &postgres_types::Type,
+        raw: &'a [u8],
+    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(AbsolutePathBuf::try_new(
+            <&str as postgres_types::FromSql>::from_sql(ty, raw)?,
+        )?)
+    }
+
+    fn accepts(ty: &postgres_types::Type) -> bool {
+        <&str as postgres_types::FromSql>::accepts(ty)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for AbsolutePathBuf {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "AbsolutePathBuf".to_owned()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("paths::AbsolutePathBuf")
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        crate::path_schema(
+            "absolute-path",
+            "An absolute path, e.g. `/home/user/file.txt`. `.` and `..` components are normalized away.",
+        )
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for AbsolutePathBuf {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut path = PathBuf::from(crate::fuzzing::root(u)?);
+        path.extend(crate::fuzzing::components(u)?);
+        AbsolutePathBuf::try_new(path).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+/// An archived [`AbsolutePathBuf`], which can be used as a [`AbsolutePath`] without copying it.
+///
+/// When accessed with validation, e.g. by `rkyv::access`, the path is checked to be a valid
+/// [`AbsolutePath`].
+#[cfg(feature = "rkyv")]
+#[derive(rkyv::Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck, verify)]
+#[repr(transparent)]
+pub struct ArchivedAbsolutePathBuf(rkyv::string::ArchivedString);
+
+#[cfg(feature = "rkyv")]
+impl ArchivedAbsolutePathBuf {
+    /// Get the archived path as a [`AbsolutePath`].
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        AbsolutePath::ref_cast(Path::new(self.0.as_str()))
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl std::fmt::Debug for ArchivedAbsolutePathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_absolute_path().fmt(f)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+unsafe impl<C> rkyv::bytecheck::Verify<C> for ArchivedAbsolutePathBuf
+where
+    C: rkyv::rancor::Fallible + ?Sized,
+    C::Error: rkyv::rancor::Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        AbsolutePath::try_new(self.0.as_str())
+            .map(|_| ())
+            .map_err(<C::Error as rkyv::rancor::Source>::new)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl rkyv::Archive for AbsolutePathBuf {
+    type Archived = ArchivedAbsolutePathBuf;
+    type Resolver = rkyv::string::StringResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        // SAFETY: `ArchivedAbsolutePathBuf` is a transparent wrapper around an `ArchivedString`.
+        let out = unsafe { out.cast_unchecked::<rkyv::string::ArchivedString>() };
+        rkyv::string::ArchivedString::resolve_from_str(
+            self.0
+                .to_str()
+                .expect("`serialize` checked that the path is utf8"),
+            resolver,
+            out,
+        );
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<S> rkyv::Serialize<S> for AbsolutePathBuf
+where
+    S: rkyv::rancor::Fallible + ?Sized,
+    S::Error: rkyv::rancor::Source,
+    str: rkyv::SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let path = crate::path_to_str(&self.0).map_err(<S::Error as rkyv::rancor::Source>::new)?;
+        rkyv::string::ArchivedString::serialize_from_str(path, serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<D> rkyv::Deserialize<AbsolutePathBuf, D> for ArchivedAbsolutePathBuf
+where
+    D: rkyv::rancor::Fallible + ?Sized,
+{
+    fn deserialize(&self, _: &mut D) -> Result<AbsolutePathBuf, D::Error> {
+        Ok(self.as_absolute_path().into())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl AbsolutePath {
+    /// Get this path as a [`camino::Utf8Path`], or `None` if it is not valid UTF-8.
+    pub fn as_utf8(&self) -> Option<&camino::Utf8Path> {
+        camino::Utf8Path::from_path(&self.0)
+    }
+}
+
+#[cfg(feature = "camino")]
+impl<'a> TryFrom<&'a camino::Utf8Path> for &'a AbsolutePath {
+    type Error = AbsolutePathNewError;
+
+    fn try_from(value: &'a camino::Utf8Path) -> Result<Self, Self::Error> {
+        AbsolutePath::try_new(value.as_std_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<&camino::Utf8Path> for AbsolutePathBuf {
+    type Error = AbsolutePathBufNewError;
+
+    fn try_from(value: &camino::Utf8Path) -> Result<Self, Self::Error> {
+        AbsolutePathBuf::try_new(value.as_std_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<camino::Utf8PathBuf> for AbsolutePathBuf {
+    type Error = AbsolutePathBufNewError;
+
+    fn try_from(value: camino::Utf8PathBuf) -> Result<Self, Self::Error> {
+        AbsolutePathBuf::try_new(value.into_std_path_buf())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<AbsolutePathBuf> for camino::Utf8PathBuf {
+    type Error = camino::FromPathBufError;
+
+    fn try_from(value: AbsolutePathBuf) -> Result<Self, Self::Error> {
+        camino::Utf8PathBuf::try_from(value.0)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::path::Path;
+    use std::path::PathBuf;
 
+    use crate::AbsoluteComponent;
     use crate::AbsoluteJoinError;
     use crate::AbsolutePath;
     use crate::AbsolutePathBuf;
     use crate::AbsolutePathBufNewError;
     use crate::AbsolutePathNewError;
+    use crate::BackupStyle;
+    use crate::InvalidFileName;
     use crate::JoinedAbsolute;
     use crate::NormalizationFailed;
     use crate::NotAbsolute;
+    use crate::RelativePath;
     use crate::RelativePathBuf;
     use crate::WasNotNormalized;
 
@@ -436,6 +1601,17 @@ mod test {
             AbsolutePath::try_new(cwd.join("foo/../../bar.txt").as_path()).unwrap_err()
         );
 
+        let err = WasNotNormalized(cwd.join("foo/../bar.txt").display().to_string());
+        let expected = cwd.join("bar.txt");
+        assert_eq!(Some(AbsolutePathBuf::try_new(&expected)?), err.suggestion());
+        assert!(err
+            .to_string()
+            .ends_with(&format!("(did you mean `{}`?)", expected.display())));
+        let root = cwd.ancestors().last().expect("a root");
+        let err = WasNotNormalized(root.join("..").display().to_string());
+        assert_eq!(None, err.suggestion());
+        assert!(!err.to_string().contains("did you mean"));
+
         Ok(())
     }
 
@@ -504,6 +1680,170 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn path_components() -> anyhow::Result<()> {
+        let cwd = std::env::current_dir()?;
+        let path = AbsolutePathBuf::try_new(&cwd)?.join("foo/bar")?;
+
+        let components: Vec<AbsoluteComponent> = path.components().collect();
+        assert_eq!(cwd.components().count() + 2, components.len());
+        assert!(components.contains(&AbsoluteComponent::RootDir));
+        assert_eq!(
+            vec![AbsoluteComponent::Normal("bar".as_ref())],
+            path.components().rev().take(1).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            path.as_path(),
+            path.components().collect::<PathBuf>().as_path()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn path_starts_and_ends_with() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::try_new(std::env::current_dir()?)?;
+        let path = cwd.join("foo/barbell")?;
+
+        assert!(path.starts_with(&cwd));
+        assert!(path.starts_with(&cwd.join("foo")?));
+        assert!(path.starts_with(&path));
+        assert!(!path.starts_with(&cwd.join("foo/bar")?));
+        assert!(!cwd.starts_with(&path));
+
+        assert!(path.ends_with(RelativePath::try_new("foo/barbell")?));
+        assert!(path.ends_with(RelativePath::try_new("barbell")?));
+        assert!(!path.ends_with(RelativePath::try_new("bell")?));
+        Ok(())
+    }
+
+    #[test]
+    fn path_common_ancestor() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::try_new(std::env::current_dir()?)?;
+        let foo_bar = cwd.join("foo/bar")?;
+        let foo_baz = cwd.join("foo/baz/qux")?;
+        let foo_barbell = cwd.join("foo/barbell")?;
+
+        assert_eq!(
+            Some(cwd.join("foo")?.as_absolute_path()),
+            foo_bar.common_ancestor(&foo_baz)
+        );
+        assert_eq!(
+            Some(cwd.join("foo")?.as_absolute_path()),
+            foo_bar.common_ancestor(&foo_barbell)
+        );
+        assert_eq!(
+            Some(foo_bar.as_absolute_path()),
+            foo_bar.common_ancestor(&foo_bar.join("x")?)
+        );
+        assert_eq!(
+            Some(foo_bar.as_absolute_path()),
+            foo_bar.common_ancestor(&foo_bar)
+        );
+        let root = cwd.ancestors().last().unwrap();
+        assert_eq!(Some(root), root.common_ancestor(&foo_bar));
+        Ok(())
+    }
+
+    #[test]
+    fn path_diverging_components() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let foo_bar_baz = cwd.join("foo/bar/baz")?;
+        let foo_quz = cwd.join("foo/quz")?;
+
+        let (common, ours, theirs) = foo_bar_baz.diverging_components(&foo_quz).unwrap();
+        assert_eq!(cwd.join("foo")?.as_absolute_path(), common);
+        assert_eq!(RelativePath::new_unchecked("bar/baz"), ours);
+        assert_eq!(RelativePath::new_unchecked("quz"), theirs);
+
+        let (common, ours, theirs) = foo_quz.diverging_components(&foo_quz).unwrap();
+        assert_eq!(foo_quz.as_absolute_path(), common);
+        assert!(ours.as_os_str().is_empty());
+        assert!(theirs.as_os_str().is_empty());
+
+        let (common, ours, theirs) = cwd.diverging_components(&foo_quz).unwrap();
+        assert_eq!(cwd.as_absolute_path(), common);
+        assert!(ours.as_os_str().is_empty());
+        assert_eq!(RelativePath::new_unchecked("foo/quz"), theirs);
+        Ok(())
+    }
+
+    #[test]
+    fn path_ancestors() -> anyhow::Result<()> {
+        let cwd = std::env::current_dir()?;
+        let abs_cwd = AbsolutePathBuf::try_new(&cwd)?;
+        let path = abs_cwd.join("foo/bar")?;
+
+        let ancestors: Vec<&AbsolutePath> = path.ancestors().collect();
+        assert_eq!(cwd.ancestors().count() + 2, ancestors.len());
+        assert_eq!(path.as_absolute_path(), ancestors[0]);
+        assert_eq!(abs_cwd.join("foo")?.as_absolute_path(), ancestors[1]);
+        assert_eq!(abs_cwd.as_absolute_path(), ancestors[2]);
+        assert!(ancestors.last().unwrap().parent().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn path_file_name() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let file = cwd.join("foo/bar.txt")?;
+
+        let name = file.file_name().expect("a file name");
+        assert_eq!(RelativePath::new_unchecked("bar.txt"), name);
+        assert_eq!(cwd.join("bar.txt")?, cwd.join_relative(name)?);
+        assert_eq!(None, AbsolutePath::new_unchecked("/").file_name());
+        Ok(())
+    }
+
+    #[test]
+    fn path_strip_prefix() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let file = cwd.join("foo/bar.txt")?;
+
+        let relative = file.strip_prefix(&cwd)?;
+        assert_eq!(RelativePath::try_new("foo/bar.txt")?, relative);
+        assert_eq!(file, cwd.join_relative(relative)?);
+        assert!(cwd.strip_prefix(&cwd)?.as_os_str().is_empty());
+        assert!(cwd.strip_prefix(&file).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn path_buf_push_pop() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let mut path = cwd.clone();
+
+        path.push("foo/./bar")?;
+        assert_eq!(cwd.join("foo/bar")?, path);
+        path.push("../baz")?;
+        assert_eq!(cwd.join("foo/baz")?, path);
+        assert!(path.pop());
+        assert_eq!(cwd.join("foo")?, path);
+
+        assert!(matches!(
+            path.push(cwd.as_path()),
+            Err(AbsoluteJoinError::JoinedAbsolute(_))
+        ));
+        let too_far = "../".repeat(cwd.components().count() + 1);
+        assert!(matches!(
+            path.push(&too_far),
+            Err(AbsoluteJoinError::NormalizationFailed(_))
+        ));
+        assert_eq!(cwd.join("foo")?, path);
+
+        let mut root = AbsolutePathBuf::try_new("/")?;
+        assert!(!root.pop());
+        assert!(root.push("..").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn path_buf_heap_size() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let path = cwd.join("some/file.txt")?;
+        assert!(path.heap_size() >= path.as_os_str().len());
+        Ok(())
+    }
+
     #[test]
     fn path_buf_try_new() -> anyhow::Result<()> {
         let cwd = std::env::current_dir()?;
@@ -682,6 +2022,368 @@ mod test {
             );
         }
 
+        #[cfg(windows)]
+        assert!(matches!(
+            AbsolutePath::new_unchecked(r"C:\foo")
+                .relative_to(AbsolutePath::new_unchecked(r"D:\bar")),
+            Err(crate::RelativeToError::DifferentRoots)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_true_case() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::create_dir_all(root.join("Foo/Bar")?)?;
+        std::fs::create_dir_all(root.join("Foo/bar")?)?;
+        std::fs::write(root.join("Foo/Bar/Baz.TXT")?, "")?;
+
+        assert_eq!(
+            root.join("Foo/Bar/Baz.TXT")?,
+            root.join("foo/Bar/baz.txt")?.true_case()?
+        );
+        assert_eq!(root.join("Foo/bar")?, root.join("FOO/bar")?.true_case()?);
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            root.join("Foo/Quz/baz.txt")?
+                .true_case()
+                .unwrap_err()
+                .kind()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn path_sibling() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let path = cwd.join("foo/bar.txt")?;
+
+        assert_eq!(cwd.join("foo/baz.txt")?, path.sibling("baz.txt")?);
+        assert_eq!(cwd.join("foo/.hidden")?, path.sibling(".hidden")?);
+        for invalid in ["", ".", "..", "a/b", "/abs"] {
+            assert_eq!(
+                InvalidFileName(invalid.to_owned()),
+                path.sibling(invalid).unwrap_err()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn path_suggest_similar() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        for name in [
+            "config.toml",
+            "config.tml",
+            "conf.toml",
+            "cargo.toml",
+            "README.md",
+        ] {
+            std::fs::write(root.join(name)?, "")?;
+        }
+        std::fs::create_dir(root.join("configs.toml")?)?;
+
+        assert_eq!(
+            vec![
+                root.join("conf.toml")?,
+                root.join("config.toml")?,
+                root.join("config.tml")?,
+                root.join("configs.toml")?,
+            ],
+            root.suggest_similar("confg.toml", 2)?
+        );
+        assert_eq!(
+            vec![root.join("config.tml")?, root.join("configs.toml")?],
+            root.suggest_similar("config.toml", 1)?
+        );
+        assert!(root.suggest_similar("main.rs", 2)?.is_empty());
+        assert!(root.join("missing")?.suggest_similar("a", 1).is_err());
+
+        assert_eq!(0, super::edit_distance("", ""));
+        assert_eq!(3, super::edit_distance("", "abc"));
+        assert_eq!(3, super::edit_distance("kitten", "sitting"));
+        assert_eq!(1, super::edit_distance("née", "nee"));
+        Ok(())
+    }
+
+    #[test]
+    fn path_child_with_timestamp() -> anyhow::Result<()> {
+        use std::time::Duration;
+        use std::time::UNIX_EPOCH;
+
+        use crate::absolute::format_timestamp;
+
+        let time = UNIX_EPOCH + Duration::from_millis(1_717_245_000_042);
+        assert_eq!(
+            "2024-06-01T12-30-00Z",
+            format_timestamp("%Y-%m-%dT%H-%M-%SZ", time)?
+        );
+        assert_eq!("20240601.042%", format_timestamp("%Y%m%d.%f%%", time)?);
+        assert_eq!("1970-01-01", format_timestamp("%Y-%m-%d", UNIX_EPOCH)?);
+        assert_eq!(
+            "2000-02-29",
+            format_timestamp("%Y-%m-%d", UNIX_EPOCH + Duration::from_secs(951_782_400))?
+        );
+        assert!(format_timestamp("%Y-%q", time).is_err());
+        assert!(format_timestamp("%", time).is_err());
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let first = root.child_with_timestamp("backup-", "%Y")?;
+        assert_eq!(Some(root.as_absolute_path()), first.parent());
+        assert!(first
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("backup-") && n.len() == 11));
+        std::fs::write(&first, "")?;
+        assert_eq!(
+            root.join(&format!(
+                "{}-1",
+                first.file_name().unwrap().to_string_lossy()
+            ))?,
+            root.child_with_timestamp("backup-", "%Y")?
+        );
+
+        for (prefix, format) in [
+            ("a/", "%Y"),
+            ("", ""),
+            ("a", "%H:%M"),
+            ("..", ""),
+            ("a", "%x"),
+        ] {
+            assert!(root.child_with_timestamp(prefix, format).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn path_with_extension() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let mut path = cwd.join("docs/index.md")?;
+
+        assert_eq!(cwd.join("docs/index.html")?, path.with_extension("html")?);
+        assert_eq!(cwd.join("docs/index")?, path.with_extension("")?);
+        assert_eq!(cwd.join("docs/README")?, path.with_file_name("README")?);
+        assert!(path.with_extension("a/b").is_err());
+        assert!(AbsolutePath::new_unchecked("/")
+            .with_extension("md")
+            .is_err());
+
+        path.set_extension("html")?;
+        assert_eq!(cwd.join("docs/index.html")?, path);
+        path.set_file_name("other.txt")?;
+        assert_eq!(cwd.join("docs/other.txt")?, path);
+        assert!(path.set_file_name("../escape").is_err());
+        assert!(path.set_extension("a/../..").is_err());
+        assert_eq!(cwd.join("docs/other.txt")?, path);
+        Ok(())
+    }
+
+    #[test]
+    fn path_write_with_backup() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = root.join("file.txt")?;
+
+        assert_eq!(None, path.write_with_backup("1", BackupStyle::Simple)?);
+        assert_eq!(
+            Some(root.join("file.txt.bak")?),
+            path.write_with_backup("2", BackupStyle::Simple)?
+        );
+        assert_eq!(
+            Some(root.join("file.txt.bak")?),
+            path.write_with_backup("3", BackupStyle::Simple)?
+        );
+        assert_eq!("2", std::fs::read_to_string(root.join("file.txt.bak")?)?);
+
+        assert_eq!(
+            Some(root.join("file.txt.~1~")?),
+            path.write_with_backup("4", BackupStyle::Numbered)?
+        );
+        std::fs::write(root.join("file.txt.~9~")?, "")?;
+        assert_eq!(
+            Some(root.join("file.txt.~10~")?),
+            path.write_with_backup("5", BackupStyle::Numbered)?
+        );
+        assert_eq!("5", std::fs::read_to_string(&path)?);
+        assert_eq!("3", std::fs::read_to_string(root.join("file.txt.~1~")?)?);
+        assert_eq!("4", std::fs::read_to_string(root.join("file.txt.~10~")?)?);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640))?;
+            let backup = path
+                .write_with_backup("6", BackupStyle::Simple)?
+                .expect("the file exists");
+            assert_eq!(
+                0o640,
+                std::fs::metadata(&path)?.permissions().mode() & 0o777
+            );
+            assert_eq!(
+                0o640,
+                std::fs::metadata(&backup)?.permissions().mode() & 0o777
+            );
+            assert_eq!("5", std::fs::read_to_string(&backup)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cow_path() -> anyhow::Result<()> {
+        use std::borrow::Cow;
+        use std::collections::HashSet;
+
+        use crate::CowAbsolutePath;
+
+        let cwd = AbsolutePathBuf::current_dir();
+        let normalized = cwd.join("foo/bar")?;
+
+        let borrowed = AbsolutePath::try_new_normalized(normalized.as_path())?;
+        assert!(matches!(borrowed, Cow::Borrowed(_)));
+        assert_eq!(normalized.as_absolute_path(), &*borrowed);
+
+        let with_dots = cwd.as_path().join("foo/./baz/../bar");
+        let owned = AbsolutePath::try_new_normalized(&with_dots)?;
+        assert!(matches!(owned, Cow::Owned(_)));
+        assert_eq!(borrowed, owned);
+        assert_eq!(Some(RelativePath::new_unchecked("bar")), owned.file_name());
+        assert_eq!(normalized, owned.into_owned());
+
+        assert!(AbsolutePath::try_new_normalized("foo/bar").is_err());
+
+        let from_buf: CowAbsolutePath = normalized.clone().into();
+        let from_ref: CowAbsolutePath = (&normalized).into();
+        assert_eq!(from_buf, from_ref);
+        assert_eq!(normalized, from_ref.into_owned());
+
+        let set: HashSet<AbsolutePathBuf> = HashSet::from([normalized.clone()]);
+        assert!(set.contains(normalized.as_absolute_path()));
+        Ok(())
+    }
+
+    #[test]
+    fn path_freshness() -> anyhow::Result<()> {
+        use std::time::Duration;
+        use std::time::SystemTime;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let input = root.join("src/nested/input.txt")?;
+        let other_input = root.join("src/other.txt")?;
+        let output = root.join("out.txt")?;
+        input.ensure_parent_exists()?;
+        for path in [&input, &other_input, &output] {
+            std::fs::write(path, "")?;
+        }
+
+        // In the future, so that the files are newer than the directories that contain them.
+        let base = SystemTime::now() + Duration::from_secs(3600);
+        let set_mtime = |path: &AbsolutePathBuf, offset: u64| -> anyhow::Result<()> {
+            std::fs::File::options()
+                .write(true)
+                .open(path)?
+                .set_modified(base + Duration::from_secs(offset))?;
+            Ok(())
+        };
+        set_mtime(&input, 10)?;
+        set_mtime(&other_input, 5)?;
+        set_mtime(&output, 20)?;
+
+        assert!(output.is_newer_than(&input)?);
+        assert!(!input.is_newer_than(&output)?);
+        assert!(!input.is_newer_than(&input)?);
+        assert!(input.is_newer_than(&root.join("missing")?).is_err());
+
+        assert_eq!(
+            (input.clone(), base + Duration::from_secs(10)),
+            root.join("src")?.newest_in_tree()?
+        );
+        assert_eq!(
+            (other_input.clone(), base + Duration::from_secs(5)),
+            other_input.newest_in_tree()?
+        );
+        set_mtime(&other_input, 30)?;
+        assert_eq!(other_input, root.join("src")?.newest_in_tree()?.0);
+        assert!(root.join("missing")?.newest_in_tree().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn path_disk_space() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let file = root.join("file.txt")?;
+        std::fs::write(&file, "file")?;
+
+        let total = root.total_space()?;
+        assert!(total > 0);
+        assert!(root.available_space()? <= total);
+        assert_eq!(total, file.total_space()?);
+        assert!(root.join("missing")?.available_space().is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "filetime")]
+    #[test]
+    fn path_set_times() -> anyhow::Result<()> {
+        use std::time::Duration;
+        use std::time::SystemTime;
+
+        let temp = tempfile::tempdir()?;
+        let path = AbsolutePathBuf::try_new(temp.path().join("foo.txt"))?;
+        let past = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+
+        path.touch()?;
+        assert!(path.is_file());
+        path.set_mtime(past)?;
+        path.set_atime(past)?;
+        assert_eq!(past, std::fs::metadata(&path)?.modified()?);
+        assert_eq!(past, std::fs::metadata(&path)?.accessed()?);
+
+        std::fs::write(&path, "contents")?;
+        path.set_mtime(past)?;
+        path.touch()?;
+        assert!(std::fs::metadata(&path)?.modified()? > past);
+        assert_eq!("contents", std::fs::read_to_string(&path)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "short-names")]
+    #[test]
+    fn path_expand_short_names() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        assert_eq!(cwd, cwd.expand_short_names()?);
+        Ok(())
+    }
+
+    #[cfg(feature = "junctions")]
+    #[test]
+    fn path_junctions() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let target = root.join("target")?;
+        let link = root.join("link")?;
+        std::fs::create_dir(&target)?;
+        assert!(!target.is_reparse_point());
+        assert!(!target.is_junction());
+
+        if cfg!(windows) {
+            link.create_junction(&target)?;
+            std::fs::write(target.join("file.txt")?, "contents")?;
+            assert!(link.is_reparse_point());
+            assert!(link.is_junction());
+            assert_eq!("contents", std::fs::read_to_string(link.join("file.txt")?)?);
+        } else {
+            assert_eq!(
+                std::io::ErrorKind::Unsupported,
+                link.create_junction(&target).unwrap_err().kind()
+            );
+        }
         Ok(())
     }
 }
@@ -726,6 +2428,19 @@ mod serde_tests {
         assert!(serde_json::from_str::<AbsolutePathBuf>(&serialized_traversal).is_err());
         Ok(())
     }
+
+    #[test]
+    fn path_deserializes_borrowed() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::try_new(std::env::current_dir()?)?.join("foo")?;
+        let serialized = serde_json::to_string(&vec![&cwd, &cwd])?;
+
+        let paths: Vec<&AbsolutePath> = serde_json::from_str(&serialized)?;
+        assert_eq!(vec![cwd.as_absolute_path(); 2], paths);
+        assert!(serde_json::from_str::<&AbsolutePath>("\"foo/bar\"").is_err());
+        let unnormalized = format!("\"{}/../bar\"", cwd.display());
+        assert!(serde_json::from_str::<&AbsolutePath>(&unnormalized).is_err());
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "diesel"))]
@@ -890,4 +2605,381 @@ mod test_diesel {
 
         Ok(())
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut connection = create_table()?;
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let file = TestFile {
+            id: 1,
+            x: AbsolutePathBuf::current_dir().join(name)?,
+            y: None,
+        };
+        assert!(
+            diesel::insert_into(crate::diesel_helpers::schema::test_files::table)
+                .values(&file)
+                .execute(&mut connection)
+                .is_err()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlx-sqlite", feature = "sqlx-runtime-tokio"))]
+mod test_sqlx {
+    use crate::block_on;
+    use crate::sqlx_helpers::create_table;
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        block_on(async {
+            let mut connection = create_table().await?;
+            let x = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+            let y = AbsolutePathBuf::current_dir().join("bar/baz.txt")?;
+            for (id, y) in [(1, None), (2, Some(&y))] {
+                sqlx::query("INSERT INTO test_files (id, x, y) VALUES (?, ?, ?)")
+                    .bind(id)
+                    .bind(&x)
+                    .bind(y)
+                    .execute(&mut connection)
+                    .await?;
+            }
+            sqlx::query("INSERT INTO test_files (id, x, y) VALUES (3, ?, NULL)")
+                .bind("foo/bar.txt")
+                .execute(&mut connection)
+                .await?;
+
+            let rows: Vec<(AbsolutePathBuf, Option<AbsolutePathBuf>)> =
+                sqlx::query_as("SELECT x, y FROM test_files WHERE id < 3 ORDER BY id")
+                    .fetch_all(&mut connection)
+                    .await?;
+            assert_eq!(vec![(x.clone(), None), (x, Some(y))], rows);
+            assert!(sqlx::query_as::<_, (AbsolutePathBuf,)>(
+                "SELECT x FROM test_files WHERE id = 3"
+            )
+            .fetch_one(&mut connection)
+            .await
+            .is_err());
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        block_on(async {
+            let mut connection = create_table().await?;
+            let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+            let path = AbsolutePathBuf::current_dir().join(name)?;
+            assert!(sqlx::query("INSERT INTO test_files (id, x) VALUES (1, ?)")
+                .bind(&path)
+                .execute(&mut connection)
+                .await
+                .is_err());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sea-orm"))]
+mod test_sea_orm {
+    use std::collections::BTreeMap;
+
+    use sea_orm::sea_query::Value;
+    use sea_orm::sea_query::ValueType;
+    use sea_orm::ConnectionTrait;
+    use sea_orm::DbBackend;
+    use sea_orm::MockDatabase;
+    use sea_orm::Statement;
+
+    use crate::block_on;
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+        let value = Value::try_from(path.clone())?;
+        assert_eq!(Value::from(path.display().to_string()), value);
+        assert_eq!(
+            Some(path.clone()),
+            <AbsolutePathBuf as ValueType>::try_from(value.clone()).ok()
+        );
+        assert!(<AbsolutePathBuf as ValueType>::try_from(Value::from("foo/bar.txt")).is_err());
+
+        block_on(async {
+            let db = MockDatabase::new(DbBackend::Sqlite)
+                .append_query_results([[
+                    BTreeMap::from([("x", value.clone()), ("y", Value::String(None))]),
+                    BTreeMap::from([("x", Value::from("foo/bar.txt")), ("y", value)]),
+                ]])
+                .into_connection();
+            let rows = db
+                .query_all(Statement::from_string(
+                    DbBackend::Sqlite,
+                    "SELECT x, y FROM test_files".to_owned(),
+                ))
+                .await?;
+
+            assert_eq!(path, rows[0].try_get::<AbsolutePathBuf>("", "x")?);
+            assert_eq!(None, rows[0].try_get::<Option<AbsolutePathBuf>>("", "y")?);
+            assert!(rows[1].try_get::<AbsolutePathBuf>("", "x").is_err());
+            assert_eq!(
+                Some(path),
+                rows[1].try_get::<Option<AbsolutePathBuf>>("", "y")?
+            );
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = AbsolutePathBuf::current_dir().join(name)?;
+        assert!(Value::try_from(path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rusqlite"))]
+mod test_rusqlite {
+    use rusqlite::params;
+
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let connection = rusqlite::Connection::open_in_memory()?;
+        connection.execute(
+            "CREATE TABLE test_files (id PRIMARY KEY NOT NULL, x TEXT NOT NULL, y TEXT NULL)",
+            [],
+        )?;
+        let x = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+        let y = AbsolutePathBuf::current_dir().join("bar/baz.txt")?;
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (?1, ?2, ?3)",
+            params![1, x.as_absolute_path(), None::<AbsolutePathBuf>],
+        )?;
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (?1, ?2, ?3)",
+            params![2, x, y],
+        )?;
+
+        let mut statement = connection.prepare("SELECT x, y FROM test_files ORDER BY id")?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(AbsolutePathBuf, Option<AbsolutePathBuf>)>>>()?;
+        assert_eq!(vec![(x.clone(), None), (x, Some(y))], rows);
+
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (3, ?1, NULL)",
+            ["foo/bar.txt"],
+        )?;
+        assert!(connection
+            .query_row("SELECT x FROM test_files WHERE id = 3", [], |row| {
+                row.get::<_, AbsolutePathBuf>(0)
+            })
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        use rusqlite::ToSql;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = AbsolutePathBuf::current_dir().join(name)?;
+        assert!(matches!(
+            path.to_sql(),
+            Err(rusqlite::Error::ToSqlConversionFailure(_))
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod test_postgres {
+    use bytes::BytesMut;
+    use postgres_types::FromSql;
+    use postgres_types::ToSql;
+    use postgres_types::Type;
+
+    use crate::AbsolutePathBuf;
+    use crate::NotUtf8;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+        let mut buf = BytesMut::new();
+        path.to_sql_checked(&Type::TEXT, &mut buf).unwrap();
+        assert_eq!(path, AbsolutePathBuf::from_sql(&Type::TEXT, &buf).unwrap());
+        assert!(AbsolutePathBuf::from_sql(&Type::TEXT, b"foo/bar.txt").is_err());
+        assert!(<AbsolutePathBuf as FromSql>::accepts(&Type::VARCHAR));
+        assert!(path.to_sql_checked(&Type::INT4, &mut buf).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = AbsolutePathBuf::current_dir().join(name)?;
+        assert!(matches!(
+            path.to_sql_checked(&Type::TEXT, &mut BytesMut::new()),
+            Err(e) if e.is::<NotUtf8>()
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "schemars"))]
+mod test_schemars {
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn path_buf_schema() -> anyhow::Result<()> {
+        let schema = serde_json::to_value(schemars::schema_for!(AbsolutePathBuf))?;
+        assert_eq!("string", schema["type"]);
+        assert_eq!("absolute-path", schema["format"]);
+        assert!(schema["description"].is_string());
+
+        // Fields are inlined rather than referring to a definition.
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Config {
+            root: AbsolutePathBuf,
+            cache: Option<AbsolutePathBuf>,
+        }
+        let schema = serde_json::to_value(schemars::schema_for!(Config))?;
+        assert_eq!("absolute-path", schema["properties"]["root"]["format"]);
+        assert!(schema.get("definitions").is_none());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod test_arbitrary {
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn generates_valid_paths() -> anyhow::Result<()> {
+        let paths: Vec<AbsolutePathBuf> = crate::fuzzing::generate(200)?;
+        for path in &paths {
+            assert_eq!(path, &AbsolutePathBuf::try_new(path.as_path())?);
+        }
+        assert!(paths.iter().any(|p| p.as_path().components().count() > 2));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rkyv"))]
+mod test_rkyv {
+    use rkyv::rancor::Error;
+
+    use crate::AbsolutePathBuf;
+    use crate::ArchivedAbsolutePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+        let bytes = rkyv::to_bytes::<Error>(&path)?;
+        let archived = rkyv::access::<ArchivedAbsolutePathBuf, Error>(&bytes)?;
+        assert_eq!(&*path, archived.as_absolute_path());
+        assert_eq!(path, rkyv::deserialize::<AbsolutePathBuf, Error>(archived)?);
+
+        let invalid = rkyv::to_bytes::<Error>(&"foo/bar.txt".to_owned())?;
+        assert!(rkyv::access::<ArchivedAbsolutePathBuf, Error>(&invalid).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = AbsolutePathBuf::current_dir().join(name)?;
+        assert!(rkyv::to_bytes::<Error>(&path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "camino"))]
+mod test_camino {
+    use camino::Utf8Path;
+    use camino::Utf8PathBuf;
+
+    use crate::AbsolutePath;
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn converts_utf8_paths() -> anyhow::Result<()> {
+        let path = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+        let utf8 = path.as_utf8().expect("the path is utf8");
+        assert_eq!(path.as_path(), utf8.as_std_path());
+
+        assert_eq!(path.as_absolute_path(), <&AbsolutePath>::try_from(utf8)?);
+        assert_eq!(path, AbsolutePathBuf::try_from(utf8)?);
+        assert_eq!(path, AbsolutePathBuf::try_from(utf8.to_path_buf())?);
+        assert_eq!(utf8, Utf8PathBuf::try_from(path.clone())?);
+
+        assert!(<&AbsolutePath>::try_from(Utf8Path::new("foo/bar.txt")).is_err());
+        assert!(AbsolutePathBuf::try_from(Utf8Path::new("foo/bar.txt")).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8_paths() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"caf\xe9.txt");
+        let path = AbsolutePathBuf::current_dir().join(name)?;
+        assert_eq!(None, path.as_utf8());
+        assert!(Utf8PathBuf::try_from(path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "acl"))]
+mod test_acl {
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn reports_effective_access() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let file = root.join("file.txt")?;
+        std::fs::write(&file, "")?;
+
+        let access = root.effective_access()?;
+        assert!(access.read && access.write && access.execute);
+        let access = file.effective_access()?;
+        assert!(access.read && access.write);
+        #[cfg(unix)]
+        assert!(!access.execute);
+
+        let mut permissions = std::fs::metadata(&file)?.permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&file, permissions)?;
+        // Root can write to anything on unix.
+        #[cfg(windows)]
+        assert!(!file.effective_access()?.write);
+
+        assert!(root.join("missing.txt")?.effective_access().is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use crate::AbsolutePathBuf;
+use crate::AliasCycle;
+use crate::AliasResolutionError;
+
+/// Resolves import specifiers like `@app/components/button` or `$lib/util` using configured
+/// alias prefixes, as done by bundlers and similar tools.
+///
+/// An alias matches a specifier that equals it, or that continues with a `/` after it. If
+/// several aliases match, the longest one wins. An alias's target may itself start with another
+/// alias, but every chain of aliases must end in an absolute path.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AliasMap {
+    aliases: BTreeMap<String, String>,
+}
+
+impl AliasMap {
+    /// Create a map with no aliases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an alias from `alias` to `target`, replacing any existing alias with the same name.
+    ///
+    /// Trailing slashes are ignored, except that an alias of only slashes becomes `/`, rather
+    /// than an empty alias that matches every absolute specifier. An empty alias is ignored.
+    pub fn alias(mut self, alias: &str, target: &str) -> Self {
+        if alias.is_empty() {
+            return self;
+        }
+        let alias = match alias.trim_end_matches('/') {
+            "" => "/",
+            trimmed => trimmed,
+        };
+        self.aliases.insert(alias.to_owned(), target.to_owned());
+        self
+    }
+
+    /// Resolve `specifier` to an absolute path.
+    ///
+    /// Returns `None` if `specifier` does not start with any alias, or an error if the aliases
+    /// form a cycle, or do not resolve to a valid absolute path.
+    pub fn resolve(
+        &self,
+        specifier: &str,
+    ) -> Result<Option<AbsolutePathBuf>, AliasResolutionError> {
+        let mut current = specifier.to_owned();
+        let mut chain: Vec<String> = vec![];
+        while let Some((alias, target)) = self.longest_match(&current) {
+            if chain.iter().any(|a| a == alias) {
+                chain.push(alias.to_owned());
+                return Err(AliasCycle(chain).into());
+            }
+            chain.push(alias.to_owned());
+            current = format!("{}{}", target, &current[alias.len()..]);
+        }
+        if chain.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(AbsolutePathBuf::try_new(current)?))
+    }
+
+    fn longest_match(&self, specifier: &str) -> Option<(&str, &str)> {
+        self.aliases
+            .iter()
+            .filter(|(alias, _)| match specifier.strip_prefix(alias.as_str()) {
+                Some(rest) => rest.is_empty() || rest.starts_with('/'),
+                None => false,
+            })
+            .max_by_key(|(alias, _)| alias.len())
+            .map(|(alias, target)| (alias.as_str(), target.as_str()))
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::AliasCycle;
+    use crate::AliasMap;
+    use crate::AliasResolutionError;
+
+    #[test]
+    fn resolves_aliases() -> anyhow::Result<()> {
+        let aliases = AliasMap::new()
+            .alias("@app", "/src/app")
+            .alias("@app/ui/", "/src/design-system")
+            .alias("$lib", "@app/lib")
+            .alias("~", "$lib/../..");
+        let resolve = |s: &str| aliases.resolve(s);
+
+        assert_eq!(
+            Some(AbsolutePathBuf::try_new("/src/app/main.ts")?),
+            resolve("@app/main.ts")?
+        );
+        assert_eq!(
+            Some(AbsolutePathBuf::try_new("/src/app")?),
+            resolve("@app")?
+        );
+        assert_eq!(
+            Some(AbsolutePathBuf::try_new("/src/design-system/button.ts")?),
+            resolve("@app/ui/button.ts")?
+        );
+        assert_eq!(
+            Some(AbsolutePathBuf::try_new("/src/app/lib/util.ts")?),
+            resolve("$lib/util.ts")?
+        );
+        assert_eq!(
+            Some(AbsolutePathBuf::try_new("/src/README.md")?),
+            resolve("~/README.md")?
+        );
+        assert_eq!(None, resolve("@application/main.ts")?);
+        assert_eq!(None, resolve("./main.ts")?);
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_add_empty_aliases() -> anyhow::Result<()> {
+        let aliases = AliasMap::new().alias("", "/empty").alias("//", "/root");
+
+        assert_eq!(
+            Some(AbsolutePathBuf::try_new("/root")?),
+            aliases.resolve("/")?
+        );
+        assert_eq!(None, aliases.resolve("/src/main.ts")?);
+        assert_eq!(None, aliases.resolve("main.ts")?);
+        Ok(())
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let aliases = AliasMap::new()
+            .alias("@a", "@b/a")
+            .alias("@b", "@c/b")
+            .alias("@c", "@a/c")
+            .alias("@relative", "src/app");
+
+        assert_eq!(
+            Err(AliasResolutionError::AliasCycle(AliasCycle(vec![
+                "@a".to_owned(),
+                "@b".to_owned(),
+                "@c".to_owned(),
+                "@a".to_owned(),
+            ]))),
+            aliases.resolve("@a/foo")
+        );
+        assert!(matches!(
+            aliases.resolve("@relative/foo"),
+            Err(AliasResolutionError::NotAbsolute(_))
+        ));
+    }
+}
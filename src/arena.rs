@@ -0,0 +1,128 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::RelativePath;
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// A bump allocator for paths that are only needed for a while, e.g. while building a large
+/// dependency graph.
+///
+/// Copying a path into the arena is a single copy into a large shared buffer instead of a heap
+/// allocation per path. Everything is freed at once when the arena is dropped.
+#[derive(Debug, Default)]
+pub struct PathArena {
+    chunks: RefCell<Vec<Chunk>>,
+    /// How many bytes of the last chunk are in use.
+    used: Cell<usize>,
+}
+
+/// A fixed size buffer that is never moved or resized, so references into it stay valid.
+#[derive(Debug)]
+struct Chunk {
+    ptr: *mut u8,
+    capacity: usize,
+}
+
+impl Chunk {
+    fn new(capacity: usize) -> Self {
+        let ptr = Box::into_raw(vec![0u8; capacity].into_boxed_slice()) as *mut u8;
+        Self { ptr, capacity }
+    }
+}
+
+// SAFETY: Each chunk exclusively owns its buffer, like a `Box<[u8]>` does.
+unsafe impl Send for Chunk {}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `capacity` came from a boxed slice in `Chunk::new`.
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.ptr, self.capacity)) });
+    }
+}
+
+impl PathArena {
+    /// Create an empty arena.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy `path` into the arena.
+    pub fn alloc_absolute(&self, path: &AbsolutePath) -> &AbsolutePath {
+        AbsolutePath::new_unchecked(self.alloc_path(path))
+    }
+
+    /// Copy `path` into the arena.
+    pub fn alloc_relative(&self, path: &RelativePath) -> &RelativePath {
+        RelativePath::new_unchecked(self.alloc_path(path))
+    }
+
+    /// The total number of bytes reserved by the arena.
+    pub fn capacity(&self) -> usize {
+        self.chunks.borrow().iter().map(|c| c.capacity).sum()
+    }
+
+    fn alloc_path(&self, path: &Path) -> &Path {
+        let bytes = self.alloc_bytes(path.as_os_str().as_encoded_bytes());
+        // SAFETY: The bytes were copied unchanged from an `OsStr`.
+        Path::new(unsafe { OsStr::from_encoded_bytes_unchecked(bytes) })
+    }
+
+    fn alloc_bytes(&self, bytes: &[u8]) -> &[u8] {
+        let mut chunks = self.chunks.borrow_mut();
+        let fits = chunks
+            .last()
+            .is_some_and(|c| c.capacity - self.used.get() >= bytes.len());
+        if !fits {
+            chunks.push(Chunk::new(CHUNK_SIZE.max(bytes.len())));
+            self.used.set(0);
+        }
+        let chunk = chunks.last().expect("a chunk was just added");
+        let start = self.used.get();
+        self.used.set(start + bytes.len());
+        // SAFETY: `start..start + bytes.len()` is within the chunk, and has not been handed out
+        // before. Chunks are only freed when the arena is dropped, so the returned slice lives as
+        // long as `self`, and the region is never written to again.
+        unsafe {
+            let dest = chunk.ptr.add(start);
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), dest, bytes.len());
+            std::slice::from_raw_parts(dest, bytes.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::PathArena;
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn allocates_paths() -> anyhow::Result<()> {
+        let arena = PathArena::new();
+        let root = AbsolutePathBuf::current_dir();
+        let first = arena.alloc_absolute(&root);
+        let relative = arena.alloc_relative(RelativePath::try_new("foo/bar.txt")?);
+
+        let mut allocated = vec![];
+        for i in 0..5000 {
+            let path = RelativePathBuf::try_new(format!("dir/file_{}.rs", i))?;
+            allocated.push((arena.alloc_relative(&path), path));
+        }
+        let long = RelativePathBuf::try_new("a/".repeat(20_000))?;
+        let long_allocated = arena.alloc_relative(&long);
+
+        assert_eq!(root.as_absolute_path(), first);
+        assert_eq!(RelativePath::try_new("foo/bar.txt")?, relative);
+        for (in_arena, original) in allocated {
+            assert_eq!(original.as_relative_path(), in_arena);
+        }
+        assert_eq!(long.as_relative_path(), long_allocated);
+        assert!(arena.capacity() >= 40_000 + 5000 * "dir/file_0.rs".len());
+        Ok(())
+    }
+}
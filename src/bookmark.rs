@@ -0,0 +1,245 @@
+use std::ffi::OsStr;
+use std::ops::Deref;
+use std::os::unix::ffi::OsStrExt;
+
+use core_foundation_sys::base::kCFAllocatorDefault;
+use core_foundation_sys::base::CFIndex;
+use core_foundation_sys::base::CFRelease;
+use core_foundation_sys::base::CFTypeRef;
+use core_foundation_sys::data::CFDataCreate;
+use core_foundation_sys::data::CFDataRef;
+use core_foundation_sys::error::CFErrorGetCode;
+use core_foundation_sys::error::CFErrorRef;
+use core_foundation_sys::url::kCFURLBookmarkResolutionWithSecurityScope;
+use core_foundation_sys::url::kCFURLBookmarkResolutionWithoutUIMask;
+use core_foundation_sys::url::CFURLBookmarkResolutionOptions;
+use core_foundation_sys::url::CFURLCreateBookmarkDataFromFile;
+use core_foundation_sys::url::CFURLCreateByResolvingBookmarkData;
+use core_foundation_sys::url::CFURLCreateFromFileSystemRepresentation;
+use core_foundation_sys::url::CFURLGetFileSystemRepresentation;
+use core_foundation_sys::url::CFURLRef;
+use core_foundation_sys::url::CFURLStartAccessingSecurityScopedResource;
+use core_foundation_sys::url::CFURLStopAccessingSecurityScopedResource;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// The file that a Finder alias or a bookmark refers to, e.g. as received by a GUI app through
+/// drag and drop.
+///
+/// Security-scoped bookmarks from [`Bookmark::resolve_security_scoped`] keep access to the file
+/// until this is dropped.
+#[derive(Debug)]
+pub struct Bookmark {
+    path: AbsolutePathBuf,
+    stale: bool,
+    /// Set if security-scoped access was started, and must be stopped on drop.
+    scoped_url: Option<CFURLRef>,
+}
+
+impl Bookmark {
+    /// Resolve bookmark data, e.g. from `NSURL.bookmarkData`, without showing any UI or mounting
+    /// volumes.
+    pub fn resolve(data: &[u8]) -> std::io::Result<Self> {
+        let data = create_data(data)?;
+        let _data = CfOwned(data.cast());
+        Self::resolve_unscoped(data)
+    }
+
+    /// Resolve a security-scoped bookmark, as created by sandboxed apps, and start accessing it.
+    ///
+    /// Access lasts until this is dropped.
+    pub fn resolve_security_scoped(data: &[u8]) -> std::io::Result<Self> {
+        let data = create_data(data)?;
+        let _data = CfOwned(data.cast());
+        let (url, stale) = resolve_data(
+            data,
+            kCFURLBookmarkResolutionWithoutUIMask | kCFURLBookmarkResolutionWithSecurityScope,
+        )?;
+        let path = match url_path(url) {
+            Ok(path) => path,
+            Err(e) => {
+                // SAFETY: `url` was created above, and is not used again.
+                unsafe { CFRelease(url.cast()) };
+                return Err(e);
+            }
+        };
+        // SAFETY: `url` is a valid url.
+        let started = unsafe { CFURLStartAccessingSecurityScopedResource(url) } != 0;
+        if !started {
+            // SAFETY: `url` was created above, and is not used again. It is only kept, and
+            // released on drop, while it is being accessed.
+            unsafe { CFRelease(url.cast()) };
+        }
+        Ok(Self {
+            path,
+            stale,
+            scoped_url: started.then_some(url),
+        })
+    }
+
+    /// Resolve the Finder alias file at `alias`.
+    pub fn from_alias_file(alias: &AbsolutePath) -> std::io::Result<Self> {
+        let bytes = alias.as_os_str().as_bytes();
+        // SAFETY: `bytes` is valid for its length.
+        let alias_url = unsafe {
+            CFURLCreateFromFileSystemRepresentation(
+                kCFAllocatorDefault,
+                bytes.as_ptr(),
+                bytes.len() as CFIndex,
+                0,
+            )
+        };
+        if alias_url.is_null() {
+            return Err(invalid_data("could not create a url for the alias"));
+        }
+        let _alias_url = CfOwned(alias_url.cast());
+        let mut error: CFErrorRef = std::ptr::null_mut();
+        // SAFETY: `alias_url` is a valid url, and `error` is a valid out pointer.
+        let data =
+            unsafe { CFURLCreateBookmarkDataFromFile(kCFAllocatorDefault, alias_url, &mut error) };
+        if data.is_null() {
+            return Err(take_error(error, "read alias"));
+        }
+        let _data = CfOwned(data.cast());
+        Self::resolve_unscoped(data)
+    }
+
+    fn resolve_unscoped(data: CFDataRef) -> std::io::Result<Self> {
+        let (url, stale) = resolve_data(data, kCFURLBookmarkResolutionWithoutUIMask)?;
+        let _url = CfOwned(url.cast());
+        Ok(Self {
+            path: url_path(url)?,
+            stale,
+            scoped_url: None,
+        })
+    }
+
+    /// The path that the bookmark refers to.
+    pub fn path(&self) -> &AbsolutePath {
+        &self.path
+    }
+
+    /// Whether the bookmark data is out of date, e.g. because the file was moved, and should be
+    /// recreated from [`Bookmark::path`].
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+}
+
+impl Deref for Bookmark {
+    type Target = AbsolutePath;
+
+    fn deref(&self) -> &Self::Target {
+        &self.path
+    }
+}
+
+impl Drop for Bookmark {
+    fn drop(&mut self) {
+        if let Some(url) = self.scoped_url.take() {
+            // SAFETY: Access to `url` was started when it was resolved, and it is not used again.
+            unsafe {
+                CFURLStopAccessingSecurityScopedResource(url);
+                CFRelease(url.cast());
+            }
+        }
+    }
+}
+
+/// Releases a Core Foundation object when dropped.
+struct CfOwned(CFTypeRef);
+
+impl Drop for CfOwned {
+    fn drop(&mut self) {
+        // SAFETY: This owns the object.
+        unsafe { CFRelease(self.0) }
+    }
+}
+
+fn create_data(data: &[u8]) -> std::io::Result<CFDataRef> {
+    // SAFETY: `data` is valid for its length, and is copied.
+    let data = unsafe { CFDataCreate(kCFAllocatorDefault, data.as_ptr(), data.len() as CFIndex) };
+    if data.is_null() {
+        Err(invalid_data("could not copy the bookmark data"))
+    } else {
+        Ok(data)
+    }
+}
+
+/// Resolve `data` to a url that the caller must release, and whether it was stale.
+fn resolve_data(
+    data: CFDataRef,
+    options: CFURLBookmarkResolutionOptions,
+) -> std::io::Result<(CFURLRef, bool)> {
+    let mut stale = 0;
+    let mut error: CFErrorRef = std::ptr::null_mut();
+    // SAFETY: `data` is valid, and `stale` and `error` are valid out pointers.
+    let url = unsafe {
+        CFURLCreateByResolvingBookmarkData(
+            kCFAllocatorDefault,
+            data,
+            options,
+            std::ptr::null(),
+            std::ptr::null(),
+            &mut stale,
+            &mut error,
+        )
+    };
+    if url.is_null() {
+        Err(take_error(error, "resolve bookmark"))
+    } else {
+        Ok((url, stale != 0))
+    }
+}
+
+fn url_path(url: CFURLRef) -> std::io::Result<AbsolutePathBuf> {
+    let mut buffer = vec![0u8; libc::PATH_MAX as usize];
+    // SAFETY: `url` is valid, and `buffer` is valid for its length.
+    let ok = unsafe {
+        CFURLGetFileSystemRepresentation(url, 1, buffer.as_mut_ptr(), buffer.len() as CFIndex)
+    };
+    if ok == 0 {
+        return Err(invalid_data("the bookmark does not refer to a file"));
+    }
+    let len = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    AbsolutePathBuf::try_new(OsStr::from_bytes(&buffer[..len]))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Convert (and release) a Core Foundation error.
+fn take_error(error: CFErrorRef, operation: &str) -> std::io::Error {
+    if error.is_null() {
+        return invalid_data(&format!("failed to {}", operation));
+    }
+    // SAFETY: `error` was returned to us, and is not used again.
+    let code = unsafe {
+        let code = CFErrorGetCode(error);
+        CFRelease(error.cast());
+        code
+    };
+    invalid_data(&format!("failed to {} (error {})", operation, code))
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::Bookmark;
+
+    #[test]
+    fn rejects_invalid_bookmarks() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let file = root.join("file.txt")?;
+        std::fs::write(&file, "not an alias")?;
+
+        assert!(Bookmark::resolve(b"not a bookmark").is_err());
+        assert!(Bookmark::resolve_security_scoped(b"").is_err());
+        assert!(Bookmark::from_alias_file(&file).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use crate::fs::Mode;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    size: u64,
+    /// When this entry was last inserted or touched, in ticks of `CacheDir::clock`.
+    last_used: u64,
+}
+
+/// A directory of cached files that is kept under a size limit by removing the least recently
+/// used files, e.g. for build caches.
+///
+/// Files are keyed by their path relative to the cache's root. Files that are already in the
+/// directory when it is opened are tracked too, treating the least recently modified as the
+/// least recently used. Only the sizes of the files themselves are counted, not the
+/// directories that contain them.
+#[derive(Debug)]
+pub struct CacheDir {
+    root: AbsolutePathBuf,
+    max_bytes: u64,
+    entries: HashMap<RelativePathBuf, Entry>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+impl CacheDir {
+    /// Open the cache at `root`, creating it if it does not exist, that should use at most
+    /// `max_bytes` once [`CacheDir::evict_to_fit`] is called.
+    pub fn new(root: &AbsolutePath, max_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(root)?;
+        let mut files = vec![];
+        let mut to_visit = vec![AbsolutePathBuf::from(root)];
+        while let Some(dir) = to_visit.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                let path = AbsolutePathBuf::new_unchecked(entry.path());
+                if metadata.is_dir() {
+                    to_visit.push(path);
+                } else {
+                    files.push((metadata.modified()?, metadata.len(), path));
+                }
+            }
+        }
+        files.sort();
+
+        let mut cache = Self {
+            root: root.into(),
+            max_bytes,
+            entries: HashMap::with_capacity(files.len()),
+            total_bytes: 0,
+            clock: 0,
+        };
+        for (_, size, path) in files {
+            let key = RelativePathBuf::from(
+                path.strip_prefix(root)
+                    .expect("files were found beneath the root"),
+            );
+            cache.record(key, size);
+        }
+        Ok(cache)
+    }
+
+    /// The directory that the cached files are in.
+    pub fn root(&self) -> &AbsolutePath {
+        self.root.as_absolute_path()
+    }
+
+    /// The combined size of the cached files.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Whether there is a cached file for `key`.
+    pub fn contains(&self, key: &RelativePath) -> bool {
+        self.key(key)
+            .is_ok_and(|key| self.entries.contains_key(&key))
+    }
+
+    /// Write `contents` to the file for `key`, replacing any previous contents, and mark it as
+    /// the most recently used. Returns the file's path.
+    ///
+    /// Nothing is evicted, so the cache can grow beyond its limit until
+    /// [`CacheDir::evict_to_fit`] is called. Fails if `key` is not beneath the root, e.g.
+    /// `../file`.
+    pub fn insert(
+        &mut self,
+        key: &RelativePath,
+        contents: &[u8],
+    ) -> std::io::Result<AbsolutePathBuf> {
+        let key = self.key(key)?;
+        let path = self.path(&key);
+        path.ensure_parent_exists()?;
+        crate::fs::write_atomic(&path, contents)?;
+        self.record(key, contents.len() as u64);
+        Ok(path)
+    }
+
+    /// Mark the file for `key` as the most recently used, e.g. when it is read. Returns whether
+    /// there was a file for `key`.
+    pub fn touch(&mut self, key: &RelativePath) -> bool {
+        let Ok(key) = self.key(key) else {
+            return false;
+        };
+        self.clock += 1;
+        match self.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the least recently used files until the cache is within its size limit, and
+    /// return the keys that were removed, least recently used first.
+    pub fn evict_to_fit(&mut self) -> std::io::Result<Vec<RelativePathBuf>> {
+        if self.total_bytes <= self.max_bytes {
+            return Ok(vec![]);
+        }
+        let mut by_age: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.last_used, key.clone()))
+            .collect();
+        by_age.sort();
+
+        let mut evicted = vec![];
+        for (_, key) in by_age {
+            if self.total_bytes <= self.max_bytes {
+                break;
+            }
+            let path = self.path(&key);
+            match crate::fs::remove(&path, Mode::Execute) {
+                Ok(_) => {}
+                // Already gone, e.g. removed by hand, so there is nothing to free.
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            if let Some(entry) = self.entries.remove(&key) {
+                self.total_bytes -= entry.size;
+            }
+            evicted.push(key);
+        }
+        Ok(evicted)
+    }
+
+    /// Normalize `key`, failing if it is not beneath the root.
+    fn key(&self, key: &RelativePath) -> std::io::Result<RelativePathBuf> {
+        self.root
+            .join_relative(key)
+            .ok()
+            .and_then(|path| {
+                path.strip_prefix(&self.root)
+                    .ok()
+                    .filter(|key| !key.as_os_str().is_empty())
+                    .map(RelativePathBuf::from)
+            })
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("cache key `{}` is not beneath the cache", key.display()),
+                )
+            })
+    }
+
+    fn path(&self, key: &RelativePath) -> AbsolutePathBuf {
+        self.root
+            .join_relative(key)
+            .expect("keys are normalized beneath the root")
+    }
+
+    fn record(&mut self, key: RelativePathBuf, size: u64) {
+        self.clock += 1;
+        let entry = Entry {
+            size,
+            last_used: self.clock,
+        };
+        if let Some(previous) = self.entries.insert(key, entry) {
+            self.total_bytes -= previous.size;
+        }
+        self.total_bytes += size;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::CacheDir;
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn evicts_least_recently_used() -> anyhow::Result<()> {
+        fn key(k: &str) -> anyhow::Result<&RelativePath> {
+            Ok(RelativePath::try_new(k)?)
+        }
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let cache_root = root.join("cache")?;
+
+        let mut cache = CacheDir::new(&cache_root, 10)?;
+        assert!(cache_root.is_dir());
+        assert_eq!(
+            cache_root.join("a/1.o")?,
+            cache.insert(key("a/1.o")?, b"1111")?
+        );
+        cache.insert(key("b/2.o")?, b"2222")?;
+        cache.insert(key("./a/../3.o")?, b"3333")?;
+        assert_eq!(12, cache.total_bytes());
+        assert!(cache.contains(key("3.o")?));
+        assert!(cache.insert(key("../outside.o")?, b"").is_err());
+
+        assert!(cache.touch(key("a/1.o")?));
+        assert!(!cache.touch(key("missing.o")?));
+        assert_eq!(
+            vec![RelativePathBuf::try_new("b/2.o")?],
+            cache.evict_to_fit()?
+        );
+        assert_eq!(8, cache.total_bytes());
+        assert!(!cache_root.join("b/2.o")?.exists());
+        assert!(cache.evict_to_fit()?.is_empty());
+
+        // Replacing a file only counts its new size.
+        cache.insert(key("3.o")?, b"33")?;
+        assert_eq!(6, cache.total_bytes());
+
+        let mut reopened = CacheDir::new(&cache_root, 0)?;
+        assert_eq!(6, reopened.total_bytes());
+        assert!(reopened.contains(key("a/1.o")?));
+        assert_eq!(2, reopened.evict_to_fit()?.len());
+        assert_eq!(0, reopened.total_bytes());
+        Ok(())
+    }
+}
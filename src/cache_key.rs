@@ -0,0 +1,217 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::AbsolutePath;
+
+/// The normalization steps that [`AbsolutePath::cache_key`] applies, in order.
+///
+/// By default, every step is applied, except that case folding only happens on windows and
+/// macOS, where filesystems are case-insensitive by default.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct CacheKeyOptions {
+    resolve_symlinks: bool,
+    strip_verbatim: bool,
+    forward_slashes: bool,
+    case_fold: bool,
+}
+
+impl Default for CacheKeyOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheKeyOptions {
+    /// Every step, with case folding on windows and macOS.
+    pub fn new() -> Self {
+        Self {
+            resolve_symlinks: true,
+            strip_verbatim: true,
+            forward_slashes: true,
+            case_fold: cfg!(any(windows, target_os = "macos")),
+        }
+    }
+
+    /// Resolve symlinks with [`std::fs::canonicalize`], so that different routes to the same
+    /// file have the same key. If the path does not exist, its deepest existing ancestor is
+    /// resolved instead, and the rest of the path is appended.
+    pub fn resolve_symlinks(mut self, resolve_symlinks: bool) -> Self {
+        self.resolve_symlinks = resolve_symlinks;
+        self
+    }
+
+    /// Remove windows' verbatim prefixes, so `\\?\C:\foo` becomes `C:\foo`, and
+    /// `\\?\UNC\server\share` becomes `\\server\share`. This matters because
+    /// [`std::fs::canonicalize`] adds them. Does nothing on other platforms.
+    pub fn strip_verbatim(mut self, strip_verbatim: bool) -> Self {
+        self.strip_verbatim = strip_verbatim;
+        self
+    }
+
+    /// Use `/` as the separator, so `C:\foo\bar` becomes `C:/foo/bar`. Does nothing on
+    /// platforms where `/` is already the separator.
+    pub fn forward_slashes(mut self, forward_slashes: bool) -> Self {
+        self.forward_slashes = forward_slashes;
+        self
+    }
+
+    /// Lowercase the key, so that paths that only differ by case have the same key.
+    pub fn case_fold(mut self, case_fold: bool) -> Self {
+        self.case_fold = case_fold;
+        self
+    }
+
+    pub(crate) fn key(&self, path: &AbsolutePath) -> std::io::Result<CacheKey> {
+        let path = if self.resolve_symlinks {
+            canonicalize_existing(path.as_path())?
+        } else {
+            path.as_path().to_path_buf()
+        };
+        // Escape anything that is not UTF-8 the same way as environment values, so that paths
+        // which only differ in those bytes do not share a key.
+        let mut key = crate::env_value::encode(path.as_os_str());
+        if self.strip_verbatim && cfg!(windows) {
+            if let Some(unc) = key.strip_prefix(r"\\?\UNC\") {
+                key = format!(r"\\{}", unc);
+            } else if let Some(rest) = key.strip_prefix(r"\\?\") {
+                key = rest.to_owned();
+            }
+        }
+        if self.forward_slashes && std::path::MAIN_SEPARATOR != '/' {
+            key = key.replace(std::path::MAIN_SEPARATOR, "/");
+        }
+        if self.case_fold {
+            key = key.to_lowercase();
+        }
+        Ok(CacheKey(key))
+    }
+}
+
+/// Canonicalize the deepest existing ancestor of `path`, and append the rest of `path` to it.
+fn canonicalize_existing(path: &Path) -> std::io::Result<PathBuf> {
+    let mut missing = vec![];
+    for ancestor in path.ancestors() {
+        match std::fs::canonicalize(ancestor) {
+            Ok(mut resolved) => {
+                resolved.extend(missing.iter().rev());
+                return Ok(resolved);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                missing.extend(ancestor.file_name());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    // Even the root does not exist, e.g. a drive that is not mounted.
+    Ok(path.to_path_buf())
+}
+
+/// A string form of a path for use as a stable cache key, from [`AbsolutePath::cache_key`].
+///
+/// `%` is escaped as `%25`, and anything that is not UTF-8 is escaped as described for
+/// [`AbsolutePath::to_env_value`], so distinct paths never share a key.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// The normalized path.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Take ownership of the normalized path.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// A 64 bit FNV-1a hash of the key, e.g. for a file name in a cache directory.
+    ///
+    /// Unlike [`std::hash::Hash`], this is the same across versions of rust and platforms.
+    pub fn stable_hash(&self) -> u64 {
+        crate::redacted::fnv1a(self.0.as_bytes())
+    }
+}
+
+impl AsRef<str> for CacheKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::CacheKeyOptions;
+
+    #[test]
+    fn creates_cache_keys() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let target = root.join("Target")?;
+        std::fs::create_dir(&target)?;
+
+        let raw = CacheKeyOptions::new()
+            .resolve_symlinks(false)
+            .case_fold(false);
+        let key = target.cache_key(&raw)?;
+        assert!(key.as_str().ends_with("/Target"));
+        assert!(!key.as_str().contains('\\'));
+        assert_eq!(
+            key.as_str().to_lowercase(),
+            target.cache_key(&raw.case_fold(true))?.as_str()
+        );
+
+        // Missing paths resolve their existing ancestors.
+        let options = CacheKeyOptions::new().case_fold(false);
+        assert_eq!(
+            format!("{}/missing/file.txt", key.as_str()),
+            target
+                .join("missing/file.txt")?
+                .cache_key(&options)?
+                .as_str()
+        );
+
+        #[cfg(unix)]
+        {
+            let link = root.join("link")?;
+            std::os::unix::fs::symlink(&target, &link)?;
+            assert_eq!(key, link.cache_key(&options)?);
+            assert_ne!(key, link.cache_key(&options.resolve_symlinks(false))?);
+            assert_eq!(
+                key.stable_hash(),
+                link.join("x/..")?.cache_key(&options)?.stable_hash()
+            );
+        }
+
+        assert_eq!(
+            0xaf63dc4c8601ec8c,
+            crate::cache_key::CacheKey("a".to_owned()).stable_hash()
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_non_utf8_paths_distinct() -> anyhow::Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let options = CacheKeyOptions::new()
+            .resolve_symlinks(false)
+            .case_fold(false);
+        let first = AbsolutePathBuf::try_new(OsStr::from_bytes(b"/tmp/\xff"))?;
+        let second = AbsolutePathBuf::try_new(OsStr::from_bytes(b"/tmp/\xfe"))?;
+        let percent = AbsolutePathBuf::try_new("/tmp/%FF")?;
+        assert_eq!("/tmp/%FF", first.cache_key(&options)?.as_str());
+        assert_eq!("/tmp/%25FF", percent.cache_key(&options)?.as_str());
+        assert_ne!(first.cache_key(&options)?, second.cache_key(&options)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::ops::Deref;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// An absolute path from [`std::fs::canonicalize`], so it exists, and has had all of its symlinks
+/// resolved.
+///
+/// Paths that reach the same file through different symlinks have equal [`CanonicalPathBuf`]s,
+/// so these are useful for identity comparisons, e.g. as map keys. Hard links to the same file
+/// are still different paths.
+/// Like [`crate::ResolvedAbsolutePathBuf`], this is only a PathBuf variant.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Ord, PartialOrd)]
+pub struct CanonicalPathBuf(AbsolutePathBuf);
+
+impl CanonicalPathBuf {
+    /// Canonicalize `path` with [`std::fs::canonicalize`].
+    ///
+    /// Relative paths are resolved against the cwd. This fails if the path does not exist.
+    pub fn try_new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let canonical = std::fs::canonicalize(path)?;
+        AbsolutePathBuf::try_new(canonical)
+            .map(Self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Get a new [`AbsolutePath`] referencing the internal Path object.
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        &self.0
+    }
+
+    /// Take ownership of the path as an [`AbsolutePathBuf`].
+    pub fn into_absolute_path_buf(self) -> AbsolutePathBuf {
+        self.0
+    }
+}
+
+impl TryFrom<&AbsolutePath> for CanonicalPathBuf {
+    type Error = std::io::Error;
+
+    fn try_from(value: &AbsolutePath) -> Result<Self, Self::Error> {
+        CanonicalPathBuf::try_new(value)
+    }
+}
+
+impl TryFrom<AbsolutePathBuf> for CanonicalPathBuf {
+    type Error = std::io::Error;
+
+    fn try_from(value: AbsolutePathBuf) -> Result<Self, Self::Error> {
+        CanonicalPathBuf::try_new(value)
+    }
+}
+
+impl From<CanonicalPathBuf> for AbsolutePathBuf {
+    fn from(value: CanonicalPathBuf) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for CanonicalPathBuf {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CanonicalPathBuf::try_new(s)
+    }
+}
+
+impl AsRef<Path> for CanonicalPathBuf {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<AbsolutePath> for CanonicalPathBuf {
+    fn as_ref(&self) -> &AbsolutePath {
+        &self.0
+    }
+}
+
+impl Deref for CanonicalPathBuf {
+    type Target = AbsolutePath;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for CanonicalPathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CanonicalPathBuf {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Canonicalizes the path, so this fails if it does not exist.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CanonicalPathBuf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let path = crate::serde_path::deserialize(deserializer)?;
+        CanonicalPathBuf::try_new(&path)
+            .map_err(|e| D::Error::custom(format!("`{}`: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::CanonicalPathBuf;
+
+    #[test]
+    fn canonicalizes_paths() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let dir = root.join("dir")?;
+        std::fs::create_dir(&dir)?;
+        std::fs::write(dir.join("file.txt")?, "")?;
+
+        let canonical = CanonicalPathBuf::try_from(dir.join("file.txt")?)?;
+        assert_eq!(
+            dir.join("file.txt")?,
+            AbsolutePathBuf::from(canonical.clone())
+        );
+        assert_eq!(dir.as_absolute_path(), canonical.parent().unwrap());
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            CanonicalPathBuf::try_new(dir.join("missing.txt")?)
+                .unwrap_err()
+                .kind()
+        );
+
+        #[cfg(unix)]
+        {
+            let link = root.join("link")?;
+            std::os::unix::fs::symlink(&dir, &link)?;
+            assert_eq!(
+                canonical,
+                CanonicalPathBuf::try_new(link.join("file.txt")?)?
+            );
+            assert_ne!(link.join("file.txt")?, canonical.into_absolute_path_buf());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// What happened to a path, as reported to (and by) an [`EventCoalescer`].
+///
+/// With the `serde` feature, kinds are (de)serialized in snake case, e.g. `"directory_changed"`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PathEventKind {
+    Created,
+    Modified,
+    Removed,
+    /// Something changed within this directory. Used in place of many events for its children.
+    DirectoryChanged,
+    /// The path was created by renaming [`PathEvent::old_path`].
+    Renamed,
+}
+
+/// A change to a single path.
+///
+/// With the `serde` feature, events are (de)serialized as e.g.
+/// `{"path": "/new", "kind": "renamed", "old_path": "/old", "timestamp": 1700000000000000000}`,
+/// where `old_path` and `timestamp` are left out if they are not set, and `timestamp` is in
+/// nanoseconds since the unix epoch. With the `jsonl` feature, streams of events can be read and
+/// written as JSON Lines with `PathEventReader` and `write_path_events()`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathEvent {
+    path: AbsolutePathBuf,
+    kind: PathEventKind,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    old_path: Option<AbsolutePathBuf>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none", with = "unix_nanos")
+    )]
+    timestamp: Option<SystemTime>,
+}
+
+impl PathEvent {
+    /// Create an event for `path`.
+    pub fn new(path: AbsolutePathBuf, kind: PathEventKind) -> Self {
+        Self {
+            path,
+            kind,
+            old_path: None,
+            timestamp: None,
+        }
+    }
+
+    /// Create a [`PathEventKind::Renamed`] event for `old_path` being renamed to `path`.
+    pub fn renamed(old_path: AbsolutePathBuf, path: AbsolutePathBuf) -> Self {
+        Self {
+            old_path: Some(old_path),
+            ..Self::new(path, PathEventKind::Renamed)
+        }
+    }
+
+    /// Record when the event happened.
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// The path that changed.
+    pub fn path(&self) -> &AbsolutePath {
+        self.path.as_absolute_path()
+    }
+
+    /// How the path changed.
+    pub fn kind(&self) -> PathEventKind {
+        self.kind
+    }
+
+    /// The path that was renamed, for [`PathEventKind::Renamed`] events.
+    pub fn old_path(&self) -> Option<&AbsolutePath> {
+        self.old_path.as_deref()
+    }
+
+    /// When the event happened, if it was recorded.
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Take ownership of the path that changed.
+    pub fn into_path(self) -> AbsolutePathBuf {
+        self.path
+    }
+}
+
+/// Timestamps as nanoseconds since the unix epoch, which unlike serde's format for
+/// [`SystemTime`] is a single number.
+#[cfg(feature = "serde")]
+mod unix_nanos {
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    pub(super) fn serialize<S>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+        let time = time.expect("`None` is skipped");
+        let nanos = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()
+            .and_then(|d| u64::try_from(d.as_nanos()).ok())
+            .ok_or_else(|| S::Error::custom("timestamps must be between 1970 and 2554"))?;
+        serializer.serialize_u64(nanos)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let nanos = u64::deserialize(deserializer)?;
+        Ok(Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos)))
+    }
+}
+
+/// Merges bursts of filesystem events (e.g. from a file watcher) into batches.
+///
+/// Events are held until none have arrived for the debounce interval, then returned together
+/// by [`EventCoalescer::poll`]. Within a batch there is at most one event per path: e.g. a file
+/// that is created then modified is reported as created, and one that is created then removed
+/// is not reported at all. Renames are split into the removal of the old path and the creation
+/// of the new one, and timestamps are dropped. If [`EventCoalescer::collapse_after`] is set, directories with more
+/// changed children than that are reported as a single
+/// [`PathEventKind::DirectoryChanged`] instead.
+///
+/// This does no I/O or waiting itself: callers pass in the current time, and can use
+/// [`EventCoalescer::deadline`] to decide how long to wait for more events.
+#[derive(Debug, Clone)]
+pub struct EventCoalescer {
+    debounce: Duration,
+    collapse_after: Option<usize>,
+    pending: BTreeMap<AbsolutePathBuf, PathEventKind>,
+    last_event: Option<Instant>,
+}
+
+impl EventCoalescer {
+    /// Create a coalescer that emits batches once there have been no events for `debounce`.
+    pub fn new(debounce: Duration) -> Self {
+        Self {
+            debounce,
+            collapse_after: None,
+            pending: BTreeMap::new(),
+            last_event: None,
+        }
+    }
+
+    /// Replace the events for a directory's children with a single
+    /// [`PathEventKind::DirectoryChanged`] event for the directory, once there are more than
+    /// `count` of them in a batch.
+    pub fn collapse_after(mut self, count: usize) -> Self {
+        self.collapse_after = Some(count);
+        self
+    }
+
+    /// Record an event that happened at `at`.
+    pub fn push(&mut self, event: PathEvent, at: Instant) {
+        self.last_event = Some(self.last_event.map_or(at, |last| last.max(at)));
+        let mut kind = event.kind;
+        if kind == PathEventKind::Renamed {
+            if let Some(old_path) = event.old_path {
+                self.record(old_path, PathEventKind::Removed);
+            }
+            kind = PathEventKind::Created;
+        }
+        self.record(event.path, kind);
+    }
+
+    fn record(&mut self, path: AbsolutePathBuf, kind: PathEventKind) {
+        match self.pending.remove(&path) {
+            None => {
+                self.pending.insert(path, kind);
+            }
+            Some(previous) => {
+                if let Some(kind) = merge(previous, kind) {
+                    self.pending.insert(path, kind);
+                }
+            }
+        }
+    }
+
+    /// When the pending events will be ready, if there are any.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.pending.is_empty() {
+            None
+        } else {
+            self.last_event.map(|last| last + self.debounce)
+        }
+    }
+
+    /// Take the pending events if none have arrived for the debounce interval before `now`.
+    ///
+    /// Events are sorted by path.
+    pub fn poll(&mut self, now: Instant) -> Option<Vec<PathEvent>> {
+        match self.deadline() {
+            Some(deadline) if deadline <= now => Some(self.flush()),
+            _ => None,
+        }
+    }
+
+    /// Take all pending events now, regardless of when they arrived.
+    pub fn flush(&mut self) -> Vec<PathEvent> {
+        self.last_event = None;
+        let mut pending = std::mem::take(&mut self.pending);
+        if let Some(limit) = self.collapse_after {
+            collapse(&mut pending, limit);
+        }
+        pending
+            .into_iter()
+            .map(|(path, kind)| PathEvent::new(path, kind))
+            .collect()
+    }
+}
+
+/// Combine two events for the same path, or `None` if they cancel out.
+fn merge(previous: PathEventKind, next: PathEventKind) -> Option<PathEventKind> {
+    use PathEventKind::*;
+
+    match (previous, next) {
+        (Created, Removed) => None,
+        (Created, _) => Some(Created),
+        (Removed, Created) => Some(Modified),
+        (_, Removed) => Some(Removed),
+        (DirectoryChanged, _) => Some(DirectoryChanged),
+        (_, next) => Some(next),
+    }
+}
+
+fn collapse(pending: &mut BTreeMap<AbsolutePathBuf, PathEventKind>, limit: usize) {
+    let mut children: BTreeMap<&AbsolutePath, usize> = BTreeMap::new();
+    for path in pending.keys() {
+        if let Some(parent) = path.parent() {
+            *children.entry(parent).or_default() += 1;
+        }
+    }
+    let busy: Vec<AbsolutePathBuf> = children
+        .into_iter()
+        .filter(|(_, count)| *count > limit)
+        .map(|(dir, _)| dir.into())
+        .collect();
+    for dir in busy {
+        pending.retain(|path, _| path.parent() != Some(dir.as_absolute_path()));
+        // Keep a more specific event for the directory itself, e.g. if it was just created.
+        pending
+            .entry(dir)
+            .or_insert(PathEventKind::DirectoryChanged);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use crate::AbsolutePathBuf;
+    use crate::EventCoalescer;
+    use crate::PathEvent;
+    use crate::PathEventKind;
+
+    #[test]
+    fn coalesces_events() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let event = |path: &str, kind| -> anyhow::Result<PathEvent> {
+            Ok(PathEvent::new(cwd.join(path)?, kind))
+        };
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        let mut coalescer = EventCoalescer::new(Duration::from_millis(50));
+        assert_eq!(None, coalescer.deadline());
+        coalescer.push(event("a.txt", PathEventKind::Created)?, at(0));
+        coalescer.push(event("a.txt", PathEventKind::Modified)?, at(10));
+        coalescer.push(event("tmp", PathEventKind::Created)?, at(20));
+        coalescer.push(event("tmp", PathEventKind::Removed)?, at(30));
+        coalescer.push(event("b.txt", PathEventKind::Removed)?, at(30));
+        coalescer.push(event("b.txt", PathEventKind::Created)?, at(40));
+
+        assert_eq!(Some(at(90)), coalescer.deadline());
+        assert_eq!(None, coalescer.poll(at(89)));
+        assert_eq!(
+            Some(vec![
+                event("a.txt", PathEventKind::Created)?,
+                event("b.txt", PathEventKind::Modified)?,
+            ]),
+            coalescer.poll(at(90))
+        );
+        assert_eq!(None, coalescer.poll(at(1000)));
+        Ok(())
+    }
+
+    #[test]
+    fn collapses_directories() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let event = |path: &str, kind| -> anyhow::Result<PathEvent> {
+            Ok(PathEvent::new(cwd.join(path)?, kind))
+        };
+        let now = Instant::now();
+
+        let mut coalescer = EventCoalescer::new(Duration::from_millis(50)).collapse_after(2);
+        for i in 0..3 {
+            coalescer.push(
+                event(&format!("build/{}.o", i), PathEventKind::Modified)?,
+                now,
+            );
+            coalescer.push(event(&format!("new/{}.o", i), PathEventKind::Created)?, now);
+        }
+        coalescer.push(event("new", PathEventKind::Created)?, now);
+        coalescer.push(event("src/a.rs", PathEventKind::Modified)?, now);
+        coalescer.push(event("src/b.rs", PathEventKind::Modified)?, now);
+
+        assert_eq!(
+            vec![
+                event("build", PathEventKind::DirectoryChanged)?,
+                event("new", PathEventKind::Created)?,
+                event("src/a.rs", PathEventKind::Modified)?,
+                event("src/b.rs", PathEventKind::Modified)?,
+            ],
+            coalescer.flush()
+        );
+        Ok(())
+    }
+}
@@ -130,7 +130,7 @@ impl serde::Serialize for CombinedPath {
     where
         S: serde::Serializer,
     {
-        self.0.serialize(serializer)
+        crate::serde_path::serialize(&self.0, serializer)
     }
 }
 
@@ -144,7 +144,7 @@ where
         &'b self,
         out: &mut diesel::serialize::Output<'b, '_, DB>,
     ) -> diesel::serialize::Result {
-        self.0.to_str().expect("paths should be utf8").to_sql(out)
+        crate::path_to_str(&self.0)?.to_sql(out)
     }
 }
 
@@ -161,7 +161,7 @@ pub enum CombinedPathBuf {
 }
 
 impl CombinedPathBuf {
-    pub fn try_new<P: Into<PathBuf> + ?Sized>(path: P) -> Result<Self, NormalizationFailed> {
+    pub fn try_new<P: Into<PathBuf>>(path: P) -> Result<Self, NormalizationFailed> {
         let p = path.into();
         if p.is_absolute() {
             Ok(CombinedPathBuf::Absolute(
@@ -187,6 +187,14 @@ impl CombinedPathBuf {
         }
     }
 
+    /// The number of bytes allocated on the heap for this path.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            CombinedPathBuf::Relative(r) => r.heap_size(),
+            CombinedPathBuf::Absolute(a) => a.heap_size(),
+        }
+    }
+
     /// Attempt to join to a path.
     ///
     /// The provided path must be relative.
@@ -338,7 +346,7 @@ impl<'de> serde::Deserialize<'de> for CombinedPathBuf {
         D: serde::Deserializer<'de>,
     {
         use serde::de::Error;
-        let path = PathBuf::deserialize(deserializer)?;
+        let path = crate::serde_path::deserialize(deserializer)?;
         CombinedPathBuf::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
     }
 }
@@ -370,11 +378,327 @@ where
     DB: diesel::backend::Backend,
     String: diesel::deserialize::FromSql<diesel::sql_types::Text, DB>,
 {
-    fn from_sql(bytes: diesel::backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+    fn from_sql(
+        bytes: <DB as diesel::backend::Backend>::RawValue<'_>,
+    ) -> diesel::deserialize::Result<Self> {
         String::from_sql(bytes).and_then(|s| Ok(CombinedPathBuf::try_new(s)?))
     }
 }
 
+#[cfg(feature = "sqlx")]
+impl<DB> sqlx::Type<DB> for CombinedPathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        String::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        String::compatible(ty)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'q, DB> sqlx::Encode<'q, DB> for CombinedPathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        crate::path_to_str(self.as_path())?.to_owned().encode(buf)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'r, DB> sqlx::Decode<'r, DB> for CombinedPathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(CombinedPathBuf::try_new(String::decode(value)?)?)
+    }
+}
+
+/// Fails if the path is not UTF-8, as only strings can be stored.
+#[cfg(feature = "sea-orm")]
+impl TryFrom<CombinedPathBuf> for sea_orm::sea_query::Value {
+    type Error = crate::NotUtf8;
+
+    fn try_from(path: CombinedPathBuf) -> Result<Self, Self::Error> {
+        let path = crate::path_to_str(path.as_path())?.to_owned();
+        Ok(sea_orm::sea_query::Value::String(Some(Box::new(path))))
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::TryGetable for CombinedPathBuf {
+    fn try_get_by<I: sea_orm::ColIdx>(
+        res: &sea_orm::QueryResult,
+        index: I,
+    ) -> Result<Self, sea_orm::TryGetError> {
+        CombinedPathBuf::try_new(String::try_get_by(res, index)?)
+            .map_err(|e| sea_orm::TryGetError::DbErr(sea_orm::DbErr::Type(e.to_string())))
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::sea_query::ValueType for CombinedPathBuf {
+    fn try_from(v: sea_orm::sea_query::Value) -> Result<Self, sea_orm::sea_query::ValueTypeErr> {
+        match v {
+            sea_orm::sea_query::Value::String(Some(path)) => {
+                CombinedPathBuf::try_new(*path).map_err(|_| sea_orm::sea_query::ValueTypeErr)
+            }
+            _ => Err(sea_orm::sea_query::ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "CombinedPathBuf".to_owned()
+    }
+
+    fn array_type() -> sea_orm::sea_query::ArrayType {
+        sea_orm::sea_query::ArrayType::String
+    }
+
+    fn column_type() -> sea_orm::sea_query::ColumnType {
+        sea_orm::sea_query::ColumnType::String(None)
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::sea_query::Nullable for CombinedPathBuf {
+    fn null() -> sea_orm::sea_query::Value {
+        sea_orm::sea_query::Value::String(None)
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::ToSql for CombinedPathBuf {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        crate::path_to_str(self.as_path())
+            .map(Into::into)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::types::FromSql for CombinedPathBuf {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        CombinedPathBuf::try_new(String::column_result(value)?)
+            .map_err(|e| rusqlite::types::FromSqlError::Other(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl postgres_types::ToSql for CombinedPathBuf {
+    fn to_sql(
+        &self,
+        ty: &postgres_types::Type,
+        out: &mut bytes::BytesMut,
+    ) -> Result<postgres_types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        crate::path_to_str(self.as_path())?.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &postgres_types::Type) -> bool {
+        <&str as postgres_types::ToSql>::accepts(ty)
+    }
+
+    postgres_types::to_sql_checked!();
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> postgres_types::FromSql<'a> for CombinedPathBuf {
+    fn from_sql(
+        ty: &postgres_types::Type,
+        raw: &'a [u8],
+    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(CombinedPathBuf::try_new(
+            <&str as postgres_types::FromSql>::from_sql(ty, raw)?,
+        )?)
+    }
+
+    fn accepts(ty: &postgres_types::Type) -> bool {
+        <&str as postgres_types::FromSql>::accepts(ty)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for CombinedPathBuf {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "CombinedPathBuf".to_owned()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("paths::CombinedPathBuf")
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        crate::path_schema(
+            "path",
+            "An absolute or relative path. `.` and `..` components are normalized away, except for leading `..` components of relative paths.",
+        )
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for CombinedPathBuf {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        if u.arbitrary()? {
+            Ok(CombinedPathBuf::Absolute(u.arbitrary()?))
+        } else {
+            Ok(CombinedPathBuf::Relative(u.arbitrary()?))
+        }
+    }
+}
+
+/// An archived [`CombinedPathBuf`], which can be used as a [`CombinedPath`] without copying it.
+///
+/// When accessed with validation, e.g. by `rkyv::access`, the path is checked to be a valid
+/// [`CombinedPath`].
+#[cfg(feature = "rkyv")]
+#[derive(rkyv::Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck, verify)]
+#[repr(transparent)]
+pub struct ArchivedCombinedPathBuf(rkyv::string::ArchivedString);
+
+#[cfg(feature = "rkyv")]
+impl ArchivedCombinedPathBuf {
+    /// Get the archived path as a [`CombinedPath`].
+    pub fn as_combined_path(&self) -> &CombinedPath {
+        CombinedPath::ref_cast(Path::new(self.0.as_str()))
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl std::fmt::Debug for ArchivedCombinedPathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_combined_path().fmt(f)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+unsafe impl<C> rkyv::bytecheck::Verify<C> for ArchivedCombinedPathBuf
+where
+    C: rkyv::rancor::Fallible + ?Sized,
+    C::Error: rkyv::rancor::Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        CombinedPath::try_new(self.0.as_str())
+            .map(|_| ())
+            .map_err(<C::Error as rkyv::rancor::Source>::new)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl rkyv::Archive for CombinedPathBuf {
+    type Archived = ArchivedCombinedPathBuf;
+    type Resolver = rkyv::string::StringResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        // SAFETY: `ArchivedCombinedPathBuf` is a transparent wrapper around an `ArchivedString`.
+        let out = unsafe { out.cast_unchecked::<rkyv::string::ArchivedString>() };
+        rkyv::string::ArchivedString::resolve_from_str(
+            self.as_path()
+                .to_str()
+                .expect("`serialize` checked that the path is utf8"),
+            resolver,
+            out,
+        );
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<S> rkyv::Serialize<S> for CombinedPathBuf
+where
+    S: rkyv::rancor::Fallible + ?Sized,
+    S::Error: rkyv::rancor::Source,
+    str: rkyv::SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let path =
+            crate::path_to_str(self.as_path()).map_err(<S::Error as rkyv::rancor::Source>::new)?;
+        rkyv::string::ArchivedString::serialize_from_str(path, serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<D> rkyv::Deserialize<CombinedPathBuf, D> for ArchivedCombinedPathBuf
+where
+    D: rkyv::rancor::Fallible + ?Sized,
+{
+    fn deserialize(&self, _: &mut D) -> Result<CombinedPathBuf, D::Error> {
+        Ok(CombinedPathBuf::try_new(self.as_combined_path().as_path())
+            .expect("archived paths are normalized"))
+    }
+}
+
+#[cfg(feature = "camino")]
+impl CombinedPath {
+    /// Get this path as a [`camino::Utf8Path`], or `None` if it is not valid UTF-8.
+    pub fn as_utf8(&self) -> Option<&camino::Utf8Path> {
+        camino::Utf8Path::from_path(&self.0)
+    }
+}
+
+#[cfg(feature = "camino")]
+impl CombinedPathBuf {
+    /// Get this path as a [`camino::Utf8Path`], or `None` if it is not valid UTF-8.
+    pub fn as_utf8(&self) -> Option<&camino::Utf8Path> {
+        camino::Utf8Path::from_path(self.as_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl<'a> TryFrom<&'a camino::Utf8Path> for &'a CombinedPath {
+    type Error = WasNotNormalized;
+
+    fn try_from(value: &'a camino::Utf8Path) -> Result<Self, Self::Error> {
+        CombinedPath::try_new(value.as_std_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<&camino::Utf8Path> for CombinedPathBuf {
+    type Error = NormalizationFailed;
+
+    fn try_from(value: &camino::Utf8Path) -> Result<Self, Self::Error> {
+        CombinedPathBuf::try_new(value.as_std_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<camino::Utf8PathBuf> for CombinedPathBuf {
+    type Error = NormalizationFailed;
+
+    fn try_from(value: camino::Utf8PathBuf) -> Result<Self, Self::Error> {
+        CombinedPathBuf::try_new(value.into_std_path_buf())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<CombinedPathBuf> for camino::Utf8PathBuf {
+    type Error = camino::FromPathBufError;
+
+    fn try_from(value: CombinedPathBuf) -> Result<Self, Self::Error> {
+        match value {
+            CombinedPathBuf::Relative(r) => r.try_into(),
+            CombinedPathBuf::Absolute(a) => a.try_into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::path::Path;
@@ -782,4 +1106,309 @@ mod test_diesel {
 
         Ok(())
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut connection = create_table()?;
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let file = TestFile {
+            id: 1,
+            x: CombinedPathBuf::try_new(name)?,
+            y: None,
+        };
+        assert!(
+            diesel::insert_into(crate::diesel_helpers::schema::test_files::table)
+                .values(&file)
+                .execute(&mut connection)
+                .is_err()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlx-sqlite", feature = "sqlx-runtime-tokio"))]
+mod test_sqlx {
+    use crate::block_on;
+    use crate::sqlx_helpers::create_table;
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        block_on(async {
+            let mut connection = create_table().await?;
+            let x = CombinedPathBuf::try_new("foo/bar.txt")?;
+            let y = CombinedPathBuf::Absolute(AbsolutePathBuf::current_dir().join("bar/baz.txt")?);
+            for (id, y) in [(1, None), (2, Some(&y))] {
+                sqlx::query("INSERT INTO test_files (id, x, y) VALUES (?, ?, ?)")
+                    .bind(id)
+                    .bind(&x)
+                    .bind(y)
+                    .execute(&mut connection)
+                    .await?;
+            }
+            let rows: Vec<(CombinedPathBuf, Option<CombinedPathBuf>)> =
+                sqlx::query_as("SELECT x, y FROM test_files ORDER BY id")
+                    .fetch_all(&mut connection)
+                    .await?;
+            assert_eq!(vec![(x.clone(), None), (x, Some(y))], rows);
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        block_on(async {
+            let mut connection = create_table().await?;
+            let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+            let path = CombinedPathBuf::try_new(name)?;
+            assert!(sqlx::query("INSERT INTO test_files (id, x) VALUES (1, ?)")
+                .bind(&path)
+                .execute(&mut connection)
+                .await
+                .is_err());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sea-orm"))]
+mod test_sea_orm {
+    use std::collections::BTreeMap;
+
+    use sea_orm::sea_query::Value;
+    use sea_orm::sea_query::ValueType;
+    use sea_orm::ConnectionTrait;
+    use sea_orm::DbBackend;
+    use sea_orm::MockDatabase;
+    use sea_orm::Statement;
+
+    use crate::block_on;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = CombinedPathBuf::try_new("foo/bar.txt")?;
+        let value = Value::try_from(path.clone())?;
+        assert_eq!(Value::from(path.display().to_string()), value);
+        assert_eq!(
+            Some(path.clone()),
+            <CombinedPathBuf as ValueType>::try_from(value.clone()).ok()
+        );
+
+        block_on(async {
+            let db = MockDatabase::new(DbBackend::Sqlite)
+                .append_query_results([[
+                    BTreeMap::from([("x", value.clone()), ("y", Value::String(None))]),
+                    BTreeMap::from([("x", value.clone()), ("y", value)]),
+                ]])
+                .into_connection();
+            let rows = db
+                .query_all(Statement::from_string(
+                    DbBackend::Sqlite,
+                    "SELECT x, y FROM test_files".to_owned(),
+                ))
+                .await?;
+
+            assert_eq!(path, rows[0].try_get::<CombinedPathBuf>("", "x")?);
+            assert_eq!(None, rows[0].try_get::<Option<CombinedPathBuf>>("", "y")?);
+            assert_eq!(
+                Some(path),
+                rows[1].try_get::<Option<CombinedPathBuf>>("", "y")?
+            );
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = CombinedPathBuf::try_new(name)?;
+        assert!(Value::try_from(path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rusqlite"))]
+mod test_rusqlite {
+    use rusqlite::params;
+
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let connection = rusqlite::Connection::open_in_memory()?;
+        connection.execute(
+            "CREATE TABLE test_files (id PRIMARY KEY NOT NULL, x TEXT NOT NULL, y TEXT NULL)",
+            [],
+        )?;
+        let x = CombinedPathBuf::try_new("foo/bar.txt")?;
+        let y = CombinedPathBuf::Absolute(AbsolutePathBuf::current_dir().join("bar/baz.txt")?);
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (?1, ?2, ?3)",
+            params![1, x, None::<CombinedPathBuf>],
+        )?;
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (?1, ?2, ?3)",
+            params![2, x, y],
+        )?;
+
+        let mut statement = connection.prepare("SELECT x, y FROM test_files ORDER BY id")?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(CombinedPathBuf, Option<CombinedPathBuf>)>>>()?;
+        assert_eq!(vec![(x.clone(), None), (x, Some(y))], rows);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        use rusqlite::ToSql;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = CombinedPathBuf::try_new(name)?;
+        assert!(matches!(
+            path.to_sql(),
+            Err(rusqlite::Error::ToSqlConversionFailure(_))
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod test_postgres {
+    use bytes::BytesMut;
+    use postgres_types::FromSql;
+    use postgres_types::ToSql;
+    use postgres_types::Type;
+
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+    use crate::NotUtf8;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = CombinedPathBuf::Absolute(AbsolutePathBuf::current_dir().join("foo/bar.txt")?);
+        let mut buf = BytesMut::new();
+        path.to_sql_checked(&Type::TEXT, &mut buf).unwrap();
+        assert_eq!(path, CombinedPathBuf::from_sql(&Type::TEXT, &buf).unwrap());
+        assert!(<CombinedPathBuf as FromSql>::accepts(&Type::VARCHAR));
+        assert!(path.to_sql_checked(&Type::INT4, &mut buf).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = CombinedPathBuf::try_new(name)?;
+        assert!(matches!(
+            path.to_sql_checked(&Type::TEXT, &mut BytesMut::new()),
+            Err(e) if e.is::<NotUtf8>()
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "schemars"))]
+mod test_schemars {
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn path_buf_schema() -> anyhow::Result<()> {
+        let schema = serde_json::to_value(schemars::schema_for!(CombinedPathBuf))?;
+        assert_eq!("string", schema["type"]);
+        assert_eq!("path", schema["format"]);
+        assert!(schema["description"].is_string());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod test_arbitrary {
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn generates_valid_paths() -> anyhow::Result<()> {
+        let paths: Vec<CombinedPathBuf> = crate::fuzzing::generate(200)?;
+        for path in &paths {
+            assert_eq!(path, &CombinedPathBuf::try_new(path.as_path())?);
+        }
+        assert!(paths.iter().any(|p| p.as_path().components().count() > 2));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rkyv"))]
+mod test_rkyv {
+    use rkyv::rancor::Error;
+
+    use crate::ArchivedCombinedPathBuf;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = CombinedPathBuf::try_new("foo/bar.txt")?;
+        let bytes = rkyv::to_bytes::<Error>(&path)?;
+        let archived = rkyv::access::<ArchivedCombinedPathBuf, Error>(&bytes)?;
+        assert_eq!(path.as_path(), archived.as_combined_path().as_path());
+        assert_eq!(path, rkyv::deserialize::<CombinedPathBuf, Error>(archived)?);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = CombinedPathBuf::try_new(name)?;
+        assert!(rkyv::to_bytes::<Error>(&path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "camino"))]
+mod test_camino {
+    use camino::Utf8Path;
+    use camino::Utf8PathBuf;
+
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPath;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn converts_utf8_paths() -> anyhow::Result<()> {
+        let relative = CombinedPathBuf::try_new("../foo")?;
+        let absolute = CombinedPathBuf::from(AbsolutePathBuf::current_dir());
+        for path in [relative, absolute] {
+            let utf8 = path.as_utf8().expect("the path is utf8");
+            assert_eq!(path.as_path(), utf8.as_std_path());
+            assert_eq!(path.as_path(), <&CombinedPath>::try_from(utf8)?.as_path());
+            assert_eq!(path, CombinedPathBuf::try_from(utf8)?);
+            assert_eq!(path, CombinedPathBuf::try_from(utf8.to_path_buf())?);
+            assert_eq!(utf8.to_path_buf(), Utf8PathBuf::try_from(path)?);
+        }
+
+        assert!(<&CombinedPath>::try_from(Utf8Path::new("foo/../bar")).is_ok());
+        assert!(CombinedPathBuf::try_from(Utf8Path::new("/..")).is_err());
+        Ok(())
+    }
 }
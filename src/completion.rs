@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use crate::AbsolutePath;
+use crate::CombinedPathBuf;
+
+/// List the filesystem entries that could complete `partial`, e.g. for a REPL or a CLI's shell
+/// completion.
+///
+/// Everything after the last separator in `partial` is treated as a partially typed file name,
+/// and everything before it as the directory to search. Relative directories are resolved
+/// against `base`, but the returned paths keep the same form as `partial` (so relative input
+/// produces relative completions). Hidden entries (those starting with `.`) are only returned
+/// if the partial file name also starts with `.`.
+///
+/// Results are sorted, and any directory that cannot be read yields no completions.
+pub fn complete_path(partial: &str, base: &AbsolutePath) -> Vec<CombinedPathBuf> {
+    let split_at = partial
+        .rfind(|c: char| std::path::is_separator(c))
+        .map_or(0, |idx| idx + 1);
+    let (dir_part, file_prefix) = partial.split_at(split_at);
+
+    let dir = if dir_part.is_empty() {
+        base.into()
+    } else {
+        match CombinedPathBuf::try_new(dir_part).and_then(|d| d.try_into_absolute(base)) {
+            Ok(dir) => dir,
+            Err(_) => return vec![],
+        }
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let show_hidden = file_prefix.starts_with('.');
+    let mut names: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name())
+        .filter(|name| {
+            let bytes = name.as_encoded_bytes();
+            bytes.starts_with(file_prefix.as_bytes()) && (show_hidden || !bytes.starts_with(b"."))
+        })
+        .collect();
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let mut completion = PathBuf::from(dir_part);
+            completion.push(name);
+            CombinedPathBuf::try_new(completion).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::complete_path;
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn completes_partial_paths() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        for dir in ["foo", "food", "bar", ".foo_hidden", "foo/baz", "foo/.quz"] {
+            std::fs::create_dir(root.join(dir)?)?;
+        }
+        std::fs::write(root.join("foo.txt")?, "")?;
+
+        let completions = |partial: &str| complete_path(partial, &root);
+        let expected = |paths: &[&str]| -> anyhow::Result<Vec<CombinedPathBuf>> {
+            Ok(paths
+                .iter()
+                .map(CombinedPathBuf::try_new)
+                .collect::<Result<_, _>>()?)
+        };
+
+        assert_eq!(
+            expected(&["bar", "foo", "foo.txt", "food"])?,
+            completions("")
+        );
+        assert_eq!(expected(&["foo", "foo.txt", "food"])?, completions("fo"));
+        assert_eq!(expected(&[".foo_hidden"])?, completions("."));
+        assert_eq!(expected(&["foo/baz"])?, completions("foo/"));
+        assert_eq!(expected(&["foo/.quz"])?, completions("foo/."));
+        assert_eq!(expected(&[])?, completions("missing/"));
+        assert_eq!(expected(&[])?, completions("zzz"));
+
+        let absolute = format!("{}/fo", root.display());
+        assert_eq!(
+            expected(&[
+                &format!("{}/foo", root.display()),
+                &format!("{}/foo.txt", root.display()),
+                &format!("{}/food", root.display()),
+            ])?,
+            completions(&absolute)
+        );
+        Ok(())
+    }
+}
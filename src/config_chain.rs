@@ -0,0 +1,105 @@
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// The configuration files with a given name in a directory and all of its ancestors, as used
+/// by hierarchical config formats like `.editorconfig`.
+///
+/// Files are ordered from the filesystem root down to the starting directory, so the nearest
+/// file comes last, and settings can be applied in order with later files taking precedence.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DirectoryConfigChain {
+    files: Vec<AbsolutePathBuf>,
+}
+
+impl DirectoryConfigChain {
+    /// Find every file named `file_name` in `start` and its ancestors.
+    ///
+    /// If `start` is a file, the search starts from the directory containing it. Entries with
+    /// the name that are not files (e.g. directories) are skipped.
+    pub fn collect(start: &AbsolutePath, file_name: &str) -> Self {
+        let mut dir = if start.is_file() {
+            start.parent()
+        } else {
+            Some(start)
+        };
+        let mut files = vec![];
+        while let Some(current) = dir {
+            if let Ok(candidate) = current.join(file_name) {
+                if candidate.is_file() {
+                    files.push(candidate);
+                }
+            }
+            dir = current.parent();
+        }
+        files.reverse();
+        Self { files }
+    }
+
+    /// The files that were found, with the nearest last.
+    pub fn files(&self) -> &[AbsolutePathBuf] {
+        &self.files
+    }
+
+    /// The file closest to the starting directory, if any were found.
+    pub fn nearest(&self) -> Option<&AbsolutePath> {
+        self.files.last().map(|f| f.as_absolute_path())
+    }
+
+    /// Whether no files were found.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl IntoIterator for DirectoryConfigChain {
+    type Item = AbsolutePathBuf;
+    type IntoIter = std::vec::IntoIter<AbsolutePathBuf>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::DirectoryConfigChain;
+
+    #[test]
+    fn collects_ancestor_configs() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let nested = root.join("a/b/c")?;
+        std::fs::create_dir_all(&nested)?;
+        std::fs::write(root.join(".editorconfig")?, "")?;
+        std::fs::write(root.join("a/b/.editorconfig")?, "")?;
+        std::fs::create_dir(root.join("a/.editorconfig")?)?;
+        std::fs::write(nested.join("main.rs")?, "")?;
+
+        let chain = DirectoryConfigChain::collect(&nested, ".editorconfig");
+        let in_root: Vec<_> = chain
+            .files()
+            .iter()
+            .filter(|f| f.starts_with(&root))
+            .cloned()
+            .collect();
+        assert_eq!(
+            vec![root.join(".editorconfig")?, root.join("a/b/.editorconfig")?],
+            in_root
+        );
+        assert_eq!(
+            Some(root.join("a/b/.editorconfig")?.as_absolute_path()),
+            chain.nearest()
+        );
+        assert_eq!(
+            chain,
+            DirectoryConfigChain::collect(&nested.join("main.rs")?, ".editorconfig")
+        );
+        assert_eq!(
+            Some(root.join(".editorconfig")?.as_absolute_path()),
+            DirectoryConfigChain::collect(&root.join("a")?, ".editorconfig").nearest()
+        );
+        assert!(DirectoryConfigChain::collect(&nested, ".missing-config").is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+use std::ffi::OsString;
+
+use crate::AbsolutePathBuf;
+use crate::CombinedPathBuf;
+use crate::MissingConfig;
+
+/// Where a configuration file found by [`ConfigLocator`] came from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ConfigSource {
+    /// The path provided via [`ConfigLocator::cli_path`].
+    Cli,
+    /// The path in the locator's environment variable.
+    EnvVar,
+    /// The user's config directory (`$XDG_CONFIG_HOME`, `~/.config`, or `%APPDATA%`).
+    UserConfig,
+    /// A system-wide config directory (`$XDG_CONFIG_DIRS`, `/etc`, or `%PROGRAMDATA%`).
+    System,
+}
+
+/// Finds an application's configuration file by searching a fixed list of locations in order.
+///
+/// The search order is:
+/// - The path given on the command line, if any (relative paths are resolved against the cwd).
+/// - The path in the environment variable (`MYAPP_CONFIG` for an app named `myapp` by default).
+/// - `<user config dir>/<app>/<file name>`
+/// - `<system config dir>/<app>/<file name>` for each system config dir.
+///
+/// The first of these that is an existing file wins. The command line and environment variable
+/// are explicit choices, so if either is set, it must exist, and the later locations are not
+/// searched.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigLocator {
+    app_name: String,
+    file_name: String,
+    env_var: String,
+    cli_path: Option<CombinedPathBuf>,
+    system_dirs: Option<Vec<AbsolutePathBuf>>,
+}
+
+impl ConfigLocator {
+    /// Create a locator for `app_name`, looking for a file named `config.toml`.
+    pub fn new(app_name: &str) -> Self {
+        let env_var = format!("{}_CONFIG", app_name.to_uppercase().replace('-', "_"));
+        Self {
+            app_name: app_name.to_owned(),
+            file_name: "config.toml".to_owned(),
+            env_var,
+            cli_path: None,
+            system_dirs: None,
+        }
+    }
+
+    /// Set the name of the file to look for inside of the config directories.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = file_name.to_owned();
+        self
+    }
+
+    /// Set the environment variable that may contain the path to the config file.
+    pub fn env_var(mut self, env_var: &str) -> Self {
+        self.env_var = env_var.to_owned();
+        self
+    }
+
+    /// Set the path that was provided on the command line, if any.
+    pub fn cli_path(mut self, cli_path: Option<CombinedPathBuf>) -> Self {
+        self.cli_path = cli_path;
+        self
+    }
+
+    /// Override the platform's default system config directories.
+    pub fn system_dirs(mut self, system_dirs: Vec<AbsolutePathBuf>) -> Self {
+        self.system_dirs = Some(system_dirs);
+        self
+    }
+
+    /// All of the locations that would be searched, in order, whether they exist or not.
+    ///
+    /// Fails if the path from the command line or environment variable cannot be resolved to an
+    /// absolute path, e.g. `../..` from `/`, rather than leaving it out.
+    pub fn candidates(&self) -> Result<Vec<(AbsolutePathBuf, ConfigSource)>, MissingConfig> {
+        self.candidates_with_env(|name| std::env::var_os(name))
+    }
+
+    /// Find the first candidate that is an existing file, and which source it came from.
+    ///
+    /// Fails if the path from the command line or environment variable cannot be resolved, or
+    /// is not an existing file, rather than falling back to the config directories.
+    pub fn locate(&self) -> Result<Option<(AbsolutePathBuf, ConfigSource)>, MissingConfig> {
+        self.locate_with_env(|name| std::env::var_os(name))
+    }
+
+    fn locate_with_env<F>(
+        &self,
+        env: F,
+    ) -> Result<Option<(AbsolutePathBuf, ConfigSource)>, MissingConfig>
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        for (path, source) in self.candidates_with_env(env)? {
+            if path.is_file() {
+                return Ok(Some((path, source)));
+            }
+            if matches!(source, ConfigSource::Cli | ConfigSource::EnvVar) {
+                return Err(MissingConfig(path.to_string_lossy().into_owned(), source));
+            }
+        }
+        Ok(None)
+    }
+
+    fn candidates_with_env<F>(
+        &self,
+        env: F,
+    ) -> Result<Vec<(AbsolutePathBuf, ConfigSource)>, MissingConfig>
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        let mut candidates = vec![];
+        if let Some(cli_path) = &self.cli_path {
+            let path = cli_path.try_into_absolute_in_cwd().map_err(|_| {
+                MissingConfig(cli_path.as_path().display().to_string(), ConfigSource::Cli)
+            })?;
+            candidates.push((path, ConfigSource::Cli));
+        }
+
+        if let Some(value) = env(&self.env_var).filter(|value| !value.is_empty()) {
+            let path = CombinedPathBuf::try_new(&value)
+                .ok()
+                .and_then(|path| path.try_into_absolute_in_cwd().ok())
+                .ok_or_else(|| {
+                    MissingConfig(value.to_string_lossy().into_owned(), ConfigSource::EnvVar)
+                })?;
+            candidates.push((path, ConfigSource::EnvVar));
+        }
+
+        if let Some(dir) = user_config_dir(&env) {
+            if let Ok(path) = dir
+                .join(&self.app_name)
+                .and_then(|d| d.join(&self.file_name))
+            {
+                candidates.push((path, ConfigSource::UserConfig));
+            }
+        }
+
+        let system_dirs = match &self.system_dirs {
+            Some(dirs) => dirs.clone(),
+            None => system_config_dirs(&env),
+        };
+        for dir in system_dirs {
+            if let Ok(path) = dir
+                .join(&self.app_name)
+                .and_then(|d| d.join(&self.file_name))
+            {
+                candidates.push((path, ConfigSource::System));
+            }
+        }
+        Ok(candidates)
+    }
+}
+
+fn absolute_env<F>(env: &F, name: &str) -> Option<AbsolutePathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    env(name).and_then(|value| AbsolutePathBuf::try_new(value).ok())
+}
+
+#[cfg(windows)]
+fn user_config_dir<F>(env: &F) -> Option<AbsolutePathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    absolute_env(env, "APPDATA")
+}
+
+#[cfg(not(windows))]
+fn user_config_dir<F>(env: &F) -> Option<AbsolutePathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    absolute_env(env, "XDG_CONFIG_HOME")
+        .or_else(|| absolute_env(env, "HOME").and_then(|home| home.join(".config").ok()))
+}
+
+#[cfg(windows)]
+fn system_config_dirs<F>(env: &F) -> Vec<AbsolutePathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    absolute_env(env, "PROGRAMDATA").into_iter().collect()
+}
+
+#[cfg(not(windows))]
+fn system_config_dirs<F>(env: &F) -> Vec<AbsolutePathBuf>
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let mut dirs: Vec<_> = match env("XDG_CONFIG_DIRS").filter(|value| !value.is_empty()) {
+        Some(value) => std::env::split_paths(&value)
+            .filter_map(|p| AbsolutePathBuf::try_new(p).ok())
+            .collect(),
+        None => vec![AbsolutePathBuf::new_unchecked("/etc/xdg")],
+    };
+    dirs.push(AbsolutePathBuf::new_unchecked("/etc"));
+    dirs
+}
+
+#[cfg(all(test, not(windows)))]
+mod test {
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+    use crate::ConfigLocator;
+    use crate::ConfigSource;
+    use crate::MissingConfig;
+
+    #[test]
+    fn searches_in_order() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path())?;
+        let cli = root.join("cli.toml")?;
+        let from_env = root.join("env.toml")?;
+        let user = root.join("home/.config/my-app/settings.toml")?;
+        let system = root.join("etc/my-app/settings.toml")?;
+
+        let env = HashMap::from([
+            ("MY_APP_CONFIG", OsString::from(from_env.as_path())),
+            ("HOME", OsString::from(root.join("home")?.as_path())),
+        ]);
+        let locator = ConfigLocator::new("my-app")
+            .file_name("settings.toml")
+            .cli_path(Some(CombinedPathBuf::try_new(cli.as_path())?))
+            .system_dirs(vec![root.join("etc")?]);
+
+        assert_eq!(
+            vec![
+                (cli.clone(), ConfigSource::Cli),
+                (from_env.clone(), ConfigSource::EnvVar),
+                (user.clone(), ConfigSource::UserConfig),
+                (system.clone(), ConfigSource::System),
+            ],
+            locator.candidates_with_env(|name| env.get(name).cloned())?
+        );
+
+        for (path, _) in locator.candidates_with_env(|name| env.get(name).cloned())? {
+            path.ensure_parent_exists()?;
+            std::fs::write(&path, "")?;
+        }
+        let without_cli = locator.clone().cli_path(None);
+        let mut without_env = env.clone();
+        without_env.remove("MY_APP_CONFIG");
+        let find = |locator: &ConfigLocator, env: &HashMap<&str, OsString>| {
+            locator.locate_with_env(|name| env.get(name).cloned())
+        };
+        assert_eq!(
+            Ok(Some((cli.clone(), ConfigSource::Cli))),
+            find(&locator, &env)
+        );
+        assert_eq!(
+            Ok(Some((from_env.clone(), ConfigSource::EnvVar))),
+            find(&without_cli, &env)
+        );
+        assert_eq!(
+            Ok(Some((user.clone(), ConfigSource::UserConfig))),
+            find(&without_cli, &without_env)
+        );
+        std::fs::remove_file(&user)?;
+        assert_eq!(
+            Ok(Some((system.clone(), ConfigSource::System))),
+            find(&without_cli, &without_env)
+        );
+        std::fs::remove_file(&system)?;
+        assert_eq!(Ok(None), find(&without_cli, &without_env));
+
+        // Explicit paths have to exist.
+        std::fs::remove_file(&cli)?;
+        std::fs::remove_file(&from_env)?;
+        assert_eq!(
+            Err(MissingConfig(
+                cli.to_string_lossy().into_owned(),
+                ConfigSource::Cli
+            )),
+            find(&locator, &env)
+        );
+        assert_eq!(
+            "missing_config",
+            find(&without_cli, &env).unwrap_err().code()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn locates_config_files() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path())?;
+        let cli = root.join("cli.toml")?;
+        let system = root.join("etc/paths-config-locator-test/config.toml")?;
+        system.ensure_parent_exists()?;
+        std::fs::write(&system, "")?;
+
+        let locator = ConfigLocator::new("paths-config-locator-test")
+            .env_var("PATHS_CONFIG_LOCATOR_TEST_UNSET")
+            .system_dirs(vec![root.join("etc")?]);
+        assert_eq!(Some((system, ConfigSource::System)), locator.locate()?);
+
+        let locator = locator.cli_path(Some(CombinedPathBuf::try_new(cli.as_path())?));
+        assert_eq!(ConfigSource::Cli, locator.locate().unwrap_err().1);
+        std::fs::write(&cli, "")?;
+        assert_eq!(Some((cli, ConfigSource::Cli)), locator.locate()?);
+        Ok(())
+    }
+
+    #[test]
+    fn default_dirs() -> anyhow::Result<()> {
+        let env = HashMap::from([
+            ("XDG_CONFIG_HOME", OsString::from("/xdg/config")),
+            ("XDG_CONFIG_DIRS", OsString::from("/first:relative:/second")),
+        ]);
+        let locator = ConfigLocator::new("app");
+
+        assert_eq!(
+            vec![
+                (
+                    AbsolutePathBuf::try_new("/xdg/config/app/config.toml")?,
+                    ConfigSource::UserConfig
+                ),
+                (
+                    AbsolutePathBuf::try_new("/first/app/config.toml")?,
+                    ConfigSource::System
+                ),
+                (
+                    AbsolutePathBuf::try_new("/second/app/config.toml")?,
+                    ConfigSource::System
+                ),
+                (
+                    AbsolutePathBuf::try_new("/etc/app/config.toml")?,
+                    ConfigSource::System
+                ),
+            ],
+            locator.candidates_with_env(|name| env.get(name).cloned())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unresolvable_explicit_paths() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path())?;
+        let system = root.join("etc/app/config.toml")?;
+        system.ensure_parent_exists()?;
+        std::fs::write(&system, "")?;
+
+        // More `..`s than the cwd has components, so it cannot be normalized.
+        let above_root = "../".repeat(std::env::current_dir()?.components().count() + 1);
+        let env = HashMap::from([("APP_CONFIG", OsString::from(&above_root))]);
+        let locator = ConfigLocator::new("app").system_dirs(vec![root.join("etc")?]);
+        assert_eq!(
+            Err(MissingConfig(above_root.clone(), ConfigSource::EnvVar)),
+            locator.locate_with_env(|name| env.get(name).cloned())
+        );
+
+        let locator = locator.cli_path(Some(CombinedPathBuf::try_new(&above_root)?));
+        assert_eq!(
+            ConfigSource::Cli,
+            locator.locate_with_env(|_| None).unwrap_err().1
+        );
+        assert!(locator.candidates_with_env(|_| None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// Remove duplicate paths, keeping the first occurrence of each and otherwise preserving order.
+///
+/// As [`AbsolutePathBuf`]s are always normalized, e.g. `/foo/../bar` and `/bar` given on a
+/// command line are duplicates once they have been converted.
+pub fn dedup_normalized(paths: Vec<AbsolutePathBuf>) -> Vec<AbsolutePathBuf> {
+    let mut seen = HashSet::with_capacity(paths.len());
+    paths
+        .into_iter()
+        .filter(|p| seen.insert(p.clone()))
+        .collect()
+}
+
+/// Remove paths that are inside of another path in `paths`, e.g. so that `src` and `src/lib.rs`
+/// are only processed once, as `src`.
+///
+/// Duplicates are also removed. The remaining paths keep their original order. This only
+/// compares the paths, and does not resolve symlinks.
+pub fn remove_covered_by_ancestors(paths: Vec<AbsolutePathBuf>) -> Vec<AbsolutePathBuf> {
+    let all: HashSet<&AbsolutePath> = paths.iter().map(|p| p.as_absolute_path()).collect();
+    let covered: HashSet<&AbsolutePath> = paths
+        .iter()
+        .filter(|p| p.ancestors().skip(1).any(|a| all.contains(a)))
+        .map(|p| p.as_absolute_path())
+        .collect();
+    let mut seen = HashSet::with_capacity(paths.len());
+    let kept: Vec<_> = paths
+        .iter()
+        .map(|p| !covered.contains(p.as_absolute_path()) && seen.insert(p))
+        .collect();
+    paths
+        .into_iter()
+        .zip(kept)
+        .filter_map(|(p, keep)| keep.then_some(p))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dedup_normalized;
+    use crate::remove_covered_by_ancestors;
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn dedups_paths() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let paths = |names: &[&str]| -> anyhow::Result<Vec<AbsolutePathBuf>> {
+            names.iter().map(|n| Ok(cwd.join(n)?)).collect()
+        };
+
+        assert_eq!(
+            paths(&["b", "a", "c"])?,
+            dedup_normalized(paths(&["b", "a", "x/../b", "c", "a"])?)
+        );
+        assert_eq!(
+            paths(&["src", "tests/a.rs", "srcs", "docs"])?,
+            remove_covered_by_ancestors(paths(&[
+                "src/lib.rs",
+                "src",
+                "tests/a.rs",
+                "src/nested/mod.rs",
+                "srcs",
+                "src",
+                "docs",
+                "docs/x/y",
+            ])?)
+        );
+        assert!(remove_covered_by_ancestors(vec![]).is_empty());
+        Ok(())
+    }
+}
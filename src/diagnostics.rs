@@ -0,0 +1,407 @@
+//! [`miette::Diagnostic`] impls for the error types, labelling the offending part of the path.
+
+use std::fmt::Display;
+use std::path::Path;
+
+use miette::Diagnostic;
+use miette::LabeledSpan;
+use miette::SourceCode;
+
+use crate::AbsoluteJoinError;
+use crate::AbsolutePathBufNewError;
+use crate::AbsolutePathNewError;
+use crate::AccessDenied;
+use crate::AliasCycle;
+use crate::AliasResolutionError;
+use crate::AlreadyRunning;
+use crate::BulkError;
+use crate::CombinedJoinError;
+use crate::InvalidEnvValue;
+use crate::InvalidFileName;
+use crate::InvalidGlobPattern;
+use crate::InvalidRewriteRule;
+use crate::JoinedAbsolute;
+use crate::Missing;
+use crate::NormalizationFailed;
+use crate::NotAbsolute;
+use crate::NotBeneathBase;
+use crate::NotRelative;
+use crate::NotUtf8;
+use crate::OverlappingRoots;
+use crate::PathIoError;
+use crate::PathListError;
+use crate::RelativeToError;
+use crate::SingleInstanceError;
+use crate::TenantScopeError;
+use crate::WasNotNormalized;
+
+/// The byte offset and text of each component in `path`, including empty ones between repeated
+/// separators.
+fn components(path: &str) -> impl Iterator<Item = (usize, &str)> {
+    path.split(std::path::is_separator)
+        .scan(0, |start, component| {
+            let this_start = *start;
+            *start += component.len() + 1;
+            Some((this_start, component))
+        })
+}
+
+fn label(start: usize, len: usize, text: &str) -> Option<Box<dyn Iterator<Item = LabeledSpan>>> {
+    Some(Box::new(std::iter::once(LabeledSpan::new(
+        Some(text.to_owned()),
+        start,
+        len,
+    ))))
+}
+
+impl Diagnostic for WasNotNormalized {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.suggestion()
+            .map(|s| Box::new(format!("use `{}`", s.as_path().display())) as Box<dyn Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (start, component) = components(&self.0).find(|(_, c)| *c == "." || *c == "..")?;
+        label(start, component.len(), "not allowed here")
+    }
+}
+
+impl Diagnostic for NormalizationFailed {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        // Find the `..` that goes above the start of the path.
+        let mut all = components(&self.0);
+        if Path::new(&self.0).has_root() {
+            all.next();
+        }
+        let mut depth = 0usize;
+        for (start, component) in all {
+            match component {
+                "" | "." => {}
+                ".." => match depth.checked_sub(1) {
+                    Some(d) => depth = d,
+                    None => return label(start, component.len(), "goes above the root"),
+                },
+                _ => depth += 1,
+            }
+        }
+        None
+    }
+}
+
+impl Diagnostic for NotAbsolute {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        label(0, self.0.len(), "relative path")
+    }
+}
+
+impl Diagnostic for NotRelative {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        // Point at the root (and prefix on windows), rather than the whole path.
+        let len = components(&self.0)
+            .find(|(_, c)| !c.is_empty() && !c.ends_with(':'))
+            .map_or(self.0.len(), |(start, _)| start);
+        label(0, len, "absolute")
+    }
+}
+
+impl Diagnostic for JoinedAbsolute {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.1)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        label(0, self.1.len(), "absolute path")
+    }
+}
+
+impl Diagnostic for InvalidFileName {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        match self
+            .0
+            .char_indices()
+            .find(|(_, c)| std::path::is_separator(*c))
+        {
+            Some((start, c)) => label(start, c.len_utf8(), "separator"),
+            None => label(0, self.0.len(), "not a file name"),
+        }
+    }
+}
+
+impl Diagnostic for InvalidGlobPattern {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        label(0, self.0.len(), &self.1)
+    }
+}
+
+impl Diagnostic for InvalidRewriteRule {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.0)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        label(0, self.0.len(), &self.1)
+    }
+}
+
+impl Diagnostic for AliasCycle {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for AccessDenied {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for NotBeneathBase {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for NotUtf8 {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for InvalidEnvValue {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for Missing {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for AlreadyRunning {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for BulkError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        Some(Box::new(self.0.iter().map(|(_, e)| e as &dyn Diagnostic)))
+    }
+}
+
+impl Diagnostic for OverlappingRoots {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for PathIoError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for RelativeToError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for SingleInstanceError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        match self {
+            SingleInstanceError::AlreadyRunning(e) => Diagnostic::code(e),
+            SingleInstanceError::Io(_) => Some(Box::new(self.code())),
+        }
+    }
+}
+
+impl Diagnostic for TenantScopeError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+}
+
+impl Diagnostic for PathListError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.code()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        match self {
+            PathListError::NormalizationFailed(_, e) => e.source_code(),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        match self {
+            PathListError::NormalizationFailed(_, e) => e.labels(),
+            _ => None,
+        }
+    }
+}
+
+/// Implement [`Diagnostic`] for enums that only wrap other diagnostics, by forwarding to the
+/// wrapped error.
+macro_rules! forward_diagnostic {
+    ($($error:ident { $($variant:ident),* })*) => {
+        $(
+            impl $error {
+                fn inner(&self) -> &dyn Diagnostic {
+                    match self {
+                        $($error::$variant(e) => e,)*
+                    }
+                }
+            }
+
+            impl Diagnostic for $error {
+                fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+                    self.inner().code()
+                }
+
+                fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+                    self.inner().help()
+                }
+
+                fn source_code(&self) -> Option<&dyn SourceCode> {
+                    self.inner().source_code()
+                }
+
+                fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+                    self.inner().labels()
+                }
+            }
+        )*
+    };
+}
+
+forward_diagnostic! {
+    AbsolutePathNewError { WasNotNormalized, NotAbsolute }
+    AbsolutePathBufNewError { NormalizationFailed, NotAbsolute }
+    AbsoluteJoinError { NormalizationFailed, JoinedAbsolute }
+    CombinedJoinError { NormalizationFailed, JoinedAbsolute }
+    AliasResolutionError { AliasCycle, NormalizationFailed, NotAbsolute }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::Diagnostic;
+    use miette::SourceSpan;
+
+    use crate::AbsolutePathBuf;
+    use crate::AbsolutePathNewError;
+    use crate::InvalidFileName;
+    use crate::NormalizationFailed;
+    use crate::WasNotNormalized;
+
+    fn spans(diagnostic: &dyn Diagnostic) -> Vec<(Option<String>, SourceSpan)> {
+        diagnostic
+            .labels()
+            .into_iter()
+            .flatten()
+            .map(|l| (l.label().map(str::to_owned), *l.inner()))
+            .collect()
+    }
+
+    #[test]
+    fn labels_bad_components() -> anyhow::Result<()> {
+        let err = AbsolutePathNewError::from(WasNotNormalized("/foo/bar/../baz".to_owned()));
+        assert_eq!(
+            Some("was_not_normalized"),
+            Diagnostic::code(&err).map(|c| c.to_string()).as_deref()
+        );
+        assert_eq!(
+            vec![(Some("not allowed here".to_owned()), (9, 2).into())],
+            spans(&err)
+        );
+        assert!(err.source_code().is_some());
+
+        let cwd = AbsolutePathBuf::current_dir();
+        let err = WasNotNormalized(cwd.as_path().join("foo/../bar").display().to_string());
+        assert_eq!(
+            Some(format!("use `{}`", cwd.join("bar")?.as_path().display())),
+            err.help().map(|h| h.to_string())
+        );
+
+        let err = NormalizationFailed("/foo/../../bar".to_owned());
+        assert_eq!(
+            vec![(Some("goes above the root".to_owned()), (8, 2).into())],
+            spans(&err)
+        );
+        let err = NormalizationFailed("foo/../..".to_owned());
+        assert_eq!(
+            vec![(Some("goes above the root".to_owned()), (7, 2).into())],
+            spans(&err)
+        );
+
+        let err = InvalidFileName("foo/bar".to_owned());
+        assert_eq!(
+            vec![(Some("separator".to_owned()), (3, 1).into())],
+            spans(&err)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::os::fd::AsFd;
+use std::os::fd::AsRawFd;
+use std::os::fd::BorrowedFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::fd::RawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Component;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::RelativePath;
+
+/// An open handle to a directory, used to access files beneath it with `openat(2)`.
+///
+/// Because lookups are done relative to the open handle rather than by path, renaming or
+/// replacing any of the directory's ancestors after it has been opened cannot redirect access
+/// elsewhere. Relative paths used with a [`DirHandle`] may not contain `..` components.
+///
+/// Symlinks can never be used to leave the directory. On linux 5.6 and later, `openat2(2)` is
+/// used with `RESOLVE_BENEATH`, so symlinks are only followed if they stay beneath the
+/// directory. Elsewhere, paths are opened one component at a time with `O_NOFOLLOW`, so any
+/// symlink is an error.
+#[derive(Debug)]
+pub struct DirHandle {
+    fd: OwnedFd,
+    path: AbsolutePathBuf,
+}
+
+impl DirHandle {
+    /// Open the directory at `path`.
+    pub fn open(path: &AbsolutePath) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY)
+            .open(path)?;
+        Ok(Self {
+            fd: file.into(),
+            path: path.into(),
+        })
+    }
+
+    /// The path that this directory was opened from.
+    ///
+    /// If the directory has since been moved, this will no longer refer to it.
+    pub fn path(&self) -> &AbsolutePath {
+        self.path.as_absolute_path()
+    }
+
+    /// A path that refers to this handle through the `/proc/self/fd` (or `/dev/fd`) filesystem.
+    ///
+    /// This is useful for passing the open directory to APIs and child processes that only
+    /// accept paths.
+    pub fn proc_path(&self) -> AbsolutePathBuf {
+        let fd_dir = if cfg!(target_os = "linux") {
+            "/proc/self/fd"
+        } else {
+            "/dev/fd"
+        };
+        AbsolutePathBuf::new_unchecked(format!("{}/{}", fd_dir, self.fd.as_raw_fd()))
+    }
+
+    /// Open an existing file beneath this directory for reading.
+    pub fn open_relative(&self, path: &RelativePath) -> std::io::Result<File> {
+        self.openat(path, libc::O_RDONLY).map(File::from)
+    }
+
+    /// Create (or truncate) a file beneath this directory, and open it for writing.
+    pub fn create_relative(&self, path: &RelativePath) -> std::io::Result<File> {
+        self.openat(path, libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC)
+            .map(File::from)
+    }
+
+    /// Open a directory beneath this directory.
+    pub fn open_dir_relative(&self, path: &RelativePath) -> std::io::Result<DirHandle> {
+        let fd = self.openat(path, libc::O_RDONLY | libc::O_DIRECTORY)?;
+        let path = self
+            .path
+            .join_relative(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        Ok(DirHandle { fd, path })
+    }
+
+    fn openat(&self, path: &RelativePath, flags: libc::c_int) -> std::io::Result<OwnedFd> {
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("`{}` may not contain `..`", path.display()),
+            ));
+        }
+        if path.as_os_str().is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "an empty path cannot be opened",
+            ));
+        }
+        let flags = flags | libc::O_CLOEXEC;
+        #[cfg(target_os = "linux")]
+        match openat2_beneath(self.fd.as_fd(), &c_string(path.as_os_str())?, flags) {
+            // Kernels before 5.6 do not have `openat2`, and some seccomp filters block it.
+            Err(e) if matches!(e.raw_os_error(), Some(libc::ENOSYS) | Some(libc::EPERM)) => {}
+            result => return result,
+        }
+        self.openat_nofollow(path, flags)
+    }
+
+    /// Open `path` one component at a time, failing if any of them is a symlink.
+    fn openat_nofollow(&self, path: &RelativePath, flags: libc::c_int) -> std::io::Result<OwnedFd> {
+        let names: Vec<&OsStr> = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        let Some((last, dirs)) = names.split_last() else {
+            return raw_openat(self.fd.as_fd(), c".", flags);
+        };
+        let mut dir: Option<OwnedFd> = None;
+        for name in dirs {
+            let parent = dir.as_ref().map_or(self.fd.as_fd(), |fd| fd.as_fd());
+            let next = raw_openat(
+                parent,
+                &c_string(name)?,
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            )?;
+            dir = Some(next);
+        }
+        let parent = dir.as_ref().map_or(self.fd.as_fd(), |fd| fd.as_fd());
+        raw_openat(parent, &c_string(last)?, flags | libc::O_NOFOLLOW)
+    }
+}
+
+fn c_string(value: &OsStr) -> std::io::Result<CString> {
+    CString::new(value.as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+fn raw_openat(dir: BorrowedFd, path: &CStr, flags: libc::c_int) -> std::io::Result<OwnedFd> {
+    // SAFETY: `path` is a valid nul terminated string, and `dir` is an open fd.
+    let fd = unsafe { libc::openat(dir.as_raw_fd(), path.as_ptr(), flags, 0o666 as libc::c_uint) };
+    if fd < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        // SAFETY: `openat` returned a new fd that nothing else owns.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+}
+
+/// `openat2(2)`, refusing to resolve `path` to anything outside of `dir`.
+#[cfg(target_os = "linux")]
+fn openat2_beneath(dir: BorrowedFd, path: &CStr, flags: libc::c_int) -> std::io::Result<OwnedFd> {
+    // SAFETY: `open_how` is plain integers, for which zero is valid.
+    let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+    how.flags = flags as u64;
+    if flags & libc::O_CREAT != 0 {
+        how.mode = 0o666;
+    }
+    how.resolve = libc::RESOLVE_BENEATH | libc::RESOLVE_NO_MAGICLINKS;
+    // SAFETY: `path` is a valid nul terminated string, `dir` is an open fd, and `how` is an
+    // `open_how` of the size that is passed.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_openat2,
+            dir.as_raw_fd(),
+            path.as_ptr(),
+            &how as *const libc::open_how,
+            std::mem::size_of::<libc::open_how>(),
+        )
+    };
+    if fd < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        // SAFETY: `openat2` returned a new fd that nothing else owns.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+    }
+}
+
+impl AsFd for DirHandle {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for DirHandle {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::io::Write;
+
+    use crate::AbsolutePathBuf;
+    use crate::DirHandle;
+    use crate::RelativePath;
+
+    #[test]
+    fn opens_relative_to_handle() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::create_dir(root.join("foo")?)?;
+
+        let handle = DirHandle::open(&root)?;
+        assert_eq!(root.as_absolute_path(), handle.path());
+
+        handle
+            .create_relative(RelativePath::new_unchecked("foo/bar.txt"))?
+            .write_all(b"contents")?;
+        let mut contents = String::new();
+        handle
+            .open_relative(RelativePath::new_unchecked("./foo/bar.txt"))?
+            .read_to_string(&mut contents)?;
+        assert_eq!("contents", contents);
+
+        let foo = handle.open_dir_relative(RelativePath::new_unchecked("foo"))?;
+        assert_eq!(root.join("foo")?.as_absolute_path(), foo.path());
+        assert!(foo
+            .open_relative(RelativePath::new_unchecked("bar.txt"))
+            .is_ok());
+        assert!(foo
+            .open_relative(RelativePath::new_unchecked("../foo/bar.txt"))
+            .is_err());
+        assert!(handle
+            .open_dir_relative(RelativePath::new_unchecked("foo/bar.txt"))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_escape_through_symlinks() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let base = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let root = base.join("root")?;
+        std::fs::create_dir(&root)?;
+        std::fs::write(base.join("secret.txt")?, "secret")?;
+        std::os::unix::fs::symlink(base.join("secret.txt")?, root.join("absolute")?)?;
+        std::os::unix::fs::symlink("../secret.txt", root.join("relative")?)?;
+        std::os::unix::fs::symlink("..", root.join("parent")?)?;
+
+        let handle = DirHandle::open(&root)?;
+        for path in ["absolute", "relative", "parent/secret.txt"] {
+            assert!(handle
+                .open_relative(RelativePath::new_unchecked(path))
+                .is_err());
+        }
+        assert!(handle
+            .create_relative(RelativePath::new_unchecked("relative"))
+            .is_err());
+        assert_eq!("secret", std::fs::read_to_string(base.join("secret.txt")?)?);
+
+        std::fs::write(root.join("file.txt")?, "")?;
+        let nofollow =
+            |path: &str| handle.openat_nofollow(RelativePath::new_unchecked(path), libc::O_RDONLY);
+        assert!(nofollow("parent").is_err());
+        assert!(nofollow("relative").is_err());
+        assert!(nofollow("./file.txt").is_ok());
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_path() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::write(root.join("foo.txt")?, "")?;
+
+        let handle = DirHandle::open(&root)?;
+        assert!(handle.proc_path().join("foo.txt")?.exists());
+        Ok(())
+    }
+}
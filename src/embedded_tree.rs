@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::Component;
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// Include an [`EmbeddedTree`] generated by [`EmbeddedTree::generate`] in a build script.
+///
+/// `$file` is the name of the generated file in `OUT_DIR`.
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     let assets = paths::AbsolutePathBuf::current_dir().join("assets").unwrap();
+///     let out_dir = paths::AbsolutePathBuf::try_new(std::env::var("OUT_DIR").unwrap()).unwrap();
+///     println!("cargo:rerun-if-changed=assets");
+///     paths::EmbeddedTree::generate(&assets, &out_dir.join("assets.rs").unwrap()).unwrap();
+/// }
+///
+/// // main.rs
+/// static ASSETS: std::sync::LazyLock<paths::EmbeddedTree> =
+///     std::sync::LazyLock::new(|| paths::include_embedded_tree!("assets.rs"));
+/// ```
+#[macro_export]
+macro_rules! include_embedded_tree {
+    ($file:literal) => {
+        include!(concat!(env!("OUT_DIR"), "/", $file))
+    };
+}
+
+/// A read-only tree of files that is embedded in the binary, e.g. for a web server's assets.
+///
+/// Files are keyed by their normalized path relative to the root of the tree, and the methods
+/// mirror the reading half of [`crate::FileSystem`]. Directories only exist as the parents of
+/// files, so empty directories are not embedded. The root is the empty path.
+///
+/// Trees are usually generated from a directory by [`EmbeddedTree::generate`] in a build script,
+/// and included with [`include_embedded_tree!`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EmbeddedTree {
+    files: BTreeMap<RelativePathBuf, &'static [u8]>,
+}
+
+impl EmbeddedTree {
+    /// Create a tree from pairs of `/` separated relative paths and file contents.
+    ///
+    /// Panics if a path is not relative, is empty, or would be outside of the tree after being
+    /// normalized, e.g. `../file`. Paths from [`EmbeddedTree::generate`] are always valid.
+    pub fn from_static(files: &[(&'static str, &'static [u8])]) -> Self {
+        let files = files
+            .iter()
+            .map(|(path, contents)| {
+                let key = normalize(RelativePath::new_unchecked(path))
+                    .filter(|key| !key.as_os_str().is_empty())
+                    .unwrap_or_else(|| panic!("`{}` is not a path beneath the tree", path));
+                (key, *contents)
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Write Rust source for an [`EmbeddedTree`] with the files beneath `dir` to `out`, for
+    /// [`include_embedded_tree!`] to include.
+    ///
+    /// This is meant to be called from build scripts. Symlinks are followed, and the files are
+    /// embedded with `include_bytes!`, so they have to exist when the crate is compiled. Fails
+    /// if `dir` or a file name is not valid UTF-8.
+    pub fn generate(dir: &AbsolutePath, out: &AbsolutePath) -> std::io::Result<()> {
+        if dir.as_path().to_str().is_none() {
+            return Err(not_utf8(dir.as_path()));
+        }
+        let mut files = vec![];
+        let mut to_visit = vec![RelativePathBuf::try_new("").expect("an empty path is relative")];
+        while let Some(relative) = to_visit.pop() {
+            let current = dir
+                .join_relative(&relative)
+                .expect("entries are beneath the directory");
+            for entry in std::fs::read_dir(&current)? {
+                let entry = entry?;
+                let name = entry
+                    .file_name()
+                    .into_string()
+                    .map_err(|_| not_utf8(&entry.path()))?;
+                let child = relative
+                    .join(&name)
+                    .expect("a directory entry is a plain file name");
+                if std::fs::metadata(entry.path())?.is_dir() {
+                    to_visit.push(child);
+                } else {
+                    files.push(child);
+                }
+            }
+        }
+        files.sort();
+
+        let mut source = "::paths::EmbeddedTree::from_static(&[\n".to_owned();
+        for file in files {
+            let key = file
+                .components()
+                .map(|c| c.as_os_str().to_str().expect("names were checked"))
+                .collect::<Vec<_>>()
+                .join("/");
+            let path = dir.join(&file).expect("files are relative");
+            writeln!(
+                source,
+                "    ({:?}, include_bytes!({:?}) as &[u8]),",
+                key,
+                path.as_path()
+                    .to_str()
+                    .expect("the directory and names were checked")
+            )
+            .expect("writing to a string cannot fail");
+        }
+        source.push_str("])\n");
+        out.ensure_parent_exists()?;
+        crate::fs::write_atomic(out, source.as_bytes())
+    }
+
+    /// The contents of the file at `path`.
+    pub fn read(&self, path: &RelativePath) -> std::io::Result<&'static [u8]> {
+        normalize(path)
+            .and_then(|key| self.files.get(&key).copied())
+            .ok_or_else(|| not_found(path))
+    }
+
+    /// The entries in the directory at `path`, sorted.
+    pub fn read_dir(&self, path: &RelativePath) -> std::io::Result<Vec<RelativePathBuf>> {
+        let dir = normalize(path).ok_or_else(|| not_found(path))?;
+        let mut entries = BTreeSet::new();
+        for file in self.files.keys() {
+            if let Some(child) = file
+                .strip_prefix(&dir)
+                .ok()
+                .and_then(|rest| rest.components().next())
+                .filter(|_| file != &dir)
+            {
+                entries.insert(
+                    dir.join(child.as_os_str())
+                        .expect("a component is a plain file name"),
+                );
+            }
+        }
+        if entries.is_empty() && !dir.as_os_str().is_empty() {
+            return Err(if self.files.contains_key(&dir) {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotADirectory,
+                    format!("`{}` is not a directory", path.display()),
+                )
+            } else {
+                not_found(path)
+            });
+        }
+        Ok(entries.into_iter().collect())
+    }
+
+    /// Whether `path` is a file.
+    pub fn is_file(&self, path: &RelativePath) -> bool {
+        normalize(path).is_some_and(|key| self.files.contains_key(&key))
+    }
+
+    /// Whether `path` is a directory, i.e. the root or the parent of a file.
+    pub fn is_dir(&self, path: &RelativePath) -> bool {
+        normalize(path).is_some_and(|dir| {
+            dir.as_os_str().is_empty()
+                || self
+                    .files
+                    .keys()
+                    .any(|file| file != &dir && file.starts_with(&dir))
+        })
+    }
+
+    /// Whether anything exists at `path`.
+    pub fn exists(&self, path: &RelativePath) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+
+    /// Iterate over the files and their contents, sorted by path.
+    pub fn iter(&self) -> impl Iterator<Item = (&RelativePath, &'static [u8])> {
+        self.files
+            .iter()
+            .map(|(path, contents)| (path.as_ref(), *contents))
+    }
+
+    /// The number of files in the tree.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether the tree has no files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// Normalize `path`, or `None` if it is outside of the tree.
+fn normalize(path: &RelativePath) -> Option<RelativePathBuf> {
+    RelativePathBuf::try_new(path.to_path_buf())
+        .ok()
+        .filter(|key| key.components().next() != Some(Component::ParentDir))
+}
+
+fn not_found(path: &RelativePath) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("`{}` is not in the embedded tree", path.display()),
+    )
+}
+
+fn not_utf8(path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("`{}` is not valid UTF-8", path.display()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::EmbeddedTree;
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn reads_embedded_files() -> anyhow::Result<()> {
+        fn path(p: &str) -> &RelativePath {
+            RelativePath::new_unchecked(p)
+        }
+
+        let tree = EmbeddedTree::from_static(&[
+            ("index.html", b"<html>"),
+            ("css/site.css", b"body {}"),
+            ("./css/print/a.css", b""),
+        ]);
+        assert_eq!(3, tree.len());
+        assert_eq!(b"<html>", tree.read(path("index.html"))?);
+        assert_eq!(b"body {}", tree.read(path("css/../css/site.css"))?);
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            tree.read(path("missing.html")).unwrap_err().kind()
+        );
+        assert!(tree.read(path("../index.html")).is_err());
+
+        assert!(tree.is_file(path("css/print/a.css")));
+        assert!(tree.is_dir(path("css/print")));
+        assert!(tree.is_dir(path("")));
+        assert!(!tree.is_dir(path("index.html")));
+        assert!(!tree.exists(path("css/missing")));
+        assert_eq!(
+            vec![
+                RelativePathBuf::try_new("css")?,
+                RelativePathBuf::try_new("index.html")?
+            ],
+            tree.read_dir(path(""))?
+        );
+        assert_eq!(
+            vec![
+                RelativePathBuf::try_new("css/print")?,
+                RelativePathBuf::try_new("css/site.css")?
+            ],
+            tree.read_dir(path("css"))?
+        );
+        assert!(tree.read_dir(path("index.html")).is_err());
+        assert!(tree.read_dir(path("missing")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn generates_source() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let assets = root.join("assets")?;
+        std::fs::create_dir_all(assets.join("css")?)?;
+        std::fs::write(assets.join("index.html")?, "<html>")?;
+        std::fs::write(assets.join("css/site.css")?, "body {}")?;
+
+        let out = root.join("out/assets.rs")?;
+        EmbeddedTree::generate(&assets, &out)?;
+        let source = std::fs::read_to_string(&out)?;
+        let css = format!(
+            "{:?}",
+            assets.join("css/site.css")?.as_path().to_str().unwrap()
+        );
+        assert!(source.starts_with("::paths::EmbeddedTree::from_static(&[\n"));
+        assert!(source.contains(&format!(
+            "(\"css/site.css\", include_bytes!({}) as &[u8]),",
+            css
+        )));
+        assert!(source.find("css/site.css") < source.find("index.html"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8_dirs() -> anyhow::Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let dir = root.join(OsStr::from_bytes(b"assets\xff"))?;
+        let out = root.join("out/tree.rs")?;
+        if std::fs::create_dir(&dir).is_err() {
+            // Some filesystems, e.g. on macOS, only allow UTF-8 names.
+            return Ok(());
+        }
+        std::fs::write(dir.join("a.txt")?, "a")?;
+
+        let err = EmbeddedTree::generate(&dir, &out).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(!out.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! How paths are encoded by [`crate::AbsolutePath::to_env_value`], so that they can be passed to
+//! child processes as UTF-8 strings without losing anything.
+//!
+//! Valid UTF-8 is kept as is, apart from `%`, which is escaped as `%25`. On unix, bytes that are
+//! not valid UTF-8 are escaped as `%XX`. On windows, unpaired surrogates are escaped as `%uXXXX`.
+
+use std::ffi::OsStr;
+use std::ffi::OsString;
+
+pub(crate) fn encode(value: &OsStr) -> String {
+    let mut out = String::with_capacity(value.len());
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        for chunk in value.as_bytes().utf8_chunks() {
+            push_escaping_percent(&mut out, chunk.valid());
+            for byte in chunk.invalid() {
+                out.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+
+        for c in char::decode_utf16(value.encode_wide()) {
+            match c {
+                Ok(c) => push_escaping_percent(&mut out, c.encode_utf8(&mut [0; 4])),
+                Err(e) => out.push_str(&format!("%u{:04X}", e.unpaired_surrogate())),
+            }
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    push_escaping_percent(&mut out, &value.to_string_lossy());
+    out
+}
+
+fn push_escaping_percent(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '%' => out.push_str("%25"),
+            c => out.push(c),
+        }
+    }
+}
+
+const INVALID_ESCAPE: &str = "invalid `%` escape";
+
+/// Parse `hex`, which must only be hex digits, unlike for [`u32::from_str_radix`].
+fn parse_hex(hex: Option<&str>) -> Result<u32, &'static str> {
+    hex.filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or(INVALID_ESCAPE)
+}
+
+pub(crate) fn decode(value: &str) -> Result<OsString, &'static str> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        let mut bytes = Vec::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(idx) = rest.find('%') {
+            bytes.extend_from_slice(&rest.as_bytes()[..idx]);
+            let byte = parse_hex(rest.get(idx + 1..idx + 3))?;
+            bytes.push(byte as u8);
+            rest = &rest[idx + 3..];
+        }
+        bytes.extend_from_slice(rest.as_bytes());
+        Ok(OsString::from_vec(bytes))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+
+        let mut units = Vec::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(idx) = rest.find('%') {
+            units.extend(rest[..idx].encode_utf16());
+            if rest[idx + 1..].starts_with('u') {
+                let unit = parse_hex(rest.get(idx + 2..idx + 6))?;
+                units.push(unit as u16);
+                rest = &rest[idx + 6..];
+            } else if rest[idx + 1..].starts_with("25") {
+                units.push(u16::from(b'%'));
+                rest = &rest[idx + 3..];
+            } else {
+                return Err(INVALID_ESCAPE);
+            }
+        }
+        units.extend(rest.encode_utf16());
+        Ok(OsString::from_wide(&units))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let mut out = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(idx) = rest.find('%') {
+            out.push_str(&rest[..idx]);
+            if !rest[idx + 1..].starts_with("25") {
+                return Err(INVALID_ESCAPE);
+            }
+            out.push('%');
+            rest = &rest[idx + 3..];
+        }
+        out.push_str(rest);
+        Ok(out.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn round_trips_env_values() -> anyhow::Result<()> {
+        let path = AbsolutePathBuf::current_dir().join("100% done/ünïcode.txt")?;
+        let value = path.to_env_value();
+        assert!(
+            value.ends_with("/100%25 done/ünïcode.txt")
+                || value.ends_with("\\100%25 done\\ünïcode.txt")
+        );
+        assert_eq!(path, AbsolutePathBuf::from_env_value(&value)?);
+
+        assert_eq!(
+            "invalid_env_value",
+            AbsolutePathBuf::from_env_value("relative/path")
+                .unwrap_err()
+                .code()
+        );
+        assert!(AbsolutePathBuf::from_env_value(&format!("{}%", value)).is_err());
+        assert!(AbsolutePathBuf::from_env_value(&format!("{}%zz", value)).is_err());
+        assert!(AbsolutePathBuf::from_env_value(&format!("{}%+1", value)).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            let name = std::ffi::OsStr::from_bytes(b"invalid\xff\xfe.txt");
+            let path = AbsolutePathBuf::current_dir().join(name)?;
+            let value = path.to_env_value();
+            assert!(value.ends_with("/invalid%FF%FE.txt"));
+            assert_eq!(path, AbsolutePathBuf::from_env_value(&value)?);
+
+            std::env::set_var("PATHS_TEST_ENV_VALUE", &value);
+            let received = std::env::var("PATHS_TEST_ENV_VALUE")?;
+            assert_eq!(path, AbsolutePathBuf::from_env_value(&received)?);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStringExt;
+
+            let name = std::ffi::OsString::from_wide(&[0x61, 0xD800, 0x62]);
+            let path = AbsolutePathBuf::current_dir().join(&name)?;
+            let value = path.to_env_value();
+            assert!(value.ends_with("\\a%uD800b"));
+            assert_eq!(path, AbsolutePathBuf::from_env_value(&value)?);
+        }
+        Ok(())
+    }
+}
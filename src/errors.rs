@@ -1,11 +1,67 @@
+use itertools::Itertools;
+
+use crate::AbsolutePathBuf;
+use crate::ConfigSource;
+
+/// Implement `code()` for errors with a single kind.
+macro_rules! error_codes {
+    ($($error:ident => $code:literal,)*) => {
+        $(
+            impl $error {
+                /// A stable identifier for this kind of error, e.g. for mapping to user facing
+                /// diagnostics. This will not change between releases.
+                pub fn code(&self) -> &'static str {
+                    $code
+                }
+            }
+        )*
+    };
+}
+
+error_codes! {
+    JoinedAbsolute => "joined_absolute",
+    WasNotNormalized => "was_not_normalized",
+    NormalizationFailed => "normalization_failed",
+    NotAbsolute => "not_absolute",
+    NotRelative => "not_relative",
+    InvalidFileName => "invalid_file_name",
+    InvalidGlobPattern => "invalid_glob_pattern",
+    InvalidRewriteRule => "invalid_rewrite_rule",
+    AliasCycle => "alias_cycle",
+    AlreadyRunning => "already_running",
+    BulkError => "invalid_paths",
+    OverlappingRoots => "overlapping_roots",
+    PathIoError => "io",
+    AccessDenied => "access_denied",
+    NotBeneathBase => "not_beneath_base",
+    NotUtf8 => "not_utf8",
+    Missing => "missing",
+    InvalidEnvValue => "invalid_env_value",
+    MissingConfig => "missing_config",
+}
+
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
 #[error("Attempted to join `{}` to non-relative path `{}`", .0, .1)]
 pub struct JoinedAbsolute(pub String, pub String);
 
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
-#[error("`{}` must be normalized, but contained '.' or '..'", .0)]
+#[error("`{}` must be normalized, but contained '.' or '..'{}", .0, did_you_mean(.0))]
 pub struct WasNotNormalized(pub String);
 
+impl WasNotNormalized {
+    /// The normalized form of the path, if it can be normalized.
+    pub fn suggestion(&self) -> Option<AbsolutePathBuf> {
+        AbsolutePathBuf::try_new(&self.0).ok()
+    }
+}
+
+fn did_you_mean(path: &str) -> String {
+    match AbsolutePathBuf::try_new(path) {
+        Ok(normalized) => format!(" (did you mean `{}`?)", normalized.as_path().display()),
+        Err(_) => String::new(),
+    }
+}
+
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
 #[error("`{}` could not be normalized", .0)]
 pub struct NormalizationFailed(pub String);
@@ -19,6 +75,7 @@ pub struct NotAbsolute(pub String);
 pub struct NotRelative(pub String);
 
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum AbsolutePathNewError {
     #[error(transparent)]
     WasNotNormalized(WasNotNormalized),
@@ -26,6 +83,16 @@ pub enum AbsolutePathNewError {
     NotAbsolute(NotAbsolute),
 }
 
+impl AbsolutePathNewError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AbsolutePathNewError::WasNotNormalized(e) => e.code(),
+            AbsolutePathNewError::NotAbsolute(e) => e.code(),
+        }
+    }
+}
+
 impl From<WasNotNormalized> for AbsolutePathNewError {
     fn from(e: WasNotNormalized) -> Self {
         AbsolutePathNewError::WasNotNormalized(e)
@@ -38,6 +105,7 @@ impl From<NotAbsolute> for AbsolutePathNewError {
 }
 
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum AbsolutePathBufNewError {
     #[error(transparent)]
     NormalizationFailed(NormalizationFailed),
@@ -45,6 +113,16 @@ pub enum AbsolutePathBufNewError {
     NotAbsolute(NotAbsolute),
 }
 
+impl AbsolutePathBufNewError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AbsolutePathBufNewError::NormalizationFailed(e) => e.code(),
+            AbsolutePathBufNewError::NotAbsolute(e) => e.code(),
+        }
+    }
+}
+
 impl From<NormalizationFailed> for AbsolutePathBufNewError {
     fn from(e: NormalizationFailed) -> Self {
         AbsolutePathBufNewError::NormalizationFailed(e)
@@ -57,6 +135,7 @@ impl From<NotAbsolute> for AbsolutePathBufNewError {
 }
 
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum AbsoluteJoinError {
     #[error(transparent)]
     NormalizationFailed(NormalizationFailed),
@@ -64,6 +143,16 @@ pub enum AbsoluteJoinError {
     JoinedAbsolute(JoinedAbsolute),
 }
 
+impl AbsoluteJoinError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AbsoluteJoinError::NormalizationFailed(e) => e.code(),
+            AbsoluteJoinError::JoinedAbsolute(e) => e.code(),
+        }
+    }
+}
+
 impl From<NormalizationFailed> for AbsoluteJoinError {
     fn from(e: NormalizationFailed) -> Self {
         AbsoluteJoinError::NormalizationFailed(e)
@@ -76,6 +165,7 @@ impl From<JoinedAbsolute> for AbsoluteJoinError {
 }
 
 #[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum CombinedJoinError {
     #[error(transparent)]
     NormalizationFailed(NormalizationFailed),
@@ -83,6 +173,16 @@ pub enum CombinedJoinError {
     JoinedAbsolute(JoinedAbsolute),
 }
 
+impl CombinedJoinError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CombinedJoinError::NormalizationFailed(e) => e.code(),
+            CombinedJoinError::JoinedAbsolute(e) => e.code(),
+        }
+    }
+}
+
 impl From<NormalizationFailed> for CombinedJoinError {
     fn from(e: NormalizationFailed) -> Self {
         CombinedJoinError::NormalizationFailed(e)
@@ -104,7 +204,251 @@ impl From<AbsoluteJoinError> for CombinedJoinError {
 }
 
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum RelativeToError {
     #[error("Provided paths are identical, and cannot be relativized")]
     PathsAreIdentical,
+    #[error(
+        "Provided paths have different roots (e.g. drives on windows), and cannot be relativized"
+    )]
+    DifferentRoots,
+}
+
+impl RelativeToError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            RelativeToError::PathsAreIdentical => "paths_are_identical",
+            RelativeToError::DifferentRoots => "different_roots",
+        }
+    }
+}
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is not a single file name", .0)]
+pub struct InvalidFileName(pub String);
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is not a valid glob pattern: {}", .0, .1)]
+pub struct InvalidGlobPattern(pub String, pub String);
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is not a valid rewrite rule: {}", .0, .1)]
+pub struct InvalidRewriteRule(pub String, pub String);
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("Aliases reference each other in a cycle: `{}`", .0.join("` -> `"))]
+pub struct AliasCycle(pub Vec<String>);
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum AliasResolutionError {
+    #[error(transparent)]
+    AliasCycle(AliasCycle),
+    #[error(transparent)]
+    NormalizationFailed(NormalizationFailed),
+    #[error(transparent)]
+    NotAbsolute(NotAbsolute),
+}
+
+impl AliasResolutionError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AliasResolutionError::AliasCycle(e) => e.code(),
+            AliasResolutionError::NormalizationFailed(e) => e.code(),
+            AliasResolutionError::NotAbsolute(e) => e.code(),
+        }
+    }
+}
+
+impl From<AliasCycle> for AliasResolutionError {
+    fn from(e: AliasCycle) -> Self {
+        AliasResolutionError::AliasCycle(e)
+    }
+}
+
+impl From<AbsolutePathBufNewError> for AliasResolutionError {
+    fn from(e: AbsolutePathBufNewError) -> Self {
+        match e {
+            AbsolutePathBufNewError::NormalizationFailed(e) => Self::NormalizationFailed(e),
+            AbsolutePathBufNewError::NotAbsolute(e) => Self::NotAbsolute(e),
+        }
+    }
+}
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` overlaps with the existing root `{}`", .0, .1)]
+pub struct OverlappingRoots(pub String, pub String);
+
+/// Every path that failed validation in [`crate::validate_all`], with its index in the input.
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("{} invalid paths: {}", .0.len(), .0.iter().map(|(i, e)| format!("[{}] {}", i, e)).join("; "))]
+pub struct BulkError(pub Vec<(usize, AbsolutePathBufNewError)>);
+
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is held by another running instance", .0)]
+pub struct AlreadyRunning(pub String, pub Option<u32>);
+
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum SingleInstanceError {
+    #[error(transparent)]
+    AlreadyRunning(AlreadyRunning),
+    #[error(transparent)]
+    Io(std::io::Error),
+}
+
+impl SingleInstanceError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SingleInstanceError::AlreadyRunning(e) => e.code(),
+            SingleInstanceError::Io(_) => "io",
+        }
+    }
+}
+
+impl From<AlreadyRunning> for SingleInstanceError {
+    fn from(e: AlreadyRunning) -> Self {
+        SingleInstanceError::AlreadyRunning(e)
+    }
+}
+impl From<std::io::Error> for SingleInstanceError {
+    fn from(e: std::io::Error) -> Self {
+        SingleInstanceError::Io(e)
+    }
+}
+
+/// An error reading a path list with [`crate::PathListReader`], tagged with the 1-based line (or
+/// record, for NUL delimited input) that it happened on.
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum PathListError {
+    #[error("line {}: {}", .0, .1)]
+    NormalizationFailed(usize, NormalizationFailed),
+    #[error("line {}: `{}` is not valid UTF-8", .0, .1)]
+    NotUtf8(usize, String),
+    #[error("line {}: {}", .0, .1)]
+    Io(usize, std::io::Error),
+}
+
+impl PathListError {
+    /// The line (or record) that the error happened on, starting at 1.
+    pub fn line(&self) -> usize {
+        match self {
+            PathListError::NormalizationFailed(line, _)
+            | PathListError::NotUtf8(line, _)
+            | PathListError::Io(line, _) => *line,
+        }
+    }
+
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PathListError::NormalizationFailed(_, e) => e.code(),
+            PathListError::NotUtf8(..) => "not_utf8",
+            PathListError::Io(..) => "io",
+        }
+    }
+}
+
+/// An io error from [`crate::fs`], with the operation and path that it happened on.
+///
+/// If the `fs-context` feature is enabled, errors from [`crate::fs`] wrap this, and it can be
+/// found with [`std::io::Error::get_ref`]. The original error is its source.
+#[derive(thiserror::Error, Debug)]
+#[error("failed to {} `{}`: {}", .0, .1, .2)]
+pub struct PathIoError(pub String, pub String, #[source] pub std::io::Error);
+
+/// An operation on a path that a [`crate::RestrictedFs`] does not allow.
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("access to `{}` is not allowed", .0)]
+pub struct AccessDenied(pub String);
+
+/// A path that a [`crate::Rebaser`] could not convert because it is not beneath the base.
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is not beneath `{}`", .0, .1)]
+pub struct NotBeneathBase(pub String, pub String);
+
+/// A path that could not be converted to a UTF-8 path type, e.g. [`crate::Utf8AbsolutePathBuf`].
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is not valid UTF-8", .0)]
+pub struct NotUtf8(pub String);
+
+/// A path that should exist, e.g. for a [`crate::ExistingAbsolutePathBuf`], but does not.
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` does not exist", .0)]
+pub struct Missing(pub String);
+
+/// A value that [`crate::AbsolutePathBuf::from_env_value`] could not decode, with the reason.
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("`{}` is not a valid path from an environment variable: {}", .0, .1)]
+pub struct InvalidEnvValue(pub String, pub String);
+
+/// A config file that was given to a [`crate::ConfigLocator`] explicitly, on the command line or
+/// in its environment variable, but is not an existing file, or cannot be resolved to a path.
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[error("config file `{}` does not exist", .0)]
+pub struct MissingConfig(pub String, pub ConfigSource);
+
+/// Why a path could not be scoped to a tenant of a [`crate::TenantRoots`].
+#[derive(Clone, Debug, thiserror::Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TenantScopeError {
+    #[error("unknown tenant `{}`", .0)]
+    UnknownTenant(String),
+    #[error("`{}` is not beneath the root of tenant `{}`", .0, .1)]
+    OutsideTenant(String, String),
+    #[error(transparent)]
+    NormalizationFailed(NormalizationFailed),
+}
+
+impl From<NormalizationFailed> for TenantScopeError {
+    fn from(e: NormalizationFailed) -> Self {
+        TenantScopeError::NormalizationFailed(e)
+    }
+}
+
+impl TenantScopeError {
+    /// A stable identifier for this kind of error. This will not change between releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TenantScopeError::UnknownTenant(_) => "unknown_tenant",
+            TenantScopeError::OutsideTenant(..) => "outside_tenant",
+            TenantScopeError::NormalizationFailed(e) => e.code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsoluteJoinError;
+    use crate::AbsolutePathNewError;
+    use crate::JoinedAbsolute;
+    use crate::NotAbsolute;
+    use crate::RelativeToError;
+    use crate::SingleInstanceError;
+
+    #[test]
+    fn error_codes() {
+        assert_eq!("not_absolute", NotAbsolute("foo".to_owned()).code());
+        assert_eq!(
+            "not_absolute",
+            AbsolutePathNewError::from(NotAbsolute("foo".to_owned())).code()
+        );
+        assert_eq!(
+            "joined_absolute",
+            AbsoluteJoinError::from(JoinedAbsolute("/foo".to_owned(), "/bar".to_owned())).code()
+        );
+        assert_eq!(
+            "paths_are_identical",
+            RelativeToError::PathsAreIdentical.code()
+        );
+        assert_eq!("different_roots", RelativeToError::DifferentRoots.code());
+        assert_eq!(
+            "io",
+            SingleInstanceError::from(std::io::Error::from(std::io::ErrorKind::Other)).code()
+        );
+    }
 }
@@ -0,0 +1,120 @@
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::PathEvent;
+
+/// Reads [`PathEvent`]s in the JSON Lines format, i.e. one JSON object per line, as written by
+/// [`write_path_events`], so that tools can exchange streams of file changes, e.g. through a
+/// pipe.
+///
+/// Empty lines are skipped. Lines that are not valid events fail with
+/// [`std::io::ErrorKind::InvalidData`], with the line number in the message, and iteration
+/// continues with the next line. Iteration stops after the first error from the reader.
+#[derive(Debug)]
+pub struct PathEventReader<R> {
+    reader: R,
+    line: usize,
+    done: bool,
+}
+
+impl<R: BufRead> PathEventReader<R> {
+    /// Read events from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PathEventReader<R> {
+    type Item = std::io::Result<PathEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.line += 1;
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => self.done = true,
+                Ok(_) if line.trim().is_empty() => {}
+                Ok(_) => {
+                    return Some(serde_json::from_str(&line).map_err(|e| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("line {}: {}", self.line, e),
+                        )
+                    }))
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Write `events` to `w` in the JSON Lines format read by [`PathEventReader`], flushing after
+/// each one so that readers see events as they happen.
+///
+/// Paths that are not valid UTF-8 cannot be represented in JSON, and return an error.
+pub fn write_path_events<'a, I, W>(events: I, mut w: W) -> std::io::Result<()>
+where
+    I: IntoIterator<Item = &'a PathEvent>,
+    W: Write,
+{
+    for event in events {
+        serde_json::to_writer(&mut w, event)?;
+        w.write_all(b"\n")?;
+        w.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use crate::write_path_events;
+    use crate::AbsolutePathBuf;
+    use crate::PathEvent;
+    use crate::PathEventKind;
+    use crate::PathEventReader;
+
+    #[test]
+    fn round_trips_events() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789);
+        let events = vec![
+            PathEvent::new(cwd.join("a.txt")?, PathEventKind::Created),
+            PathEvent::new(cwd.join("dir")?, PathEventKind::DirectoryChanged)
+                .with_timestamp(timestamp),
+            PathEvent::renamed(cwd.join("old.txt")?, cwd.join("new.txt")?),
+        ];
+        let mut out = vec![];
+        write_path_events(&events, &mut out)?;
+        let out = String::from_utf8(out)?;
+        assert_eq!(3, out.lines().count());
+        assert!(out.contains(r#""kind":"directory_changed","timestamp":1700000000123456789}"#));
+        assert!(out.contains(r#""kind":"renamed","old_path":"#));
+
+        let read = PathEventReader::new(out.as_bytes()).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(events, read);
+        assert_eq!(Some(timestamp), read[1].timestamp());
+
+        let input = format!(
+            "{}\n\n{{\"path\":\"relative\",\"kind\":\"created\"}}\n",
+            out.lines().next().unwrap()
+        );
+        let read: Vec<_> = PathEventReader::new(input.as_bytes()).collect();
+        assert_eq!(2, read.len());
+        assert_eq!(events[0], *read[0].as_ref().unwrap());
+        let err = read[1].as_ref().unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().starts_with("line 3: "));
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::Missing;
+
+/// An [`AbsolutePathBuf`] that existed when it was created, e.g. for config files, so that
+/// missing paths fail as soon as they are loaded rather than when they are first used.
+///
+/// The filesystem can change afterwards, so use [`ExistingAbsolutePathBuf::revalidate`] to check
+/// again before anything that depends on the path still existing.
+/// Like [`crate::CanonicalPathBuf`], this is only a PathBuf variant.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Ord, PartialOrd)]
+pub struct ExistingAbsolutePathBuf(AbsolutePathBuf);
+
+impl ExistingAbsolutePathBuf {
+    /// Create an [`ExistingAbsolutePathBuf`], checking that `path` exists (following symlinks).
+    ///
+    /// Paths whose metadata cannot be read, e.g. because of permissions, are also [`Missing`].
+    pub fn try_new(path: AbsolutePathBuf) -> Result<Self, Missing> {
+        check_exists(&path)?;
+        Ok(Self(path))
+    }
+
+    /// Check that the path still exists.
+    pub fn revalidate(&self) -> Result<(), Missing> {
+        check_exists(&self.0)
+    }
+
+    /// Get a new [`AbsolutePath`] referencing the internal Path object.
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        &self.0
+    }
+
+    /// Take ownership of the path as an [`AbsolutePathBuf`].
+    pub fn into_absolute_path_buf(self) -> AbsolutePathBuf {
+        self.0
+    }
+}
+
+fn check_exists(path: &AbsolutePath) -> Result<(), Missing> {
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(Missing(path.to_string_lossy().to_string()))
+    }
+}
+
+impl TryFrom<AbsolutePathBuf> for ExistingAbsolutePathBuf {
+    type Error = Missing;
+
+    fn try_from(value: AbsolutePathBuf) -> Result<Self, Self::Error> {
+        ExistingAbsolutePathBuf::try_new(value)
+    }
+}
+
+impl TryFrom<&AbsolutePath> for ExistingAbsolutePathBuf {
+    type Error = Missing;
+
+    fn try_from(value: &AbsolutePath) -> Result<Self, Self::Error> {
+        ExistingAbsolutePathBuf::try_new(value.into())
+    }
+}
+
+impl From<ExistingAbsolutePathBuf> for AbsolutePathBuf {
+    fn from(value: ExistingAbsolutePathBuf) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<Path> for ExistingAbsolutePathBuf {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<AbsolutePath> for ExistingAbsolutePathBuf {
+    fn as_ref(&self) -> &AbsolutePath {
+        &self.0
+    }
+}
+
+impl Deref for ExistingAbsolutePathBuf {
+    type Target = AbsolutePath;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for ExistingAbsolutePathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ExistingAbsolutePathBuf {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Checks that the path exists, so e.g. config files fail to load if they refer to missing paths.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ExistingAbsolutePathBuf {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let path = AbsolutePathBuf::deserialize(deserializer)?;
+        ExistingAbsolutePathBuf::try_new(path).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::ExistingAbsolutePathBuf;
+
+    #[test]
+    fn checks_paths_exist() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = root.join("config.toml")?;
+
+        let err = ExistingAbsolutePathBuf::try_new(path.clone()).unwrap_err();
+        assert_eq!("missing", err.code());
+        assert_eq!(path.to_string_lossy(), err.0);
+
+        std::fs::write(&path, "")?;
+        let existing = ExistingAbsolutePathBuf::try_from(path.as_absolute_path())?;
+        existing.revalidate()?;
+        assert_eq!(path.as_absolute_path(), existing.as_absolute_path());
+
+        std::fs::remove_file(&path)?;
+        assert_eq!(err, existing.revalidate().unwrap_err());
+        assert_eq!(path, existing.into_absolute_path_buf());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test_serde {
+    use crate::AbsolutePathBuf;
+    use crate::ExistingAbsolutePathBuf;
+
+    #[test]
+    fn deserializes_existing_paths() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let json = serde_json::to_string(&root.join("missing")?)?;
+        assert!(serde_json::from_str::<ExistingAbsolutePathBuf>(&json).is_err());
+
+        let json = serde_json::to_string(&root)?;
+        let existing: ExistingAbsolutePathBuf = serde_json::from_str(&json)?;
+        assert_eq!(root, AbsolutePathBuf::from(existing));
+        Ok(())
+    }
+}
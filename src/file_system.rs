@@ -0,0 +1,245 @@
+use crate::fs::Mode;
+use crate::fs::Operation;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::AccessDenied;
+use crate::PathFilter;
+use crate::RelativePath;
+
+/// The filesystem operations that this crate's typed paths are used for, so that code can be
+/// written against real disks, restricted views of them like [`RestrictedFs`], or fakes in tests.
+pub trait FileSystem {
+    /// Read the whole of the file at `path`.
+    fn read(&self, path: &AbsolutePath) -> std::io::Result<Vec<u8>>;
+
+    /// The entries in the directory at `path`, sorted.
+    fn read_dir(&self, path: &AbsolutePath) -> std::io::Result<Vec<AbsolutePathBuf>>;
+
+    /// Whether `path` is a file, following symlinks.
+    fn is_file(&self, path: &AbsolutePath) -> bool;
+
+    /// Whether `path` is a directory, following symlinks.
+    fn is_dir(&self, path: &AbsolutePath) -> bool;
+
+    /// Whether anything exists at `path`.
+    fn exists(&self, path: &AbsolutePath) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+
+    /// Replace the file at `path` with `contents`, creating it if necessary.
+    fn write(&self, path: &AbsolutePath, contents: &[u8]) -> std::io::Result<()>;
+
+    /// Create the directory at `path`, and any missing parents.
+    fn create_dir_all(&self, path: &AbsolutePath) -> std::io::Result<()>;
+
+    /// Rename `from` to `to`, replacing any file at `to`.
+    fn rename(&self, from: &AbsolutePath, to: &AbsolutePath) -> std::io::Result<()>;
+
+    /// Remove the file or directory (including its contents) at `path`.
+    fn remove(&self, path: &AbsolutePath) -> std::io::Result<()>;
+}
+
+/// The real filesystem, using the helpers in [`crate::fs`], so that changes are audited by
+/// [`crate::fs::AuditHook`]s.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct StdFs;
+
+impl FileSystem for StdFs {
+    fn read(&self, path: &AbsolutePath) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn read_dir(&self, path: &AbsolutePath) -> std::io::Result<Vec<AbsolutePathBuf>> {
+        let mut entries = std::fs::read_dir(path)?
+            .map(|e| {
+                Ok(path
+                    .join(e?.file_name())
+                    .expect("a directory entry is a plain file name"))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn is_file(&self, path: &AbsolutePath) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &AbsolutePath) -> bool {
+        path.is_dir()
+    }
+
+    fn write(&self, path: &AbsolutePath, contents: &[u8]) -> std::io::Result<()> {
+        crate::fs::write_atomic(path, contents)
+    }
+
+    fn create_dir_all(&self, path: &AbsolutePath) -> std::io::Result<()> {
+        crate::fs::create_dir_all(path, Mode::Execute).map(|_| ())
+    }
+
+    fn rename(&self, from: &AbsolutePath, to: &AbsolutePath) -> std::io::Result<()> {
+        let operation = Operation::Rename {
+            from: from.into(),
+            to: to.into(),
+        };
+        crate::fs::audit(&operation, None)?;
+        std::fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &AbsolutePath) -> std::io::Result<()> {
+        crate::fs::remove(path, Mode::Execute).map(|_| ())
+    }
+}
+
+/// A [`FileSystem`] that only allows access to some of the paths beneath a root, e.g. for plugin
+/// hosts that grant plugins limited access to the disk.
+///
+/// A path is allowed if it is beneath the root, and its path relative to the root matches
+/// `allow` but not `deny`. An empty `deny` filter denies nothing. Anything else fails with
+/// [`std::io::ErrorKind::PermissionDenied`], wrapping an [`AccessDenied`] that can be found with
+/// [`std::io::Error::get_ref`]. Denied entries are left out of [`FileSystem::read_dir`].
+///
+/// This only checks paths lexically, so symlinks beneath the root can still lead outside of it.
+#[derive(Debug, Clone)]
+pub struct RestrictedFs<F = StdFs> {
+    inner: F,
+    root: AbsolutePathBuf,
+    allow: PathFilter,
+    deny: PathFilter,
+}
+
+impl RestrictedFs {
+    /// Restrict the real filesystem to the paths beneath `root` that match `allow` and not
+    /// `deny`.
+    pub fn new(root: &AbsolutePath, allow: PathFilter, deny: PathFilter) -> Self {
+        Self::wrap(StdFs, root, allow, deny)
+    }
+}
+
+impl<F: FileSystem> RestrictedFs<F> {
+    /// Like [`RestrictedFs::new`], but restricting another [`FileSystem`].
+    pub fn wrap(inner: F, root: &AbsolutePath, allow: PathFilter, deny: PathFilter) -> Self {
+        Self {
+            inner,
+            root: root.into(),
+            allow,
+            deny,
+        }
+    }
+
+    /// Whether `path` may be accessed.
+    pub fn is_allowed(&self, path: &AbsolutePath) -> bool {
+        self.relative(path).is_some()
+    }
+
+    fn relative<'a>(&self, path: &'a AbsolutePath) -> Option<&'a RelativePath> {
+        path.strip_prefix(&self.root).ok().filter(|relative| {
+            self.allow.matches(relative) && (self.deny.is_empty() || !self.deny.matches(relative))
+        })
+    }
+
+    fn check(&self, path: &AbsolutePath) -> std::io::Result<()> {
+        if self.is_allowed(path) {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                AccessDenied(path.display().to_string()),
+            ))
+        }
+    }
+}
+
+impl<F: FileSystem> FileSystem for RestrictedFs<F> {
+    fn read(&self, path: &AbsolutePath) -> std::io::Result<Vec<u8>> {
+        self.check(path)?;
+        self.inner.read(path)
+    }
+
+    fn read_dir(&self, path: &AbsolutePath) -> std::io::Result<Vec<AbsolutePathBuf>> {
+        self.check(path)?;
+        let mut entries = self.inner.read_dir(path)?;
+        entries.retain(|entry| self.is_allowed(entry));
+        Ok(entries)
+    }
+
+    fn is_file(&self, path: &AbsolutePath) -> bool {
+        self.is_allowed(path) && self.inner.is_file(path)
+    }
+
+    fn is_dir(&self, path: &AbsolutePath) -> bool {
+        self.is_allowed(path) && self.inner.is_dir(path)
+    }
+
+    fn write(&self, path: &AbsolutePath, contents: &[u8]) -> std::io::Result<()> {
+        self.check(path)?;
+        self.inner.write(path, contents)
+    }
+
+    fn create_dir_all(&self, path: &AbsolutePath) -> std::io::Result<()> {
+        self.check(path)?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn rename(&self, from: &AbsolutePath, to: &AbsolutePath) -> std::io::Result<()> {
+        self.check(from)?;
+        self.check(to)?;
+        self.inner.rename(from, to)
+    }
+
+    fn remove(&self, path: &AbsolutePath) -> std::io::Result<()> {
+        self.check(path)?;
+        self.inner.remove(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::AccessDenied;
+    use crate::FileSystem;
+    use crate::PathFilter;
+    use crate::RestrictedFs;
+
+    #[test]
+    fn restricts_access() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let plugin = root.join("plugin")?;
+        std::fs::create_dir_all(plugin.join("data")?)?;
+        std::fs::write(plugin.join("data/a.txt")?, "a")?;
+        std::fs::write(plugin.join("data/secret.key")?, "key")?;
+        std::fs::write(root.join("other.txt")?, "other")?;
+
+        let fs = RestrictedFs::new(
+            &plugin,
+            PathFilter::new().include("data/**")?,
+            PathFilter::new().include("**/*.key")?,
+        );
+
+        assert_eq!(b"a".to_vec(), fs.read(&plugin.join("data/a.txt")?)?);
+        fs.write(&plugin.join("data/b.txt")?, b"b")?;
+        fs.rename(&plugin.join("data/b.txt")?, &plugin.join("data/c.txt")?)?;
+        assert_eq!(
+            vec![plugin.join("data/a.txt")?, plugin.join("data/c.txt")?],
+            fs.read_dir(&plugin.join("data")?)?
+        );
+
+        let err = fs.read(&plugin.join("data/secret.key")?).unwrap_err();
+        assert_eq!(std::io::ErrorKind::PermissionDenied, err.kind());
+        assert_eq!(
+            Some(&AccessDenied(
+                plugin.join("data/secret.key")?.display().to_string()
+            )),
+            err.get_ref().and_then(|e| e.downcast_ref::<AccessDenied>())
+        );
+        assert!(fs.read(&root.join("other.txt")?).is_err());
+        assert!(fs.write(&plugin.join("outside.txt")?, b"").is_err());
+        assert!(fs
+            .rename(&plugin.join("data/a.txt")?, &root.join("a.txt")?)
+            .is_err());
+        assert!(!fs.exists(&plugin.join("data/secret.key")?));
+        assert!(plugin.join("data/a.txt")?.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,1231 @@
+//! Filesystem helpers that operate on the typed paths in this crate.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::RwLock;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// Check whether each of `paths` exists, returning the results in the same order.
+///
+/// This is intended for verifying large numbers of candidate paths at once. If the `rayon`
+/// feature is enabled, the checks are spread across rayon's thread pool.
+pub fn check_exists_many<I>(paths: I) -> Vec<bool>
+where
+    I: IntoIterator,
+    I::Item: AsRef<AbsolutePath> + Sync,
+{
+    let paths: Vec<I::Item> = paths.into_iter().collect();
+
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        paths.par_iter().map(|p| p.as_ref().exists()).collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        paths.iter().map(|p| p.as_ref().exists()).collect()
+    }
+}
+
+/// Like [`std::fs::create_dir_all`], but returns the directories that were created, outermost
+/// first, so that they can be removed again later.
+///
+/// Directories that already existed are not included, so if `path` already exists the result is
+/// empty. If creating any directory fails, the ones that were already created by this call are
+/// removed before the error is returned.
+pub fn create_dir_all_tracked(path: &AbsolutePath) -> std::io::Result<Vec<AbsolutePathBuf>> {
+    create_dirs(path, &CreateOptions::new())
+}
+
+/// [`create_dir_all_tracked`], creating each directory with `options`.
+fn create_dirs(
+    path: &AbsolutePath,
+    options: &CreateOptions,
+) -> std::io::Result<Vec<AbsolutePathBuf>> {
+    let mut created: Vec<AbsolutePathBuf> = vec![];
+    for dir in missing_dirs(path) {
+        let checked = audit(&Operation::CreateDir(dir.into()), None);
+        match checked.and_then(|_| options.create_dir(dir)) {
+            Ok(()) => created.push(dir.into()),
+            // Something else created it in the meantime.
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && dir.is_dir() => {}
+            Err(e) => {
+                for dir in created.iter().rev() {
+                    let _ = std::fs::remove_dir(dir);
+                }
+                return Err(with_context(e, "create directory", dir));
+            }
+        }
+    }
+    Ok(created)
+}
+
+/// `path` and its ancestors that are not directories, outermost first.
+fn missing_dirs(path: &AbsolutePath) -> Vec<&AbsolutePath> {
+    let mut missing = vec![];
+    let mut dir = Some(path);
+    while let Some(current) = dir {
+        if current.is_dir() {
+            break;
+        }
+        missing.push(current);
+        dir = current.parent();
+    }
+    missing.reverse();
+    missing
+}
+
+/// The permissions of files and directories created by [`write_with_options`],
+/// [`write_atomic_with_options`] and [`create_dir_all_with_options`], e.g. `0o600` for secrets.
+///
+/// By default, files and directories are created like the standard library does, i.e. with
+/// `0o666` and `0o777` without the process's umask. Permissions are only supported on unix, so
+/// these options are ignored on other platforms.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct CreateOptions {
+    mode: Option<u32>,
+    umask_respect: bool,
+}
+
+impl Default for CreateOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CreateOptions {
+    /// The default permissions, with the umask respected.
+    pub fn new() -> Self {
+        Self {
+            mode: None,
+            umask_respect: true,
+        }
+    }
+
+    /// The permission bits to create with, e.g. `0o600`.
+    ///
+    /// Unlike [`std::os::unix::fs::OpenOptionsExt::mode`], files that already exist are also
+    /// changed to these permissions when they are written. Directories that already exist are
+    /// left alone.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Whether the process's umask is removed from [`CreateOptions::mode`], as it is by
+    /// `open(2)`. If this is `false`, paths get exactly [`CreateOptions::mode`], e.g. so that a
+    /// shared directory is group writable regardless of the umask.
+    ///
+    /// Outside of linux, the umask can only be read by changing it, so it is briefly set to
+    /// `0o022` when it is needed. Files created by other threads at that moment may get the
+    /// wrong permissions.
+    pub fn umask_respect(mut self, umask_respect: bool) -> Self {
+        self.umask_respect = umask_respect;
+        self
+    }
+
+    /// The permissions to set on a path after creating it, if they need to be set.
+    #[cfg(unix)]
+    fn permissions(&self) -> Option<std::fs::Permissions> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = self.mode?;
+        let mode = if self.umask_respect {
+            mode & !current_umask()
+        } else {
+            mode
+        };
+        Some(std::fs::Permissions::from_mode(mode))
+    }
+
+    fn create_dir(&self, path: &Path) -> std::io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+
+            let mut builder = std::fs::DirBuilder::new();
+            if let Some(mode) = self.mode {
+                builder.mode(mode);
+            }
+            builder.create(path)?;
+            // The umask has already been removed if it is respected.
+            if !self.umask_respect {
+                if let Some(permissions) = self.permissions() {
+                    std::fs::set_permissions(path, permissions)?;
+                }
+            }
+            Ok(())
+        }
+        #[cfg(not(unix))]
+        {
+            std::fs::create_dir(path)
+        }
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<std::fs::File> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            if let Some(mode) = self.mode {
+                options.mode(mode);
+            }
+        }
+        let file = options.open(path)?;
+        self.apply(&file)?;
+        Ok(file)
+    }
+
+    /// Set the permissions of `file`, a temporary file that is about to replace `path`.
+    ///
+    /// Without a [`CreateOptions::mode`], an existing file at `path` keeps its permissions, and
+    /// a new one gets the same permissions as [`std::fs::write`] would give it.
+    fn apply_replacing(&self, file: &std::fs::File, path: &Path) -> std::io::Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let permissions = match self.permissions() {
+                Some(permissions) => permissions,
+                None => match std::fs::metadata(path) {
+                    Ok(metadata) => {
+                        std::fs::Permissions::from_mode(metadata.permissions().mode() & 0o7777)
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        std::fs::Permissions::from_mode(0o666 & !current_umask())
+                    }
+                    Err(e) => return Err(e),
+                },
+            };
+            file.set_permissions(permissions)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (file, path);
+            Ok(())
+        }
+    }
+
+    /// Set the permissions of `file`, which may have been created by someone else.
+    fn apply(&self, file: &std::fs::File) -> std::io::Result<()> {
+        #[cfg(unix)]
+        if let Some(permissions) = self.permissions() {
+            file.set_permissions(permissions)?;
+        }
+        #[cfg(not(unix))]
+        let _ = file;
+        Ok(())
+    }
+}
+
+/// The process's umask.
+#[cfg(unix)]
+fn current_umask() -> u32 {
+    // Reading the umask without changing it, which is racy, needs linux 4.7.
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+        if let Some(umask) = status
+            .lines()
+            .find_map(|line| line.strip_prefix("Umask:"))
+            .and_then(|umask| u32::from_str_radix(umask.trim(), 8).ok())
+        {
+            return umask;
+        }
+    }
+    // Other threads that create files before the umask is set back get `0o022` instead. There
+    // is no way to read the umask without changing it on these platforms.
+    // SAFETY: `umask` cannot fail. It is process wide, so it is set back straight away.
+    let umask: libc::mode_t = unsafe { libc::umask(0o022) };
+    // SAFETY: As above.
+    unsafe { libc::umask(umask) };
+    umask as u32
+}
+
+/// Whether the helpers that take one should change the filesystem, or only report what they
+/// would do, e.g. for a `--dry-run` flag.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum Mode {
+    #[default]
+    Execute,
+    /// Make no changes, but still return the operations that would have been performed.
+    DryRun,
+}
+
+/// A change to the filesystem, as performed (or planned, in [`Mode::DryRun`]) by the helpers in
+/// this module and [`crate::FsTransaction`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    CreateDir(AbsolutePathBuf),
+    Write(AbsolutePathBuf),
+    Copy {
+        from: AbsolutePathBuf,
+        to: AbsolutePathBuf,
+    },
+    Rename {
+        from: AbsolutePathBuf,
+        to: AbsolutePathBuf,
+    },
+    /// Remove a file, or a directory and everything in it.
+    Remove(AbsolutePathBuf),
+}
+
+impl Operation {
+    /// The path that is changed by this operation, i.e. the destination of copies and renames.
+    pub fn path(&self) -> &AbsolutePath {
+        match self {
+            Operation::CreateDir(path) | Operation::Write(path) | Operation::Remove(path) => path,
+            Operation::Copy { to, .. } | Operation::Rename { to, .. } => to,
+        }
+    }
+}
+
+/// Called before the helpers in this module and [`crate::FsTransaction`] change the filesystem,
+/// so that applications can log or veto changes in one place.
+///
+/// The rest of the crate's helpers that create, replace or remove files report to the hooks too,
+/// e.g. [`crate::AbsolutePath::write_with_backup`], [`crate::LogFile`] rotation,
+/// [`crate::Scaffold::create`], [`crate::AbsolutePath::child_unique`] and
+/// [`crate::AnonymousTempFile::persist_as`]. Appending to a file that is already open, e.g. with
+/// [`crate::LogFile::append_line`], is not reported.
+///
+/// Set one for the whole process with [`set_audit_hook`], or for a single transaction with
+/// [`crate::FsTransaction::with_audit_hook`]. Hooks are not called in [`Mode::DryRun`], as
+/// nothing is changed.
+pub trait AuditHook: Send + Sync {
+    /// Called before `operation` changes `path` (which is [`Operation::path`]).
+    ///
+    /// Returning an error vetoes the operation, and the error is returned to the caller.
+    fn before(&self, operation: &Operation, path: &AbsolutePath) -> std::io::Result<()>;
+}
+
+static AUDIT_HOOK: RwLock<Option<Arc<dyn AuditHook>>> = RwLock::new(None);
+
+/// Set the [`AuditHook`] that is called before every change made by this crate, replacing any
+/// previous one. `None` removes it.
+///
+/// This is called before the hook of an individual transaction, if it has one.
+pub fn set_audit_hook(hook: Option<Arc<dyn AuditHook>>) {
+    *AUDIT_HOOK.write().unwrap_or_else(|e| e.into_inner()) = hook;
+}
+
+/// Run the global audit hook, then `local`, for `operation`.
+pub(crate) fn audit(operation: &Operation, local: Option<&dyn AuditHook>) -> std::io::Result<()> {
+    let global = AUDIT_HOOK.read().unwrap_or_else(|e| e.into_inner()).clone();
+    for hook in global.as_deref().into_iter().chain(local) {
+        hook.before(operation, operation.path())?;
+    }
+    Ok(())
+}
+
+/// Like [`std::fs::create_dir_all`], returning an [`Operation::CreateDir`] for each directory
+/// that was (or would be) created, outermost first.
+pub fn create_dir_all(path: &AbsolutePath, mode: Mode) -> std::io::Result<Vec<Operation>> {
+    create_dir_all_with_options(path, mode, &CreateOptions::new())
+}
+
+/// Like [`create_dir_all`], but creates directories with `options`.
+pub fn create_dir_all_with_options(
+    path: &AbsolutePath,
+    mode: Mode,
+    options: &CreateOptions,
+) -> std::io::Result<Vec<Operation>> {
+    let created = match mode {
+        Mode::Execute => create_dirs(path, options)?,
+        Mode::DryRun => missing_dirs(path).into_iter().map(|p| p.into()).collect(),
+    };
+    Ok(created.into_iter().map(Operation::CreateDir).collect())
+}
+
+/// Remove the file, symlink or directory (including its contents) at `path`.
+///
+/// Fails if nothing exists at `path`, in either mode.
+pub fn remove(path: &AbsolutePath, mode: Mode) -> std::io::Result<Vec<Operation>> {
+    let operation = Operation::Remove(path.into());
+    let removed = std::fs::symlink_metadata(path).and_then(|metadata| match mode {
+        Mode::Execute => {
+            audit(&operation, None)?;
+            if metadata.is_dir() {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_file(path)
+            }
+        }
+        Mode::DryRun => Ok(()),
+    });
+    removed.map_err(|e| with_context(e, "remove", path))?;
+    Ok(vec![operation])
+}
+
+/// Recursively copy the directory `src` to `dest`, merging into `dest` if it already exists.
+///
+/// Symlinks in `src` are followed. Returns an [`Operation::CreateDir`] for each directory that
+/// was (or would be) created, and an [`Operation::Copy`] for each file, in the order they were
+/// performed.
+pub fn copy_dir(
+    src: &AbsolutePath,
+    dest: &AbsolutePath,
+    mode: Mode,
+) -> std::io::Result<Vec<Operation>> {
+    let mut operations = create_dir_all(dest, mode)?;
+    copy_dir_inner(src, dest, mode, &mut operations)
+        .map_err(|e| with_copy_context(e, src, dest))?;
+    Ok(operations)
+}
+
+/// Copy the contents of `src` into `dest`, which has already been (or would have been) created.
+fn copy_dir_inner(
+    src: &AbsolutePath,
+    dest: &AbsolutePath,
+    mode: Mode,
+    operations: &mut Vec<Operation>,
+) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(src)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let from = src
+            .join(entry.file_name())
+            .expect("a directory entry is a plain file name");
+        let to = dest
+            .join(entry.file_name())
+            .expect("a directory entry is a plain file name");
+        if from.is_dir() {
+            if !to.is_dir() {
+                let operation = Operation::CreateDir(to.clone());
+                if mode == Mode::Execute {
+                    audit(&operation, None)?;
+                    std::fs::create_dir(&to)?;
+                }
+                operations.push(operation);
+            }
+            copy_dir_inner(&from, &to, mode, operations)?;
+        } else {
+            let operation = Operation::Copy {
+                from: from.clone(),
+                to: to.clone(),
+            };
+            if mode == Mode::Execute {
+                audit(&operation, None)?;
+                std::fs::copy(&from, &to)?;
+            }
+            operations.push(operation);
+        }
+    }
+    Ok(())
+}
+
+/// Write `contents` to `path`, so that readers see either the old file or the complete new one.
+///
+/// The data is written to a temporary file in the same directory, which is then renamed over
+/// `path`. If `path` already exists, the new file keeps its permissions. Otherwise it gets the
+/// permissions that [`std::fs::write`] would give it.
+pub fn write_atomic(path: &AbsolutePath, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    write_atomic_with_options(path, contents, &CreateOptions::new())
+}
+
+/// Like [`write_atomic`], but the new file is created with `options`.
+///
+/// The permissions are set before the file is renamed over `path`, so the contents are never
+/// visible with any other permissions.
+pub fn write_atomic_with_options(
+    path: &AbsolutePath,
+    contents: impl AsRef<[u8]>,
+    options: &CreateOptions,
+) -> std::io::Result<()> {
+    audit(&Operation::Write(path.into()), None)
+        .and_then(|_| write_atomic_inner(path, contents.as_ref(), options))
+        .map_err(|e| with_context(e, "write", path))
+}
+
+/// [`write_atomic`], for callers that have already run the audit hooks.
+pub(crate) fn write_atomic_unaudited(path: &AbsolutePath, contents: &[u8]) -> std::io::Result<()> {
+    write_atomic_inner(path, contents, &CreateOptions::new())
+        .map_err(|e| with_context(e, "write", path))
+}
+
+/// Like [`std::fs::write`], but the file is created (or changed to) the permissions in
+/// `options`.
+pub fn write_with_options(
+    path: &AbsolutePath,
+    contents: impl AsRef<[u8]>,
+    options: &CreateOptions,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    audit(&Operation::Write(path.into()), None)
+        .and_then(|_| options.open(path)?.write_all(contents.as_ref()))
+        .map_err(|e| with_context(e, "write", path))
+}
+
+fn write_atomic_inner(
+    path: &AbsolutePath,
+    contents: &[u8],
+    options: &CreateOptions,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let parent = path.parent().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "cannot write to the filesystem root",
+        )
+    })?;
+    let mut temp = parent.create_anonymous_temp()?;
+    options.apply_replacing(temp.as_file(), path)?;
+    temp.write_all(contents)?;
+    temp.as_file().sync_all()?;
+    temp.persist_as_unaudited(path)?;
+    Ok(())
+}
+
+/// Paths split up by [`partition_by_kind`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PathKinds {
+    /// Paths to anything that exists but is not a directory, e.g. regular files or devices.
+    pub files: Vec<AbsolutePathBuf>,
+    /// Paths to directories.
+    pub dirs: Vec<AbsolutePathBuf>,
+    /// Paths that do not exist, or could not be checked.
+    pub missing: Vec<AbsolutePathBuf>,
+}
+
+/// Split `paths` into files, directories and missing paths, e.g. to decide which command line
+/// arguments should be walked recursively.
+///
+/// Symlinks are followed, so broken symlinks are missing. Each bucket keeps the order of
+/// `paths`. As with [`check_exists_many`], the checks are run in parallel if the `rayon`
+/// feature is enabled.
+pub fn partition_by_kind<I>(paths: I) -> PathKinds
+where
+    I: IntoIterator<Item = AbsolutePathBuf>,
+{
+    let paths: Vec<AbsolutePathBuf> = paths.into_iter().collect();
+    let is_dir = |p: &AbsolutePathBuf| std::fs::metadata(p).ok().map(|m| m.is_dir());
+
+    #[cfg(feature = "rayon")]
+    let kinds: Vec<Option<bool>> = {
+        use rayon::prelude::*;
+        paths.par_iter().map(is_dir).collect()
+    };
+
+    #[cfg(not(feature = "rayon"))]
+    let kinds: Vec<Option<bool>> = paths.iter().map(is_dir).collect();
+
+    let mut partitioned = PathKinds::default();
+    for (path, kind) in paths.into_iter().zip(kinds) {
+        match kind {
+            Some(false) => partitioned.files.push(path),
+            Some(true) => partitioned.dirs.push(path),
+            None => partitioned.missing.push(path),
+        }
+    }
+    partitioned
+}
+
+/// How [`reflink`] produced its copy.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum CopyMethod {
+    /// The destination shares its data blocks with the source until either is modified.
+    Reflink,
+    /// The filesystem does not support cloning, so the data was copied.
+    Copy,
+}
+
+/// Copy `src` to `dest` as a copy-on-write clone where the filesystem supports it.
+///
+/// This uses `FICLONE` on linux (e.g. btrfs, xfs) and `clonefile` on macOS (APFS), and falls
+/// back to [`std::fs::copy`] elsewhere, or when the two paths are on different filesystems.
+/// Any existing file at `dest` is replaced, and permissions are copied from `src`. Fails if
+/// `dest` is `src`, e.g. through a symlink, rather than truncating it.
+pub fn reflink(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<CopyMethod> {
+    audit_copy(src, dest)
+        .and_then(|_| reflink_inner(src, dest))
+        .map_err(|e| with_copy_context(e, src, dest))
+}
+
+fn reflink_inner(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<CopyMethod> {
+    reject_same_file(src, dest)?;
+    if try_reflink(src, dest)? {
+        Ok(CopyMethod::Reflink)
+    } else {
+        std::fs::copy(src, dest)?;
+        Ok(CopyMethod::Copy)
+    }
+}
+
+/// Fail if `dest` is `src`, which copying would truncate before it is read.
+fn reject_same_file(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<()> {
+    if is_same_file(src, dest)? {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the source and destination are the same file",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `dest` exists and is the same file as `src`, e.g. through a symlink or hard link.
+fn is_same_file(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<bool> {
+    let dest_metadata = match std::fs::metadata(dest) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let src_metadata = std::fs::metadata(src)?;
+        Ok(src_metadata.dev() == dest_metadata.dev() && src_metadata.ino() == dest_metadata.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dest_metadata;
+        Ok(std::fs::canonicalize(src)? == std::fs::canonicalize(dest)?)
+    }
+}
+
+/// Returns whether the clone was made, or if the caller should fall back to copying.
+#[cfg(target_os = "linux")]
+fn try_reflink(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    // Clone beside the destination, then rename over it, so that `dest` is left alone if the
+    // clone fails.
+    let parent = match dest.parent() {
+        Some(parent) => parent,
+        None => return Ok(false),
+    };
+    let temp_dest = parent
+        .join(crate::unique_file_name(".tmp-"))
+        .expect("a plain file name can always be joined");
+    let src_file = std::fs::File::open(src)?;
+    let temp_file = std::fs::File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temp_dest)?;
+    // SAFETY: Both fds are open for the duration of the call.
+    let res = unsafe { libc::ioctl(temp_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) };
+    let cloned = if res == 0 {
+        temp_file
+            .set_permissions(src_file.metadata()?.permissions())
+            .and_then(|_| std::fs::rename(&temp_dest, dest))
+            .map(|_| true)
+    } else {
+        let e = std::io::Error::last_os_error();
+        match e.raw_os_error() {
+            Some(libc::EOPNOTSUPP)
+            | Some(libc::EXDEV)
+            | Some(libc::EINVAL)
+            | Some(libc::ENOTTY) => Ok(false),
+            _ => Err(e),
+        }
+    };
+    if !matches!(cloned, Ok(true)) {
+        let _ = std::fs::remove_file(&temp_dest);
+    }
+    cloned
+}
+
+/// Returns whether the clone was made, or if the caller should fall back to copying.
+#[cfg(target_os = "macos")]
+fn try_reflink(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<bool> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let to_c = |p: &std::path::Path| {
+        CString::new(p.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+    };
+    // clonefile refuses to replace an existing file, so clone beside the destination, then
+    // rename over it.
+    let parent = match dest.parent() {
+        Some(parent) => parent,
+        None => return Ok(false),
+    };
+    let temp_dest = parent
+        .join(crate::unique_file_name(".tmp-"))
+        .expect("a plain file name can always be joined");
+    let c_src = to_c(src)?;
+    let c_temp_dest = to_c(&temp_dest)?;
+    // SAFETY: Both paths are valid nul terminated strings.
+    let res = unsafe { libc::clonefile(c_src.as_ptr(), c_temp_dest.as_ptr(), 0) };
+    if res == 0 {
+        return std::fs::rename(&temp_dest, dest)
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&temp_dest);
+            })
+            .map(|_| true);
+    }
+    let e = std::io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::ENOTSUP) | Some(libc::EXDEV) => Ok(false),
+        _ => Err(e),
+    }
+}
+
+/// Returns whether the clone was made, or if the caller should fall back to copying.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn try_reflink(_src: &AbsolutePath, _dest: &AbsolutePath) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Copy `src` to `dest` without allocating space for runs of zeros (holes) in `dest`.
+///
+/// On linux and macOS, the holes in `src` are found with `SEEK_DATA` / `SEEK_HOLE`, so only the
+/// data regions are read. Elsewhere, or if the filesystem does not support seeking for holes,
+/// `src` is read in full, and blocks that are entirely zero are skipped over in `dest`. This is
+/// useful for things like VM images, whose logical size is much larger than their allocated
+/// size (see [`AbsolutePath::allocated_size`]).
+///
+/// Any existing file at `dest` is replaced, and permissions are copied from `src`. Fails if
+/// `dest` is `src`, rather than truncating it. Returns the number of bytes of data that were
+/// written.
+pub fn copy_sparse(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<u64> {
+    audit_copy(src, dest)
+        .and_then(|_| copy_sparse_inner(src, dest))
+        .map_err(|e| with_copy_context(e, src, dest))
+}
+
+fn audit_copy(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<()> {
+    let operation = Operation::Copy {
+        from: src.into(),
+        to: dest.into(),
+    };
+    audit(&operation, None)
+}
+
+fn copy_sparse_inner(src: &AbsolutePath, dest: &AbsolutePath) -> std::io::Result<u64> {
+    reject_same_file(src, dest)?;
+    let mut src_file = std::fs::File::open(src)?;
+    let metadata = src_file.metadata()?;
+    let mut dest_file = std::fs::File::create(dest)?;
+    dest_file.set_len(metadata.len())?;
+
+    let written = match data_regions(&src_file, metadata.len())? {
+        Some(regions) => {
+            let mut written = 0;
+            for (start, end) in regions {
+                written += copy_range(&mut src_file, &mut dest_file, start, end, false)?;
+            }
+            written
+        }
+        None => copy_range(&mut src_file, &mut dest_file, 0, metadata.len(), true)?,
+    };
+    dest_file.set_permissions(metadata.permissions())?;
+    Ok(written)
+}
+
+const SPARSE_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Copy bytes `[start, end)` from `src` to the same offsets in `dest`, optionally skipping
+/// blocks of zeros. Returns the number of bytes written.
+fn copy_range(
+    src: &mut std::fs::File,
+    dest: &mut std::fs::File,
+    start: u64,
+    end: u64,
+    skip_zeros: bool,
+) -> std::io::Result<u64> {
+    use std::io::Read;
+    use std::io::Seek;
+    use std::io::SeekFrom;
+    use std::io::Write;
+
+    let mut buf = vec![0u8; SPARSE_BLOCK_SIZE];
+    let mut pos = start;
+    let mut written = 0;
+    src.seek(SeekFrom::Start(start))?;
+    while pos < end {
+        let to_read = std::cmp::min(buf.len() as u64, end - pos) as usize;
+        let read = src.read(&mut buf[..to_read])?;
+        if read == 0 {
+            break;
+        }
+        if !(skip_zeros && buf[..read].iter().all(|b| *b == 0)) {
+            dest.seek(SeekFrom::Start(pos))?;
+            dest.write_all(&buf[..read])?;
+            written += read as u64;
+        }
+        pos += read as u64;
+    }
+    Ok(written)
+}
+
+/// The `[start, end)` ranges of `file` that contain data, or `None` if they cannot be found.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn data_regions(file: &std::fs::File, len: u64) -> std::io::Result<Option<Vec<(u64, u64)>>> {
+    use std::os::fd::AsRawFd;
+
+    let seek = |offset: u64, whence: libc::c_int| -> std::io::Result<Option<u64>> {
+        // SAFETY: The fd is open for the duration of the call.
+        let res = unsafe { libc::lseek(file.as_raw_fd(), offset as libc::off_t, whence) };
+        if res >= 0 {
+            Ok(Some(res as u64))
+        } else {
+            let e = std::io::Error::last_os_error();
+            match e.raw_os_error() {
+                // There is no more data after `offset`.
+                Some(libc::ENXIO) => Ok(None),
+                _ => Err(e),
+            }
+        }
+    };
+
+    let mut regions = vec![];
+    let mut pos = 0;
+    while pos < len {
+        let start = match seek(pos, libc::SEEK_DATA) {
+            Ok(Some(start)) => start,
+            Ok(None) => break,
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let end = seek(start, libc::SEEK_HOLE)?.unwrap_or(len);
+        regions.push((start, end));
+        pos = end;
+    }
+    Ok(Some(regions))
+}
+
+/// The `[start, end)` ranges of `file` that contain data, or `None` if they cannot be found.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn data_regions(_file: &std::fs::File, _len: u64) -> std::io::Result<Option<Vec<(u64, u64)>>> {
+    Ok(None)
+}
+
+/// With the `fs-context` feature, wrap `e` in a [`crate::PathIoError`] that says which
+/// operation failed on which path.
+#[cfg(feature = "fs-context")]
+fn with_context(e: std::io::Error, operation: &str, path: &Path) -> std::io::Error {
+    std::io::Error::new(
+        e.kind(),
+        crate::PathIoError(operation.to_owned(), path.display().to_string(), e),
+    )
+}
+
+#[cfg(not(feature = "fs-context"))]
+fn with_context(e: std::io::Error, _operation: &str, _path: &Path) -> std::io::Error {
+    e
+}
+
+#[cfg(feature = "fs-context")]
+fn with_copy_context(e: std::io::Error, src: &Path, dest: &Path) -> std::io::Error {
+    with_context(e, &format!("copy `{}` to", src.display()), dest)
+}
+
+#[cfg(not(feature = "fs-context"))]
+fn with_copy_context(e: std::io::Error, _src: &Path, _dest: &Path) -> std::io::Error {
+    e
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Seek;
+    use std::io::SeekFrom;
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use crate::fs::check_exists_many;
+    use crate::fs::copy_dir;
+    use crate::fs::copy_range;
+    use crate::fs::copy_sparse;
+    use crate::fs::create_dir_all;
+    use crate::fs::create_dir_all_tracked;
+    use crate::fs::partition_by_kind;
+    use crate::fs::reflink;
+    use crate::fs::remove;
+    use crate::fs::set_audit_hook;
+    use crate::fs::write_atomic;
+    use crate::fs::AuditHook;
+    use crate::fs::Mode;
+    use crate::fs::Operation;
+    use crate::fs::PathKinds;
+    use crate::AbsolutePath;
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn checks_many_paths() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let existing = root.join("foo")?;
+        let missing = root.join("bar")?;
+        std::fs::write(&existing, "foo")?;
+
+        assert_eq!(
+            vec![true, false, true],
+            check_exists_many([
+                existing.as_absolute_path(),
+                missing.as_absolute_path(),
+                root.as_absolute_path()
+            ])
+        );
+        assert_eq!(
+            vec![false, true],
+            check_exists_many(&[missing.clone(), existing.clone()])
+        );
+        assert!(check_exists_many(Vec::<&AbsolutePath>::new()).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn creates_dirs_tracked() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::create_dir(root.join("a")?)?;
+
+        assert_eq!(
+            vec![root.join("a/b")?, root.join("a/b/c")?],
+            create_dir_all_tracked(&root.join("a/b/c")?)?
+        );
+        assert!(root.join("a/b/c")?.is_dir());
+        assert!(create_dir_all_tracked(&root.join("a/b/c")?)?.is_empty());
+        assert!(create_dir_all_tracked(&root)?.is_empty());
+
+        std::fs::write(root.join("a/file")?, "")?;
+        assert!(create_dir_all_tracked(&root.join("a/file/d")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn audits_and_vetoes_changes() -> anyhow::Result<()> {
+        struct Hook {
+            root: AbsolutePathBuf,
+            seen: Mutex<Vec<Operation>>,
+        }
+
+        impl AuditHook for Hook {
+            fn before(&self, operation: &Operation, path: &AbsolutePath) -> std::io::Result<()> {
+                // Other tests run at the same time, and also call the global hook.
+                if !path.starts_with(&self.root) {
+                    return Ok(());
+                }
+                self.seen.lock().unwrap().push(operation.clone());
+                if path
+                    .file_name()
+                    .is_some_and(|n| n.as_os_str() == "protected")
+                {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::PermissionDenied,
+                        "protected",
+                    ));
+                }
+                Ok(())
+            }
+        }
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let hook = Arc::new(Hook {
+            root: root.clone(),
+            seen: Mutex::new(vec![]),
+        });
+        set_audit_hook(Some(hook.clone()));
+
+        let written = write_atomic(&root.join("a.txt")?, "a");
+        let vetoed = write_atomic(&root.join("protected")?, "");
+        let removed = remove(&root.join("a.txt")?, Mode::Execute);
+        let dry_run = create_dir_all(&root.join("x/y")?, Mode::DryRun);
+        let backup_target = root.join("b.txt")?;
+        let backed_up = backup_target
+            .write_with_backup("b", crate::BackupStyle::Simple)
+            .and_then(|_| backup_target.write_with_backup("c", crate::BackupStyle::Simple));
+        set_audit_hook(None);
+
+        written?;
+        assert_eq!(
+            std::io::ErrorKind::PermissionDenied,
+            vetoed.unwrap_err().kind()
+        );
+        assert!(!root.join("protected")?.exists());
+        removed?;
+        dry_run?;
+        backed_up?;
+        assert_eq!(
+            vec![
+                Operation::Write(root.join("a.txt")?),
+                Operation::Write(root.join("protected")?),
+                Operation::Remove(root.join("a.txt")?),
+                Operation::Write(root.join("b.txt")?),
+                Operation::Copy {
+                    from: root.join("b.txt")?,
+                    to: root.join("b.txt.bak")?,
+                },
+                Operation::Write(root.join("b.txt")?),
+            ],
+            *hook.seen.lock().unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn supports_dry_runs() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let src = root.join("src")?;
+        std::fs::create_dir_all(src.join("nested")?)?;
+        std::fs::write(src.join("a.txt")?, "a")?;
+        std::fs::write(src.join("nested/b.txt")?, "b")?;
+        let dest = root.join("out/copy")?;
+
+        let expected_copy = vec![
+            Operation::CreateDir(root.join("out")?),
+            Operation::CreateDir(dest.clone()),
+            Operation::Copy {
+                from: src.join("a.txt")?,
+                to: dest.join("a.txt")?,
+            },
+            Operation::CreateDir(dest.join("nested")?),
+            Operation::Copy {
+                from: src.join("nested/b.txt")?,
+                to: dest.join("nested/b.txt")?,
+            },
+        ];
+        assert_eq!(expected_copy, copy_dir(&src, &dest, Mode::DryRun)?);
+        assert!(!root.join("out")?.exists());
+        assert_eq!(expected_copy, copy_dir(&src, &dest, Mode::Execute)?);
+        assert_eq!("b", std::fs::read_to_string(dest.join("nested/b.txt")?)?);
+
+        let dir = root.join("x/y")?;
+        let expected_dirs = vec![
+            Operation::CreateDir(root.join("x")?),
+            Operation::CreateDir(dir.clone()),
+        ];
+        assert_eq!(expected_dirs, create_dir_all(&dir, Mode::DryRun)?);
+        assert!(!dir.exists());
+        assert_eq!(expected_dirs, create_dir_all(&dir, Mode::Execute)?);
+        assert!(dir.is_dir());
+
+        assert_eq!(
+            vec![Operation::Remove(dest.clone())],
+            remove(&dest, Mode::DryRun)?
+        );
+        assert!(dest.exists());
+        remove(&dest, Mode::Execute)?;
+        assert!(!dest.exists());
+        assert!(remove(&dest, Mode::DryRun).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn writes_atomically() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = root.join("file.txt")?;
+
+        write_atomic(&path, "first")?;
+        assert_eq!("first", std::fs::read_to_string(&path)?);
+        write_atomic(&path, b"second")?;
+        assert_eq!("second", std::fs::read_to_string(&path)?);
+        assert_eq!(1, std::fs::read_dir(&root)?.count());
+        assert!(write_atomic(&root.join("missing/file.txt")?, "").is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_permissions_when_writing_atomically() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let mode = |path: &AbsolutePath| -> anyhow::Result<u32> {
+            Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+        };
+
+        let path = root.join("config.toml")?;
+        std::fs::write(&path, "first")?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644))?;
+        write_atomic(&path, "second")?;
+        assert_eq!("second", std::fs::read_to_string(&path)?);
+        assert_eq!(0o644, mode(&path)?);
+
+        let expected = root.join("expected.txt")?;
+        std::fs::write(&expected, "")?;
+        let path = root.join("new.txt")?;
+        write_atomic(&path, "")?;
+        assert_eq!(mode(&expected)?, mode(&path)?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn creates_with_permissions() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        use crate::fs::create_dir_all_with_options;
+        use crate::fs::write_atomic_with_options;
+        use crate::fs::write_with_options;
+        use crate::fs::CreateOptions;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let mode = |path: &AbsolutePath| -> anyhow::Result<u32> {
+            Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+        };
+
+        let secret = CreateOptions::new().mode(0o600);
+        let path = root.join("secret.txt")?;
+        std::fs::write(&path, "")?;
+        write_with_options(&path, "secret", &secret)?;
+        assert_eq!("secret", std::fs::read_to_string(&path)?);
+        assert_eq!(0o600, mode(&path)?);
+
+        let path = root.join("atomic.txt")?;
+        write_atomic_with_options(&path, "secret", &secret)?;
+        assert_eq!("secret", std::fs::read_to_string(&path)?);
+        assert_eq!(0o600, mode(&path)?);
+
+        let shared = CreateOptions::new().mode(0o777).umask_respect(false);
+        let root_mode = mode(&root)?;
+        let path = root.join("shared/nested")?;
+        create_dir_all_with_options(&path, Mode::Execute, &shared)?;
+        assert_eq!(0o777, mode(&path)?);
+        assert_eq!(0o777, mode(&root.join("shared")?)?);
+        assert_eq!(root_mode, mode(&root)?);
+
+        let path = root.join("shared/nested/file.txt")?;
+        write_atomic_with_options(&path, "", &shared.mode(0o666))?;
+        assert_eq!(0o666, mode(&path)?);
+
+        let expected = root.join("expected.txt")?;
+        std::fs::write(&expected, "")?;
+        let path = root.join("default.txt")?;
+        write_atomic_with_options(&path, "", &CreateOptions::new())?;
+        assert_eq!(mode(&expected)?, mode(&path)?);
+        let path = root.join("default-written.txt")?;
+        write_with_options(&path, "", &CreateOptions::default())?;
+        assert_eq!(mode(&expected)?, mode(&path)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "fs-context")]
+    #[test]
+    fn adds_context_to_errors() -> anyhow::Result<()> {
+        use std::error::Error;
+
+        use crate::PathIoError;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let missing = root.join("missing/file.txt")?;
+
+        let err = write_atomic(&missing, "").unwrap_err();
+        assert_eq!(std::io::ErrorKind::NotFound, err.kind());
+        let inner = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<PathIoError>())
+            .expect("a PathIoError");
+        assert!(inner
+            .to_string()
+            .starts_with(&format!("failed to write `{}`: ", missing.display())));
+        assert_eq!(
+            Some(std::io::ErrorKind::NotFound),
+            inner
+                .source()
+                .and_then(|e| e.downcast_ref::<std::io::Error>())
+                .map(|e| e.kind())
+        );
+
+        let err = copy_sparse(&missing, &root.join("dest")?).unwrap_err();
+        assert!(err.to_string().starts_with(&format!(
+            "failed to copy `{}` to `{}`",
+            missing.display(),
+            root.join("dest")?.display()
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn partitions_by_kind() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let file = root.join("file")?;
+        let dir = root.join("dir")?;
+        let missing = root.join("missing")?;
+        std::fs::write(&file, "")?;
+        std::fs::create_dir(&dir)?;
+
+        assert_eq!(
+            PathKinds {
+                files: vec![file.clone(), file.clone()],
+                dirs: vec![dir.clone(), root.clone()],
+                missing: vec![missing.clone()],
+            },
+            partition_by_kind([file.clone(), dir, missing, root, file])
+        );
+        assert_eq!(PathKinds::default(), partition_by_kind(vec![]));
+        Ok(())
+    }
+
+    #[test]
+    fn reflinks_or_copies() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let src = root.join("src.txt")?;
+        let dest = root.join("dest.txt")?;
+        std::fs::write(&src, "contents")?;
+        std::fs::write(&dest, "old contents that are longer")?;
+
+        reflink(&src, &dest)?;
+        assert_eq!("contents", std::fs::read_to_string(&dest)?);
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            reflink(&root.join("missing")?, &dest).unwrap_err().kind()
+        );
+        assert_eq!(
+            std::io::ErrorKind::InvalidInput,
+            reflink(&src, &src).unwrap_err().kind()
+        );
+        assert_eq!("contents", std::fs::read_to_string(&src)?);
+        assert_eq!(2, std::fs::read_dir(&root)?.count());
+        Ok(())
+    }
+
+    #[test]
+    fn copies_sparse_files() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let src = root.join("src.img")?;
+        let dest = root.join("dest.img")?;
+        let zero_detect_dest = root.join("zero_detect.img")?;
+
+        let len = 4 * 1024 * 1024;
+        let mut file = std::fs::File::create(&src)?;
+        file.set_len(len)?;
+        file.seek(SeekFrom::Start(len / 2))?;
+        file.write_all(b"data")?;
+        drop(file);
+        let expected = std::fs::read(&src)?;
+
+        let written = copy_sparse(&src, &dest)?;
+        assert!(written >= 4 && written < len);
+        assert_eq!(expected, std::fs::read(&dest)?);
+
+        let mut src_file = std::fs::File::open(&src)?;
+        let mut dest_file = std::fs::File::create(&zero_detect_dest)?;
+        dest_file.set_len(len)?;
+        let written = copy_range(&mut src_file, &mut dest_file, 0, len, true)?;
+        assert!(written < len);
+        assert_eq!(expected, std::fs::read(&zero_detect_dest)?);
+
+        if src.allocated_size()? < len {
+            assert!(dest.allocated_size()? < len);
+            assert!(zero_detect_dest.allocated_size()? < len);
+        }
+
+        assert_eq!(
+            std::io::ErrorKind::InvalidInput,
+            copy_sparse(&src, &src).unwrap_err().kind()
+        );
+        assert_eq!(expected, std::fs::read(&src)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+//! Helpers for fuzzing code that consumes paths, with the `arbitrary` feature.
+//!
+//! [`AbsolutePathBuf`](crate::AbsolutePathBuf), [`RelativePathBuf`](crate::RelativePathBuf) and
+//! [`CombinedPathBuf`](crate::CombinedPathBuf) implement [`arbitrary::Arbitrary`], and only
+//! generate valid paths. To also exercise the code that validates paths, use [`RawPath`],
+//! which is often invalid on purpose.
+
+use arbitrary::Arbitrary;
+use arbitrary::Unstructured;
+
+/// A path string that is often invalid on purpose, e.g. for fuzzing parsers and
+/// `try_new()` calls.
+///
+/// Strings mix absolute and relative forms, `.` and `..` components (including ones that would
+/// go above the root), empty components, both kinds of separator, trailing separators and
+/// stray NUL characters.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct RawPath(pub String);
+
+impl<'a> Arbitrary<'a> for RawPath {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut raw = match u.int_in_range(0..=2)? {
+            0 => String::new(),
+            1 => "/".to_owned(),
+            _ => root(u)?,
+        };
+        for i in 0..u.int_in_range(0..=6)? {
+            if i > 0 {
+                raw.push(*u.choose(&['/', '\\'])?);
+            }
+            match u.int_in_range(0..=7)? {
+                0 => {}
+                1 => raw.push('.'),
+                2 | 3 => raw.push_str(".."),
+                4 => raw.push('\0'),
+                _ => raw.push_str(&component(u)?),
+            }
+        }
+        if u.ratio(1, 8)? {
+            raw.push('/');
+        }
+        Ok(RawPath(raw))
+    }
+}
+
+/// The root of a generated absolute path.
+pub(crate) fn root(u: &mut Unstructured) -> arbitrary::Result<String> {
+    #[cfg(windows)]
+    {
+        Ok(format!("{}:\\", *u.choose(&['C', 'D', 'Z'])?))
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = u;
+        Ok("/".to_owned())
+    }
+}
+
+/// A generated file name that is not `.` or `..`.
+///
+/// Names are mostly short and from a small alphabet, so generated paths often share prefixes
+/// or collide, and are sometimes arbitrary unicode.
+pub(crate) fn component(u: &mut Unstructured) -> arbitrary::Result<String> {
+    let name: String = if u.ratio(1, 4)? {
+        u.arbitrary::<String>()?
+            .chars()
+            .filter(|&c| !std::path::is_separator(c) && c != '\0' && c != ':')
+            .collect()
+    } else {
+        let len = u.int_in_range(1..=8)?;
+        (0..len)
+            .map(|_| u.choose(&['a', 'b', 'c', 'x', '.', '-', '_']).copied())
+            .collect::<arbitrary::Result<_>>()?
+    };
+    match name.as_str() {
+        "" | "." | ".." => Ok("_".to_owned()),
+        _ => Ok(name),
+    }
+}
+
+/// Generated components for a path, e.g. `a/b` for `["a", "b"]`.
+pub(crate) fn components(u: &mut Unstructured) -> arbitrary::Result<Vec<String>> {
+    (0..u.int_in_range(0..=6)?).map(|_| component(u)).collect()
+}
+
+/// Generate `count` values of `T` from deterministic bytes.
+#[cfg(test)]
+pub(crate) fn generate<T: for<'a> Arbitrary<'a>>(count: usize) -> anyhow::Result<Vec<T>> {
+    let bytes: Vec<u8> = (0..65536u32)
+        .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+        .collect();
+    let mut u = Unstructured::new(&bytes);
+    (0..count).map(|_| Ok(T::arbitrary(&mut u)?)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzzing::RawPath;
+    use crate::CombinedPathBuf;
+
+    #[test]
+    fn generates_raw_paths() -> anyhow::Result<()> {
+        let raw: Vec<RawPath> = crate::fuzzing::generate(200)?;
+        let valid = raw
+            .iter()
+            .filter(|RawPath(raw)| CombinedPathBuf::try_new(raw).is_ok())
+            .count();
+        assert!(valid > 0);
+        assert!(valid < raw.len());
+        Ok(())
+    }
+}
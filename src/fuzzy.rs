@@ -0,0 +1,227 @@
+use std::path::Component;
+
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// The score for each matched character.
+const SCORE_MATCH: i32 = 16;
+/// The penalty for the first character of a gap between matched characters.
+const PENALTY_GAP_START: i32 = 3;
+/// The penalty for each further character of a gap.
+const PENALTY_GAP_EXTENSION: i32 = 1;
+/// The bonus for matching the first character of a path component.
+const BONUS_SEPARATOR: i32 = 10;
+/// The bonus for matching the first character of a word after `-`, `_`, `.` or a space.
+const BONUS_WORD: i32 = 8;
+/// The bonus for matching an uppercase letter after a lowercase one, or a digit after a letter.
+const BONUS_CAMEL: i32 = 7;
+/// The least bonus for matching a character right after the previously matched one.
+const BONUS_CONSECUTIVE: i32 = 4;
+/// The bonus for each character matched in the file name, i.e. the last component.
+const BONUS_FILE_NAME: i32 = 2;
+
+/// Fuzzily match `query` against each of `candidates`, like fzf, e.g. for an editor's quick
+/// open. Returns the candidates that match, best first, with their scores.
+///
+/// A candidate matches if it contains all of the characters of `query` in order, with anything
+/// in between. Matches at the start of a path component score best, then the start of words
+/// (after `-`, `_`, `.` or a space, or camel case), and runs of consecutive characters and
+/// characters in the file name score more than scattered ones. A `/` or `\` in the query only
+/// matches a separator, so `src/lib` requires `lib` to be in a later component than `src`.
+///
+/// Matching is case insensitive unless the query contains an uppercase letter. Only ASCII letters
+/// are case folded. Equal scores are ordered by shorter path, then by the order of `candidates`.
+/// An empty query matches every candidate with a score of `0`.
+pub fn fuzzy_match<'a>(
+    candidates: &'a [RelativePathBuf],
+    query: &str,
+) -> Vec<(i32, &'a RelativePathBuf)> {
+    let query: Vec<u8> = query
+        .bytes()
+        .map(|b| if b == b'\\' { b'/' } else { b })
+        .collect();
+    let case_sensitive = query.iter().any(u8::is_ascii_uppercase);
+
+    // Reused between candidates so that each one does not need its own allocations.
+    let mut scratch = Scratch::default();
+    let mut matches: Vec<_> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            scratch.load(candidate);
+            let score = scratch.score(&query, case_sensitive)?;
+            Some((score, scratch.text.len(), index, candidate))
+        })
+        .collect();
+    matches.sort_by(
+        |(l_score, l_len, l_index, _), (r_score, r_len, r_index, _)| {
+            r_score
+                .cmp(l_score)
+                .then(l_len.cmp(r_len))
+                .then(l_index.cmp(r_index))
+        },
+    );
+    matches
+        .into_iter()
+        .map(|(score, _, _, candidate)| (score, candidate))
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct Scratch {
+    /// The candidate's components joined with `/`.
+    text: Vec<u8>,
+    /// Where the file name starts in `text`.
+    file_name_start: usize,
+    /// The best score for the previous query character matched at each position of `text`.
+    previous: Vec<Option<i32>>,
+    current: Vec<Option<i32>>,
+}
+
+impl Scratch {
+    fn load(&mut self, path: &RelativePath) {
+        self.text.clear();
+        self.file_name_start = 0;
+        for component in path.components() {
+            if component == Component::CurDir {
+                continue;
+            }
+            if !self.text.is_empty() {
+                self.text.push(b'/');
+            }
+            self.file_name_start = self.text.len();
+            self.text
+                .extend_from_slice(component.as_os_str().as_encoded_bytes());
+        }
+    }
+
+    /// The score of the best alignment of `query` with `text`, if there is one.
+    fn score(&mut self, query: &[u8], case_sensitive: bool) -> Option<i32> {
+        if query.is_empty() {
+            return Some(0);
+        }
+        let len = self.text.len();
+        self.previous.clear();
+        self.previous.resize(len, None);
+        self.current.clear();
+        self.current.resize(len, None);
+
+        for (i, &q) in query.iter().enumerate() {
+            // The best score for a match of the previous query character that is followed by a
+            // gap ending just before `j`.
+            let mut best_gap: Option<i32> = None;
+            for j in 0..len {
+                if i > 0 && j >= 2 {
+                    best_gap = [
+                        best_gap.map(|s| s - PENALTY_GAP_EXTENSION),
+                        self.previous[j - 2].map(|s| s - PENALTY_GAP_START),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .max();
+                }
+                let c = self.text[j];
+                let matches = if case_sensitive {
+                    c == q
+                } else {
+                    c.eq_ignore_ascii_case(&q)
+                };
+                if !matches {
+                    self.current[j] = None;
+                    continue;
+                }
+
+                let mut bonus = self.bonus(j);
+                if j >= self.file_name_start {
+                    bonus += BONUS_FILE_NAME;
+                }
+                self.current[j] = if i == 0 {
+                    // Leading characters are not a gap, so the first match is free to go
+                    // anywhere, but the first character of a query is usually the start of
+                    // something.
+                    Some(SCORE_MATCH + bonus * 2)
+                } else {
+                    let consecutive = (j >= 1)
+                        .then(|| self.previous[j - 1])
+                        .flatten()
+                        .map(|s| s + SCORE_MATCH + bonus.max(BONUS_CONSECUTIVE));
+                    let after_gap = best_gap.map(|s| s + SCORE_MATCH + bonus);
+                    consecutive.max(after_gap)
+                };
+            }
+            std::mem::swap(&mut self.previous, &mut self.current);
+        }
+        self.previous.iter().flatten().copied().max()
+    }
+
+    /// The bonus for matching the character at `index` of `text`.
+    fn bonus(&self, index: usize) -> i32 {
+        let Some(&before) = index.checked_sub(1).map(|i| &self.text[i]) else {
+            return BONUS_SEPARATOR;
+        };
+        let c = self.text[index];
+        match before {
+            b'/' => BONUS_SEPARATOR,
+            b'-' | b'_' | b'.' | b' ' if c != before => BONUS_WORD,
+            _ if before.is_ascii_lowercase() && c.is_ascii_uppercase() => BONUS_CAMEL,
+            _ if before.is_ascii_alphabetic() && c.is_ascii_digit() => BONUS_CAMEL,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzzy_match;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn matches_fuzzily() -> anyhow::Result<()> {
+        let candidates = [
+            "README.md",
+            "src/lib.rs",
+            "src/relative.rs",
+            "tests/fixtures/libraries/list.txt",
+            "src/file_system.rs",
+            "lib/src/main.rs",
+        ]
+        .into_iter()
+        .map(RelativePathBuf::try_new)
+        .collect::<Result<Vec<_>, _>>()?;
+        let names = |query: &str| -> Vec<String> {
+            fuzzy_match(&candidates, query)
+                .into_iter()
+                .map(|(_, path)| path.to_lossy_string())
+                .collect()
+        };
+
+        assert_eq!(
+            vec![
+                "src/lib.rs",
+                "lib/src/main.rs",
+                "tests/fixtures/libraries/list.txt"
+            ],
+            names("lib")
+        );
+        assert_eq!(vec!["src/lib.rs"], names("srclib"));
+        // A separator in the query has to match one.
+        assert_eq!(
+            vec!["src/lib.rs", "src/relative.rs", "src/file_system.rs"],
+            names("src/l")
+        );
+        assert_eq!(vec!["src/file_system.rs"], names("fsys"));
+        // Uppercase makes the query case sensitive.
+        assert_eq!(vec!["README.md"], names("readme"));
+        assert_eq!(vec!["README.md"], names("RM"));
+        assert!(names("Readme").is_empty());
+        assert!(names("xyz").is_empty());
+
+        let all = fuzzy_match(&candidates, "");
+        assert_eq!(candidates.len(), all.len());
+        assert!(all.iter().all(|(score, _)| *score == 0));
+
+        let scored = fuzzy_match(&candidates, "lib");
+        assert!(scored[0].0 > scored[1].0);
+        Ok(())
+    }
+}
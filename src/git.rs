@@ -0,0 +1,123 @@
+use std::process::Command;
+use std::process::Stdio;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::RelativePathBuf;
+
+/// The git repository (or worktree) that contains a path.
+///
+/// Discovery only looks for a `.git` directory or file, but [`GitContext::is_tracked`] runs the
+/// `git` executable, which must be on `PATH`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct GitContext {
+    root: AbsolutePathBuf,
+}
+
+impl GitContext {
+    /// Find the repository containing `path`, by looking for `.git` in `path` and its ancestors.
+    ///
+    /// Returns `None` if `path` is not inside of a repository.
+    pub fn discover(path: &AbsolutePath) -> Option<Self> {
+        crate::find_git_root(path).map(|root| Self { root: root.into() })
+    }
+
+    /// The root of the repository's working tree.
+    pub fn repo_root(&self) -> &AbsolutePath {
+        self.root.as_absolute_path()
+    }
+
+    /// Get `path` relative to the repository root, or `None` if it is outside of the repository.
+    ///
+    /// The root itself is returned as an empty path.
+    pub fn to_repo_relative(&self, path: &AbsolutePath) -> Option<RelativePathBuf> {
+        path.strip_prefix(&self.root)
+            .ok()
+            .map(RelativePathBuf::from)
+    }
+
+    /// Whether git is tracking `path`. For directories, this is whether any files beneath them
+    /// are tracked.
+    ///
+    /// Paths outside of the repository are never tracked. Errors are only returned if `git`
+    /// could not be run.
+    pub fn is_tracked(&self, path: &AbsolutePath) -> std::io::Result<bool> {
+        let relative = match self.to_repo_relative(path) {
+            Some(relative) => relative,
+            None => return Ok(false),
+        };
+        // Paths are not pathspecs, so e.g. `*.rs` or `:(top)` are only ever file names.
+        let status = Command::new("git")
+            .arg("--literal-pathspecs")
+            .arg("-C")
+            .arg(self.root.as_path())
+            .args(["ls-files", "--error-unmatch", "--"])
+            .arg(if relative.as_os_str().is_empty() {
+                ".".as_ref()
+            } else {
+                relative.as_os_str()
+            })
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(status.success())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::process::Command;
+
+    use crate::AbsolutePathBuf;
+    use crate::GitContext;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn discovers_repo() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let repo = root.join("repo")?;
+        let nested = repo.join("src/nested")?;
+        std::fs::create_dir_all(&nested)?;
+        std::fs::write(nested.join("tracked.rs")?, "")?;
+        std::fs::write(nested.join("untracked.rs")?, "")?;
+
+        assert!(Command::new("git")
+            .arg("-C")
+            .arg(repo.as_path())
+            .arg("init")
+            .arg("-q")
+            .status()?
+            .success());
+        assert!(Command::new("git")
+            .arg("-C")
+            .arg(repo.as_path())
+            .args(["add", "src/nested/tracked.rs"])
+            .status()?
+            .success());
+
+        let git = GitContext::discover(&nested).expect("a repo to be found");
+        assert_eq!(repo.as_absolute_path(), git.repo_root());
+        assert_eq!(Some(git.clone()), GitContext::discover(&repo));
+        assert_eq!(
+            Some(RelativePathBuf::try_new("src/nested/tracked.rs")?),
+            git.to_repo_relative(&nested.join("tracked.rs")?)
+        );
+        assert_eq!(
+            Some(RelativePathBuf::try_new("")?),
+            git.to_repo_relative(&repo)
+        );
+        assert_eq!(None, git.to_repo_relative(&root));
+
+        assert!(git.is_tracked(&nested.join("tracked.rs")?)?);
+        assert!(git.is_tracked(&nested)?);
+        assert!(git.is_tracked(&repo)?);
+        assert!(!git.is_tracked(&nested.join("untracked.rs")?)?);
+        assert!(!git.is_tracked(&nested.join("missing.rs")?)?);
+        assert!(!git.is_tracked(&root.join("outside.rs")?)?);
+        assert!(!git.is_tracked(&nested.join("*.rs")?)?);
+        assert!(!git.is_tracked(&repo.join(":(glob)src/**")?)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,786 @@
+use std::collections::HashSet;
+use std::path::Component;
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::InvalidGlobPattern;
+use crate::RelativePath;
+
+/// Find all of the paths beneath `root` that match the glob `pattern`.
+///
+/// Patterns are relative to `root`, and use `/` to separate components. The supported syntax is:
+/// - `?` matches any single character, and `*` matches any number of characters, within one
+///   component.
+/// - `**` as a whole component matches zero or more directories.
+/// - `[abc]`, `[a-z]` and `[!a-z]` match a single character in (or not in) the class.
+/// - `{a,b}` matches either of the comma separated alternatives. These may be nested, and may
+///   contain `/`.
+/// - `!(a|b)` matches anything within one component except the `|` separated alternatives.
+/// - `\` escapes the next character.
+/// - A leading `(?i)` makes the whole pattern case-insensitive.
+///
+/// Entries starting with `.` are only matched by components that also start with `.`, and `**`
+/// does not descend into them, or follow symlinks to directories. Each matching path is
+/// returned once, and directories that cannot be read are skipped.
+pub fn glob(
+    root: &AbsolutePath,
+    pattern: &str,
+) -> Result<impl Iterator<Item = AbsolutePathBuf>, InvalidGlobPattern> {
+    let pattern = GlobPattern::new(pattern)?;
+    Ok(GlobWalk::new(pattern, root))
+}
+
+/// Selects relative paths using include and exclude glob patterns, with the syntax used by
+/// [`glob`].
+///
+/// A path matches if it matches any of the include patterns (or there are none), and none of
+/// the exclude patterns.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PathFilter {
+    include: Vec<GlobPattern>,
+    exclude: Vec<GlobPattern>,
+}
+
+impl PathFilter {
+    /// Create a filter that matches every path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern that paths must match.
+    pub fn include(mut self, pattern: &str) -> Result<Self, InvalidGlobPattern> {
+        self.include.push(GlobPattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Add a pattern that paths must not match.
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, InvalidGlobPattern> {
+        self.exclude.push(GlobPattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Whether this filter has no patterns, and so matches every path.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether `path` is selected by this filter.
+    pub fn matches(&self, path: &RelativePath) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches_relative(path)))
+            && !self.exclude.iter().any(|p| p.matches_relative(path))
+    }
+}
+
+/// A glob pattern that has been compiled for matching one path component at a time, with the
+/// syntax used by [`glob`], e.g. for testing ignore or include rules against paths directly.
+///
+/// ```
+/// use paths::AbsolutePath;
+/// use paths::GlobPattern;
+/// use paths::RelativePath;
+///
+/// let pattern = GlobPattern::new("src/**/*.rs").unwrap();
+/// assert!(pattern.matches_relative(RelativePath::new_unchecked("src/bin/main.rs")));
+/// assert!(!pattern.matches_relative(RelativePath::new_unchecked("tests/main.rs")));
+/// assert!(pattern.matches(AbsolutePath::new_unchecked("/repo/src/lib.rs")));
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GlobPattern {
+    /// The components of each expansion of any `{a,b}` groups that contain `/`.
+    alternatives: Vec<Vec<Segment>>,
+    case_insensitive: bool,
+    /// Whether wildcards match names starting with `.`.
+    match_hidden: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Segment {
+    /// `**`, which matches zero or more directories.
+    AnyDirs,
+    /// A component with no wildcards, which can be looked up directly.
+    Literal(String),
+    /// A component with wildcards.
+    Pattern(Vec<Token>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyString,
+    /// `[...]`, or `[!...]` if negated.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// `{a,b}`
+    Alternation(Vec<Vec<Token>>),
+    /// `!(a|b)`
+    Negation(Vec<Vec<Token>>),
+}
+
+impl GlobPattern {
+    /// Compile `pattern`, which must be relative.
+    pub fn new(pattern: &str) -> Result<Self, InvalidGlobPattern> {
+        let invalid = |reason: &str| InvalidGlobPattern(pattern.to_owned(), reason.to_owned());
+        let (case_insensitive, rest) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        if rest.starts_with('/') || Path::new(rest).is_absolute() {
+            return Err(invalid("patterns must be relative"));
+        }
+        let alternatives = expand_braces(rest)
+            .and_then(|expanded| {
+                expanded
+                    .iter()
+                    .map(|p| parse_segments(p, case_insensitive))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(invalid)?;
+        Ok(Self {
+            alternatives,
+            case_insensitive,
+            match_hidden: false,
+        })
+    }
+
+    /// Let wildcards and `**` match names starting with `.`, as in `.gitignore` files.
+    pub(crate) fn matching_hidden(mut self) -> Self {
+        self.match_hidden = true;
+        self
+    }
+
+    /// Whether `path` matches this pattern beneath any of its ancestors, e.g. `src/*.rs` matches
+    /// `/repo/src/lib.rs`, like a `.gitignore` pattern that is not anchored to a directory.
+    pub fn matches(&self, path: &AbsolutePath) -> bool {
+        let mut components = vec![];
+        for component in path.as_path().components() {
+            if let Component::Normal(name) = component {
+                match name.to_str() {
+                    Some(name) => components.push(name),
+                    // Only the components after this one can match.
+                    None => components.clear(),
+                }
+            }
+        }
+        (0..=components.len()).any(|start| self.matches_components(&components[start..]))
+    }
+
+    /// Whether the whole of `path` matches this pattern.
+    pub fn matches_relative(&self, path: &RelativePath) -> bool {
+        let mut components = vec![];
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::Normal(name) => match name.to_str() {
+                    Some(name) => components.push(name),
+                    None => return false,
+                },
+                _ => return false,
+            }
+        }
+        self.matches_components(&components)
+    }
+
+    fn matches_components(&self, components: &[&str]) -> bool {
+        self.alternatives
+            .iter()
+            .any(|segments| self.match_segments(segments, components))
+    }
+
+    fn match_segments(&self, segments: &[Segment], components: &[&str]) -> bool {
+        match segments.split_first() {
+            None => components.is_empty(),
+            Some((Segment::AnyDirs, rest)) => {
+                let visible = components
+                    .iter()
+                    .take_while(|c| self.match_hidden || !c.starts_with('.'))
+                    .count();
+                (0..=visible).any(|i| self.match_segments(rest, &components[i..]))
+            }
+            Some((segment, rest)) => match components.split_first() {
+                Some((name, components)) => {
+                    segment.matches(name, self.case_insensitive, self.match_hidden)
+                        && self.match_segments(rest, components)
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// Find the `{a,b}` group that starts at `open`, returning the positions of its top level
+/// commas and its closing brace.
+fn find_group(chars: &[char], open: usize) -> Option<Vec<usize>> {
+    let mut depth = 0;
+    let mut splits = vec![open];
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                splits.push(i);
+                return Some(splits);
+            }
+            '}' => depth -= 1,
+            ',' if depth == 0 => splits.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Expand the first `{a,b}` group in `pattern` that contains a `/`, and then recursively expand
+/// the results. Groups within a single component are left to be matched by [`Token::Alternation`].
+fn expand_braces(pattern: &str) -> Result<Vec<String>, &'static str> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => {
+                let splits = find_group(&chars, i).ok_or("unclosed `{`")?;
+                let close = *splits.last().unwrap();
+                if !chars[i..close].contains(&'/') {
+                    i = close;
+                } else {
+                    let prefix: String = chars[..i].iter().collect();
+                    let suffix: String = chars[close + 1..].iter().collect();
+                    let mut expanded = vec![];
+                    for bounds in splits.windows(2) {
+                        let alternative: String = chars[bounds[0] + 1..bounds[1]].iter().collect();
+                        expanded.extend(expand_braces(&format!(
+                            "{}{}{}",
+                            prefix, alternative, suffix
+                        ))?);
+                    }
+                    return Ok(expanded);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    Ok(vec![pattern.to_owned()])
+}
+
+fn parse_segments(pattern: &str, case_insensitive: bool) -> Result<Vec<Segment>, &'static str> {
+    let mut segments = vec![];
+    for component in pattern.split('/') {
+        let segment = match component {
+            "" | "." => continue,
+            ".." => return Err("`..` is not supported"),
+            "**" if segments.last() == Some(&Segment::AnyDirs) => continue,
+            "**" => Segment::AnyDirs,
+            _ => {
+                let tokens = parse_tokens(component)?;
+                let literal: Option<String> = tokens
+                    .iter()
+                    .map(|t| match t {
+                        Token::Char(c) => Some(*c),
+                        _ => None,
+                    })
+                    .collect();
+                match literal {
+                    // Case-insensitive literals still have to be found by listing the directory.
+                    Some(literal) if !case_insensitive => Segment::Literal(literal),
+                    _ => Segment::Pattern(tokens),
+                }
+            }
+        };
+        segments.push(segment);
+    }
+    if segments.is_empty() {
+        return Err("the pattern is empty");
+    }
+    Ok(segments)
+}
+
+fn parse_tokens(component: &str) -> Result<Vec<Token>, &'static str> {
+    let chars: Vec<char> = component.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let token = match chars[i] {
+            '\\' => {
+                i += 1;
+                Token::Char(*chars.get(i).ok_or("trailing `\\`")?)
+            }
+            '?' => Token::AnyChar,
+            '*' => {
+                while chars.get(i + 1) == Some(&'*') {
+                    i += 1;
+                }
+                Token::AnyString
+            }
+            '[' => {
+                let (token, close) = parse_class(&chars, i)?;
+                i = close;
+                token
+            }
+            '{' => {
+                let splits = find_group(&chars, i).ok_or("unclosed `{`")?;
+                i = *splits.last().unwrap();
+                Token::Alternation(parse_alternatives(&chars, &splits)?)
+            }
+            '!' if chars.get(i + 1) == Some(&'(') => {
+                let splits = find_negation(&chars, i + 1).ok_or("unclosed `!(`")?;
+                i = *splits.last().unwrap();
+                Token::Negation(parse_alternatives(&chars, &splits)?)
+            }
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Find the `(a|b)` group that starts at `open`, returning the positions of its top level `|`s
+/// and its closing parenthesis.
+fn find_negation(chars: &[char], open: usize) -> Option<Vec<usize>> {
+    let mut depth = 0;
+    let mut splits = vec![open];
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                splits.push(i);
+                return Some(splits);
+            }
+            ')' => depth -= 1,
+            '|' if depth == 0 => splits.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parse the text between each pair of `splits` as its own list of tokens.
+fn parse_alternatives(chars: &[char], splits: &[usize]) -> Result<Vec<Vec<Token>>, &'static str> {
+    splits
+        .windows(2)
+        .map(|bounds| parse_tokens(&chars[bounds[0] + 1..bounds[1]].iter().collect::<String>()))
+        .collect()
+}
+
+/// Parse the character class that starts at `open`, returning it and the position of its
+/// closing `]`.
+fn parse_class(chars: &[char], open: usize) -> Result<(Token, usize), &'static str> {
+    let mut i = open + 1;
+    let negated = matches!(chars.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = vec![];
+    // A `]` at the start of the class is a literal.
+    while chars.get(i) != Some(&']') || ranges.is_empty() {
+        let start = class_char(chars, &mut i)?;
+        let mut end = start;
+        // A `-` at the end of the class is a literal.
+        if chars.get(i) == Some(&'-') && !matches!(chars.get(i + 1), Some(']') | None) {
+            i += 1;
+            end = class_char(chars, &mut i)?;
+        }
+        ranges.push((start, end));
+    }
+    Ok((Token::Class { negated, ranges }, i))
+}
+
+/// The possibly escaped character at `i` in a character class, moving `i` past it.
+fn class_char(chars: &[char], i: &mut usize) -> Result<char, &'static str> {
+    let mut c = chars.get(*i).ok_or("unclosed `[`")?;
+    if *c == '\\' {
+        *i += 1;
+        c = chars.get(*i).ok_or("unclosed `[`")?;
+    }
+    *i += 1;
+    Ok(*c)
+}
+
+impl Segment {
+    fn matches(&self, name: &str, case_insensitive: bool, match_hidden: bool) -> bool {
+        match self {
+            Segment::AnyDirs => true,
+            Segment::Literal(literal) => literal == name,
+            Segment::Pattern(tokens) => {
+                if !match_hidden && name.starts_with('.') && !starts_with_dot(tokens) {
+                    return false;
+                }
+                let name: Vec<char> = name.chars().collect();
+                match_tokens(tokens, &name, case_insensitive)
+            }
+        }
+    }
+}
+
+/// Whether `tokens` explicitly match a leading `.`, and so may match hidden entries.
+fn starts_with_dot(tokens: &[Token]) -> bool {
+    match tokens.first() {
+        Some(Token::Char('.')) => true,
+        Some(Token::Alternation(alternatives)) => alternatives.iter().any(|a| starts_with_dot(a)),
+        _ => false,
+    }
+}
+
+impl Token {
+    fn matches_char(&self, c: char, case_insensitive: bool) -> bool {
+        let matches = |c: char| match self {
+            Token::Char(expected) => *expected == c,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(start, end)| *start <= c && c <= *end) != *negated
+            }
+            _ => true,
+        };
+        if case_insensitive {
+            c.to_lowercase().chain(c.to_uppercase()).any(matches)
+        } else {
+            matches(c)
+        }
+    }
+}
+
+/// Whether `tokens` match all of `name`.
+///
+/// Whether the tokens from each index match the rest of the name from each index is only worked
+/// out once, as otherwise every `*` multiplies the number of ways the name is split up, e.g. for
+/// `*a*a*a*a*b` and a long run of `a`s.
+fn match_tokens(tokens: &[Token], name: &[char], case_insensitive: bool) -> bool {
+    TokenMatcher {
+        tokens,
+        name,
+        case_insensitive,
+        memo: vec![None; (tokens.len() + 1) * (name.len() + 1)],
+    }
+    .matches_from(0, 0)
+}
+
+struct TokenMatcher<'a> {
+    tokens: &'a [Token],
+    name: &'a [char],
+    case_insensitive: bool,
+    /// Whether `tokens[t..]` matches `name[n..]`, at `t * (name.len() + 1) + n`.
+    memo: Vec<Option<bool>>,
+}
+
+impl TokenMatcher<'_> {
+    fn matches_from(&mut self, t: usize, n: usize) -> bool {
+        let key = t * (self.name.len() + 1) + n;
+        if let Some(matched) = self.memo[key] {
+            return matched;
+        }
+        let tokens = self.tokens;
+        let name = self.name;
+        let case_insensitive = self.case_insensitive;
+        let matched = match tokens.get(t) {
+            None => n == name.len(),
+            Some(Token::AnyString) => (n..=name.len()).any(|i| self.matches_from(t + 1, i)),
+            Some(Token::Alternation(alternatives)) => (n..=name.len()).any(|i| {
+                self.matches_from(t + 1, i)
+                    && alternatives
+                        .iter()
+                        .any(|alt| match_tokens(alt, &name[n..i], case_insensitive))
+            }),
+            Some(Token::Negation(alternatives)) => (n..=name.len()).any(|i| {
+                self.matches_from(t + 1, i)
+                    && !alternatives
+                        .iter()
+                        .any(|alt| match_tokens(alt, &name[n..i], case_insensitive))
+            }),
+            Some(token) => {
+                n < name.len()
+                    && token.matches_char(name[n], case_insensitive)
+                    && self.matches_from(t + 1, n + 1)
+            }
+        };
+        self.memo[key] = Some(matched);
+        matched
+    }
+}
+
+/// A depth first walk of the filesystem, pruned to the directories a [`GlobPattern`] can match.
+struct GlobWalk {
+    pattern: GlobPattern,
+    root: AbsolutePathBuf,
+    /// Paths that matched the first `n` segments of an alternative, as `(path, alt, n)`.
+    pending: Vec<(AbsolutePathBuf, usize, usize)>,
+    seen: HashSet<AbsolutePathBuf>,
+}
+
+struct Entry {
+    name: String,
+    path: AbsolutePathBuf,
+    /// Whether this is a directory, following symlinks.
+    is_dir: bool,
+    is_symlink: bool,
+}
+
+impl GlobWalk {
+    fn new(pattern: GlobPattern, root: &AbsolutePath) -> Self {
+        let pending = (0..pattern.alternatives.len())
+            .rev()
+            .map(|alt| (root.into(), alt, 0))
+            .collect();
+        Self {
+            pattern,
+            root: root.into(),
+            pending,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// The entries in `dir` in sorted order, skipping any that are not valid utf-8.
+    fn entries(dir: &AbsolutePath) -> Vec<Entry> {
+        let read_dir = match std::fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return vec![],
+        };
+        let mut entries: Vec<_> = read_dir
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name().into_string().ok()?;
+                let path = AbsolutePathBuf::new_unchecked(entry.path());
+                let is_symlink = entry.file_type().ok()?.is_symlink();
+                Some(Entry {
+                    name,
+                    is_dir: path.is_dir(),
+                    path,
+                    is_symlink,
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+    }
+}
+
+impl Iterator for GlobWalk {
+    type Item = AbsolutePathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, alt, idx)) = self.pending.pop() {
+            let segments = &self.pattern.alternatives[alt];
+            let is_last = idx + 1 == segments.len();
+            match segments.get(idx) {
+                None => {
+                    if path != self.root && self.seen.insert(path.clone()) {
+                        return Some(path);
+                    }
+                }
+                Some(Segment::AnyDirs) => {
+                    for entry in Self::entries(&path).into_iter().rev() {
+                        if !self.pattern.match_hidden && entry.name.starts_with('.') {
+                            continue;
+                        }
+                        if entry.is_dir && !entry.is_symlink {
+                            self.pending.push((entry.path, alt, idx));
+                        } else if is_last {
+                            self.pending.push((entry.path, alt, idx + 1));
+                        }
+                    }
+                    // Matching zero directories is tried first, so shallower matches come first.
+                    self.pending.push((path, alt, idx + 1));
+                }
+                Some(Segment::Literal(name)) => {
+                    let child = AbsolutePathBuf::new_unchecked(path.as_path().join(name));
+                    if child.is_dir() || (is_last && child.symlink_metadata().is_ok()) {
+                        self.pending.push((child, alt, idx + 1));
+                    }
+                }
+                Some(segment) => {
+                    for entry in Self::entries(&path).into_iter().rev() {
+                        if (entry.is_dir || is_last)
+                            && segment.matches(
+                                &entry.name,
+                                self.pattern.case_insensitive,
+                                self.pattern.match_hidden,
+                            )
+                        {
+                            self.pending.push((entry.path, alt, idx + 1));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::glob;
+    use crate::glob::match_tokens;
+    use crate::glob::parse_tokens;
+    use crate::AbsolutePathBuf;
+    use crate::GlobPattern;
+    use crate::PathFilter;
+    use crate::RelativePath;
+
+    #[test]
+    fn matches_components() -> anyhow::Result<()> {
+        let matches_with = |pattern: &str, name: &str, case_insensitive| -> anyhow::Result<bool> {
+            let name: Vec<char> = name.chars().collect();
+            Ok(match_tokens(
+                &parse_tokens(pattern).map_err(anyhow::Error::msg)?,
+                &name,
+                case_insensitive,
+            ))
+        };
+        let matches = |pattern: &str, name: &str| matches_with(pattern, name, false);
+        assert!(matches("foo", "foo")?);
+        assert!(!matches("foo", "food")?);
+        assert!(matches("*.rs", "lib.rs")?);
+        assert!(matches("*.rs", ".rs")?);
+        assert!(!matches("*.rs", "lib.rs.bak")?);
+        assert!(matches("a*b*c", "aXbYbZc")?);
+        assert!(!matches("a*b*c", "aXbYbZ")?);
+        assert!(matches("f?o", "fxo")?);
+        assert!(!matches("f?o", "fo")?);
+        assert!(matches("[abc]x", "bx")?);
+        assert!(matches("[a-c0-9]", "7")?);
+        assert!(!matches("[!a-c]", "b")?);
+        assert!(matches("[^a-c]", "d")?);
+        assert!(matches("[]]", "]")?);
+        assert!(matches("[a-]", "-")?);
+        assert!(matches("\\*", "*")?);
+        assert!(!matches("\\*", "a")?);
+
+        assert!(matches("*.{rs,toml}", "Cargo.toml")?);
+        assert!(!matches("*.{rs,toml}", "Cargo.lock")?);
+        assert!(matches("{a,b{c,d}}x", "bdx")?);
+        assert!(matches("{,pre}fix", "fix")?);
+        assert!(matches("!(*.rs)", "Cargo.toml")?);
+        assert!(!matches("!(*.rs)", "lib.rs")?);
+        assert!(matches("!(foo|bar).txt", "baz.txt")?);
+        assert!(!matches("!(foo|bar).txt", "bar.txt")?);
+        assert!(matches("lib!(_test).rs", "lib.rs")?);
+        assert!(!matches("lib!(_test).rs", "lib_test.rs")?);
+
+        assert!(!matches("readme.[m]d", "README.MD")?);
+        assert!(matches_with("readme.[m]d", "README.MD", true)?);
+        assert!(matches_with("*.{RS,toml}", "lib.rs", true)?);
+
+        let pathological = "*a".repeat(20) + "*b";
+        assert!(!matches(&pathological, &"a".repeat(100))?);
+        assert!(matches(&pathological, &("a".repeat(100) + "b"))?);
+        assert!(!matches(&"{*a,*b}".repeat(10), &("a".repeat(60) + "c"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn filters_paths() -> anyhow::Result<()> {
+        let filter = PathFilter::new()
+            .include("src/**/*.rs")?
+            .include("(?i)readme.md")?
+            .exclude("**/!(lib|main).rs")?;
+        let matches = |path: &str| filter.matches(RelativePath::new_unchecked(path));
+
+        assert!(matches("src/lib.rs"));
+        assert!(matches("./src/nested/main.rs"));
+        assert!(matches("README.md"));
+        assert!(!matches("src/nested/mod.rs"));
+        assert!(!matches("src/.hidden/lib.rs"));
+        assert!(!matches("tests/lib.rs"));
+        assert!(!matches("../src/lib.rs"));
+
+        let everything = PathFilter::new().exclude("{target,.git}/**")?;
+        assert!(everything.matches(RelativePath::new_unchecked("src/lib.rs")));
+        assert!(!everything.matches(RelativePath::new_unchecked("target/debug/foo")));
+        assert!(!everything.matches(RelativePath::new_unchecked(".git")));
+        Ok(())
+    }
+
+    #[test]
+    fn matches_typed_paths() -> anyhow::Result<()> {
+        let root = AbsolutePathBuf::current_dir();
+        let pattern = GlobPattern::new("src/*.rs")?;
+        assert!(pattern.matches_relative(RelativePath::try_new("src/lib.rs")?));
+        assert!(!pattern.matches_relative(RelativePath::try_new("crate/src/lib.rs")?));
+        assert!(pattern.matches(&root.join("src/lib.rs")?));
+        assert!(pattern.matches(&root.join("crate/src/lib.rs")?));
+        assert!(!pattern.matches(&root.join("src/lib.rs/foo")?));
+        assert!(!pattern.matches(&root.join("src/nested/lib.rs")?));
+
+        let pattern = GlobPattern::new("**/target")?;
+        assert!(pattern.matches(&root.join("target")?));
+        assert!(!pattern.matches(&root.join("target/debug")?));
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(GlobPattern::new("/abs/*.rs").is_err());
+        assert!(GlobPattern::new("../*.rs").is_err());
+        assert!(GlobPattern::new("").is_err());
+        assert!(GlobPattern::new("[abc").is_err());
+        assert!(GlobPattern::new("{a,b").is_err());
+        assert!(GlobPattern::new("foo\\").is_err());
+        assert!(GlobPattern::new("!(foo").is_err());
+        assert!(GlobPattern::new("(?i)/abs").is_err());
+        assert!(GlobPattern::new("{a,b}/**/[!x]*.rs").is_ok());
+    }
+
+    #[test]
+    fn globs_filesystem() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        for file in [
+            "README.md",
+            "src/lib.rs",
+            "src/main.rs",
+            "src/nested/mod.rs",
+            "src/nested/data.json",
+            "src/.hidden/secret.rs",
+            "tests/it.rs",
+            "benches/bench.rs",
+        ] {
+            let path = root.join(file)?;
+            path.ensure_parent_exists()?;
+            std::fs::write(path, "")?;
+        }
+
+        let found = |pattern: &str| -> anyhow::Result<Vec<String>> {
+            glob(&root, pattern)?
+                .map(|p| Ok(p.relative_to(&root)?.to_lossy_string()))
+                .collect()
+        };
+
+        assert_eq!(vec!["src/lib.rs", "src/main.rs"], found("src/*.rs")?);
+        assert_eq!(
+            vec!["src/lib.rs", "src/main.rs", "src/nested/mod.rs"],
+            found("src/**/*.rs")?
+        );
+        assert_eq!(
+            vec![
+                "src/lib.rs",
+                "src/main.rs",
+                "src/nested/mod.rs",
+                "tests/it.rs"
+            ],
+            found("{src,tests}/**/*.rs")?
+        );
+        assert_eq!(vec!["src/.hidden/secret.rs"], found("src/.*/*.rs")?);
+        assert_eq!(vec!["src/main.rs"], found("src/[m-z]*.rs")?);
+        assert_eq!(vec!["README.md"], found("README.md")?);
+        assert_eq!(
+            vec!["src/nested", "src/nested/data.json", "src/nested/mod.rs"],
+            found("src/nested/**")?
+        );
+        assert_eq!(vec!["src/lib.rs"], found("src/{lib,lib}.rs")?);
+        assert_eq!(vec!["src/lib.rs"], found("src/!(main|mod).rs")?);
+        assert_eq!(vec!["README.md"], found("(?i)readme.MD")?);
+        assert_eq!(vec!["src/main.rs"], found("(?i)SRC/Main.rs")?);
+        assert_eq!(Vec::<String>::new(), found("missing/**/*.rs")?);
+        assert_eq!(Vec::<String>::new(), found("src/*.txt")?);
+        Ok(())
+    }
+}
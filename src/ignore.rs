@@ -0,0 +1,186 @@
+use crate::AbsolutePath;
+use crate::GlobPattern;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// Rules read from `.gitignore` format files, deciding which paths should be skipped.
+///
+/// Each file's patterns are relative to the directory the file is in. As in git, later rules
+/// take precedence over earlier ones, `!` re-includes paths, a trailing `/` only matches
+/// directories, and patterns without a `/` (other than a trailing one) match at any depth.
+/// Wildcards use the syntax of [`crate::glob`], but also match names starting with `.`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct IgnoreRule {
+    base: RelativePathBuf,
+    pattern: GlobPattern,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    /// Create an empty set of rules, which ignores nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the rules from the contents of a `.gitignore` format file in `dir`.
+    ///
+    /// Lines with invalid patterns are skipped, as git does.
+    pub fn add_rules(&mut self, dir: &RelativePath, contents: &str) {
+        for line in contents.lines() {
+            if let Some(rule) = IgnoreRule::parse(dir, line) {
+                self.rules.push(rule);
+            }
+        }
+    }
+
+    /// Add the rules from `file`, whose patterns are relative to `dir`. Missing files are
+    /// treated as empty.
+    pub fn add_file(&mut self, dir: &RelativePath, file: &AbsolutePath) -> std::io::Result<()> {
+        match std::fs::read_to_string(file) {
+            Ok(contents) => {
+                self.add_rules(dir, &contents);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether `path`, or any of the directories that contain it, is ignored.
+    pub fn is_ignored(&self, path: &RelativePath, is_dir: bool) -> bool {
+        path.ancestors()
+            .skip(1)
+            .filter(|a| !a.as_os_str().is_empty())
+            .any(|a| self.is_ignored_entry(a, true))
+            || self.is_ignored_entry(path, is_dir)
+    }
+
+    /// Whether the rules ignore `path` itself, without checking the directories that contain it.
+    pub(crate) fn is_ignored_entry(&self, path: &RelativePath, is_dir: bool) -> bool {
+        for rule in self.rules.iter().rev() {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            match path.strip_prefix(&rule.base) {
+                Ok(rest)
+                    if !rest.as_os_str().is_empty()
+                        && rule
+                            .pattern
+                            .matches_relative(RelativePath::new_unchecked(rest)) =>
+                {
+                    return !rule.negated;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+impl IgnoreRule {
+    fn parse(dir: &RelativePath, line: &str) -> Option<Self> {
+        // Trailing spaces are ignored unless they are escaped.
+        let mut line = line.trim_end_matches('\r');
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // Patterns with a `/` are relative to `dir`, others can match at any depth.
+        let pattern = match line.strip_prefix('/') {
+            Some(rest) => escape_glob(rest),
+            None if line.contains('/') => escape_glob(line),
+            None => format!("**/{}", escape_glob(line)),
+        };
+        let pattern = GlobPattern::new(&pattern).ok()?.matching_hidden();
+        Some(Self {
+            base: dir.into(),
+            pattern,
+            negated,
+            dir_only,
+        })
+    }
+}
+
+/// Escape the glob syntax that `.gitignore` files treat literally.
+fn escape_glob(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                escaped.push(c);
+                escaped.extend(chars.next());
+            }
+            '{' | '}' | '!' | '(' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::IgnoreRules;
+    use crate::RelativePath;
+
+    #[test]
+    fn ignores_paths() {
+        let mut rules = IgnoreRules::new();
+        rules.add_rules(
+            RelativePath::new_unchecked(""),
+            "# build output\n\
+             target/\n\
+             *.log\n\
+             !keep.log\n\
+             /root-only.txt\n\
+             docs/*.html\n\
+             \\#literal\n\
+             {braces}\n\
+             trailing.txt   \n\
+             [invalid\n",
+        );
+        rules.add_rules(RelativePath::new_unchecked("sub"), "*.tmp\n!target/\n");
+        let ignored =
+            |path: &str, is_dir| rules.is_ignored(RelativePath::new_unchecked(path), is_dir);
+
+        assert!(ignored("target", true));
+        assert!(ignored("nested/target", true));
+        assert!(!ignored("target", false));
+        assert!(ignored("target/debug/foo", false));
+        assert!(ignored("app.log", false));
+        assert!(ignored("nested/.hidden.log", false));
+        assert!(!ignored("keep.log", false));
+        assert!(!ignored("nested/keep.log", false));
+        assert!(ignored("root-only.txt", false));
+        assert!(!ignored("nested/root-only.txt", false));
+        assert!(ignored("docs/index.html", false));
+        assert!(!ignored("nested/docs/index.html", false));
+        assert!(ignored("#literal", false));
+        assert!(ignored("{braces}", false));
+        assert!(ignored("trailing.txt", false));
+        assert!(!ignored("src/lib.rs", false));
+
+        assert!(ignored("sub/foo.tmp", false));
+        assert!(!ignored("foo.tmp", false));
+        assert!(!ignored("sub/target", true));
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::Write;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::CombinedPathBuf;
+
+/// The first line of files written by [`PathInterner::write_to`].
+const HEADER: &[u8] = b"paths-interner v1\n";
+
+/// A small, copyable handle to a path in a [`PathInterner`].
+///
+/// Ids are assigned in order starting from 0, and are kept when the interner is saved and
+/// loaded again, so they can be stored elsewhere across runs.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct PathId(u32);
+
+impl PathId {
+    /// Recreate an id from [`PathId::as_u32`].
+    pub fn from_u32(id: u32) -> Self {
+        Self(id)
+    }
+
+    /// The numeric value of this id.
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// Deduplicates paths, giving each distinct path a [`PathId`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PathInterner {
+    paths: Vec<AbsolutePathBuf>,
+    ids: HashMap<AbsolutePathBuf, PathId>,
+}
+
+impl PathInterner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the id for `path`, adding it if it has not been seen before.
+    pub fn intern(&mut self, path: &AbsolutePath) -> PathId {
+        if let Some(id) = self.ids.get(path) {
+            return *id;
+        }
+        let id = PathId(
+            u32::try_from(self.paths.len()).expect("fewer than u32::MAX paths to be interned"),
+        );
+        self.paths.push(path.into());
+        self.ids.insert(path.into(), id);
+        id
+    }
+
+    /// Get the id for `path`, if it has been interned.
+    pub fn get(&self, path: &AbsolutePath) -> Option<PathId> {
+        self.ids.get(path).copied()
+    }
+
+    /// Get the path for `id`, if it came from this interner.
+    pub fn resolve(&self, id: PathId) -> Option<&AbsolutePath> {
+        self.paths.get(id.0 as usize).map(|p| p.as_absolute_path())
+    }
+
+    /// The number of distinct paths.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Whether no paths have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// An estimate of the number of bytes allocated on the heap for the table.
+    ///
+    /// This ignores the hash map's control bytes and any allocator overhead.
+    pub fn heap_size(&self) -> usize {
+        // Each path is stored twice, once in `paths` and once as a key of `ids`.
+        2 * self.paths.iter().map(|p| p.heap_size()).sum::<usize>()
+            + self.paths.capacity() * std::mem::size_of::<AbsolutePathBuf>()
+            + self.ids.capacity() * std::mem::size_of::<(AbsolutePathBuf, PathId)>()
+    }
+
+    /// Write the table to `w`, so that it can be loaded with [`PathInterner::read_from`].
+    ///
+    /// The format is a header line followed by each path in id order, NUL delimited.
+    pub fn write_to<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        w.write_all(HEADER)?;
+        crate::write_nul_delimited(&self.paths, w)
+    }
+
+    /// Read a table written by [`PathInterner::write_to`].
+    pub fn read_from<R: BufRead>(mut reader: R) -> std::io::Result<Self> {
+        let mut header = vec![0; HEADER.len()];
+        reader.read_exact(&mut header)?;
+        if header != HEADER {
+            return Err(invalid_data("not a path interner table".to_owned()));
+        }
+        let mut interner = Self::new();
+        for path in crate::read_nul_delimited(reader) {
+            let path = match path.map_err(|e| invalid_data(e.to_string()))? {
+                CombinedPathBuf::Absolute(path) => path,
+                CombinedPathBuf::Relative(path) => {
+                    return Err(invalid_data(format!(
+                        "`{}` is not absolute",
+                        path.as_path().display()
+                    )));
+                }
+            };
+            let expected = interner.len();
+            if interner.intern(&path).0 as usize != expected {
+                return Err(invalid_data(format!(
+                    "`{}` appears more than once",
+                    path.as_path().display()
+                )));
+            }
+        }
+        Ok(interner)
+    }
+
+    /// Atomically save the table to `file`.
+    pub fn save(&self, file: &AbsolutePath) -> std::io::Result<()> {
+        let mut buf = vec![];
+        self.write_to(&mut buf)?;
+        crate::fs::write_atomic(file, buf)
+    }
+
+    /// Load a table saved with [`PathInterner::save`].
+    pub fn load(file: &AbsolutePath) -> std::io::Result<Self> {
+        Self::read_from(std::io::BufReader::new(std::fs::File::open(file)?))
+    }
+}
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::PathId;
+    use crate::PathInterner;
+
+    #[test]
+    fn interns_paths() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let mut interner = PathInterner::new();
+        let foo = interner.intern(&cwd.join("foo")?);
+        let bar = interner.intern(&cwd.join("bar\nbaz")?);
+
+        assert_eq!(foo, interner.intern(&cwd.join("foo")?));
+        assert_ne!(foo, bar);
+        assert_eq!(Some(bar), interner.get(&cwd.join("bar\nbaz")?));
+        assert_eq!(None, interner.get(&cwd.join("missing")?));
+        assert_eq!(
+            Some(cwd.join("foo")?.as_absolute_path()),
+            interner.resolve(foo)
+        );
+        assert_eq!(None, interner.resolve(PathId::from_u32(2)));
+        assert_eq!(foo, PathId::from_u32(foo.as_u32()));
+        assert_eq!(2, interner.len());
+        assert!(interner.heap_size() >= 2 * cwd.join("foo")?.as_os_str().len());
+        Ok(())
+    }
+
+    #[test]
+    fn saves_and_loads() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let file = root.join("paths.table")?;
+
+        let mut interner = PathInterner::new();
+        let ids: Vec<_> = ["c", "a", "b/d"]
+            .iter()
+            .map(|p| Ok(interner.intern(&root.join(p)?)))
+            .collect::<anyhow::Result<_>>()?;
+        interner.save(&file)?;
+
+        let loaded = PathInterner::load(&file)?;
+        assert_eq!(interner, loaded);
+        for id in ids {
+            assert_eq!(interner.resolve(id), loaded.resolve(id));
+        }
+
+        assert!(PathInterner::read_from(&b"something else\n"[..]).is_err());
+        assert!(PathInterner::read_from(&b"paths-interner v1\nrelative\0"[..]).is_err());
+        Ok(())
+    }
+}
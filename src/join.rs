@@ -0,0 +1,219 @@
+use std::convert::Infallible;
+
+use ref_cast::RefCast;
+
+use crate::AbsoluteJoinError;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::CombinedJoinError;
+use crate::CombinedPath;
+use crate::CombinedPathBuf;
+use crate::JoinedAbsolute;
+use crate::NormalizationFailed;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// Joining a path onto another, for code that is generic over the path types.
+///
+/// Each implementation's `Error` is the narrowest one that the join can fail with, e.g.
+/// joining a [`RelativePath`] onto a [`RelativePath`] cannot fail, so the error is
+/// [`Infallible`], and joining one onto an [`AbsolutePath`] can only fail to normalize. The
+/// owned types join like their borrowed counterparts.
+///
+/// Because the path types have inherent `join()` methods that accept any `AsRef<Path>`, this
+/// only changes which method is called in generic code:
+///
+/// ```
+/// use paths::AbsolutePathBuf;
+/// use paths::Join;
+/// use paths::RelativePath;
+///
+/// fn config_file<'a, P>(dir: &P, name: &'a RelativePath) -> Result<AbsolutePathBuf, P::Error>
+/// where
+///     P: Join<&'a RelativePath, Output = AbsolutePathBuf> + ?Sized,
+/// {
+///     Join::join(dir, name)
+/// }
+///
+/// let dir = AbsolutePathBuf::current_dir();
+/// let name = RelativePath::try_new("config.toml").unwrap();
+/// assert!(config_file(&dir, name).unwrap().ends_with(name));
+/// assert!(config_file(dir.as_absolute_path(), name).unwrap().ends_with(name));
+/// ```
+pub trait Join<Rhs> {
+    /// The joined path.
+    type Output;
+    /// Why the join failed.
+    type Error;
+
+    /// Join `rhs` onto this path, normalizing the result.
+    fn join(&self, rhs: Rhs) -> Result<Self::Output, Self::Error>;
+}
+
+impl<'a> Join<&'a RelativePath> for AbsolutePath {
+    type Output = AbsolutePathBuf;
+    type Error = NormalizationFailed;
+
+    fn join(&self, rhs: &'a RelativePath) -> Result<Self::Output, Self::Error> {
+        self.join_relative(rhs)
+    }
+}
+
+impl<'a> Join<&'a CombinedPath> for AbsolutePath {
+    type Output = AbsolutePathBuf;
+    type Error = AbsoluteJoinError;
+
+    fn join(&self, rhs: &'a CombinedPath) -> Result<Self::Output, Self::Error> {
+        AbsolutePath::join(self, rhs.as_path())
+    }
+}
+
+impl<'a> Join<&'a RelativePath> for RelativePath {
+    type Output = RelativePathBuf;
+    type Error = Infallible;
+
+    fn join(&self, rhs: &'a RelativePath) -> Result<Self::Output, Self::Error> {
+        Ok(RelativePath::join(self, rhs.as_path()).expect("both paths are relative"))
+    }
+}
+
+impl<'a> Join<&'a CombinedPath> for RelativePath {
+    type Output = RelativePathBuf;
+    type Error = JoinedAbsolute;
+
+    fn join(&self, rhs: &'a CombinedPath) -> Result<Self::Output, Self::Error> {
+        RelativePath::join(self, rhs.as_path())
+    }
+}
+
+impl<'a> Join<&'a RelativePath> for CombinedPath {
+    type Output = CombinedPathBuf;
+    type Error = NormalizationFailed;
+
+    fn join(&self, rhs: &'a RelativePath) -> Result<Self::Output, Self::Error> {
+        if self.is_absolute() {
+            Ok(AbsolutePath::new_unchecked(self.as_path())
+                .join_relative(rhs)?
+                .into())
+        } else {
+            Ok(RelativePath::new_unchecked(self.as_path())
+                .join(rhs.as_path())
+                .expect("both paths are relative")
+                .into())
+        }
+    }
+}
+
+impl<'a> Join<&'a CombinedPath> for CombinedPath {
+    type Output = CombinedPathBuf;
+    type Error = CombinedJoinError;
+
+    fn join(&self, rhs: &'a CombinedPath) -> Result<Self::Output, Self::Error> {
+        CombinedPath::join(self, rhs.as_path())
+    }
+}
+
+impl<Rhs> Join<Rhs> for AbsolutePathBuf
+where
+    AbsolutePath: Join<Rhs>,
+{
+    type Output = <AbsolutePath as Join<Rhs>>::Output;
+    type Error = <AbsolutePath as Join<Rhs>>::Error;
+
+    fn join(&self, rhs: Rhs) -> Result<Self::Output, Self::Error> {
+        Join::join(self.as_absolute_path(), rhs)
+    }
+}
+
+impl<Rhs> Join<Rhs> for RelativePathBuf
+where
+    RelativePath: Join<Rhs>,
+{
+    type Output = <RelativePath as Join<Rhs>>::Output;
+    type Error = <RelativePath as Join<Rhs>>::Error;
+
+    fn join(&self, rhs: Rhs) -> Result<Self::Output, Self::Error> {
+        Join::join(self.as_relative_path(), rhs)
+    }
+}
+
+impl<Rhs> Join<Rhs> for CombinedPathBuf
+where
+    CombinedPath: Join<Rhs>,
+{
+    type Output = <CombinedPath as Join<Rhs>>::Output;
+    type Error = <CombinedPath as Join<Rhs>>::Error;
+
+    fn join(&self, rhs: Rhs) -> Result<Self::Output, Self::Error> {
+        Join::join(CombinedPath::ref_cast(self.as_path()), rhs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPath;
+    use crate::CombinedPathBuf;
+    use crate::Join;
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+
+    fn join_all<'a, P, Q>(base: &P, paths: &[&'a Q]) -> Result<Vec<P::Output>, P::Error>
+    where
+        P: Join<&'a Q> + ?Sized,
+        Q: ?Sized,
+    {
+        paths.iter().map(|path| Join::join(base, *path)).collect()
+    }
+
+    #[test]
+    fn joins_generically() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let relative = [RelativePath::try_new("foo")?, RelativePath::try_new("bar")?];
+        let combined = [
+            CombinedPath::try_new("foo")?,
+            CombinedPath::try_new("/bar")?,
+        ];
+
+        assert_eq!(
+            vec![cwd.join("foo")?, cwd.join("bar")?],
+            join_all(&cwd, &relative)?
+        );
+        assert_eq!(
+            vec![cwd.join("foo")?, cwd.join("bar")?],
+            join_all(cwd.as_absolute_path(), &relative)?
+        );
+        assert_eq!(
+            "joined_absolute",
+            join_all(&cwd, &combined).unwrap_err().code()
+        );
+        let above_root = vec![".."; cwd.components().count()].join("/");
+        assert!(join_all(&cwd, &[RelativePath::try_new(&above_root)?]).is_err());
+
+        let base = RelativePathBuf::try_new("base")?;
+        let joined = join_all(&base, &relative).unwrap_or_else(|e| match e {});
+        assert_eq!(
+            vec![
+                RelativePathBuf::try_new("base/foo")?,
+                RelativePathBuf::try_new("base/bar")?
+            ],
+            joined
+        );
+        assert!(join_all(&base, &combined).is_err());
+
+        let base = CombinedPathBuf::try_new("..")?;
+        assert_eq!(
+            vec![
+                CombinedPathBuf::try_new("../foo")?,
+                CombinedPathBuf::try_new("../bar")?
+            ],
+            join_all(&base, &relative)?
+        );
+        let base = CombinedPathBuf::from(cwd.clone());
+        assert_eq!(
+            vec![CombinedPathBuf::from(cwd.join("foo")?)],
+            join_all(&base, &combined[..1])?
+        );
+        Ok(())
+    }
+}
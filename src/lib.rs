@@ -1,21 +1,226 @@
 #![deny(clippy::all)]
 
 mod absolute;
+mod alias_map;
+mod arena;
+#[cfg(all(target_os = "macos", feature = "bookmarks"))]
+mod bookmark;
+mod cache_dir;
+mod cache_key;
+mod canonical;
+mod coalesce;
 mod combined;
+mod completion;
+mod config_chain;
+mod config_locator;
+mod dedup;
+#[cfg(feature = "miette")]
+mod diagnostics;
+#[cfg(all(unix, feature = "openat"))]
+mod dir_handle;
+mod embedded_tree;
+mod env_value;
 mod errors;
+#[cfg(feature = "jsonl")]
+mod event_log;
+mod existing;
+mod file_system;
+pub mod fs;
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+mod fuzzy;
+#[cfg(feature = "git")]
+mod git;
+mod glob;
+mod ignore;
+mod interner;
+mod join;
+mod list_page;
+mod lock;
+mod log_file;
+mod mru_list;
+mod path_kind;
+mod path_list;
+mod path_map;
+mod path_or_stdio;
+mod path_set;
+mod rebaser;
+mod redacted;
 mod relative;
+mod render_tree;
 mod resolved_absolute;
+mod rewrite;
+mod root_set;
+mod router;
+#[cfg(all(target_os = "linux", feature = "landlock"))]
+mod sandbox;
+mod scaffold;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
+#[cfg(feature = "serde")]
+mod serde_path;
+mod single_instance;
+mod temp_file;
+mod tenant;
+mod transaction;
+mod tree_builder;
+mod unique_name;
+mod utf8;
+mod validate;
+mod volumes;
+mod walk;
+#[cfg(windows)]
+mod windows;
 
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::path::Component;
 use std::path::Path;
 
+pub use absolute::AbsoluteComponent;
 pub use absolute::AbsolutePath;
 pub use absolute::AbsolutePathBuf;
+#[cfg(feature = "rkyv")]
+pub use absolute::ArchivedAbsolutePathBuf;
+pub use absolute::BackupStyle;
+pub use absolute::CowAbsolutePath;
+#[cfg(feature = "acl")]
+pub use absolute::EffectiveAccess;
+pub use alias_map::AliasMap;
+pub use arena::PathArena;
+#[cfg(all(target_os = "macos", feature = "bookmarks"))]
+pub use bookmark::Bookmark;
+pub use cache_dir::CacheDir;
+pub use cache_key::CacheKey;
+pub use cache_key::CacheKeyOptions;
+pub use canonical::CanonicalPathBuf;
+pub use coalesce::EventCoalescer;
+pub use coalesce::PathEvent;
+pub use coalesce::PathEventKind;
+#[cfg(feature = "rkyv")]
+pub use combined::ArchivedCombinedPathBuf;
 pub use combined::CombinedPath;
 pub use combined::CombinedPathBuf;
+pub use completion::complete_path;
+pub use config_chain::DirectoryConfigChain;
+pub use config_locator::ConfigLocator;
+pub use config_locator::ConfigSource;
+pub use dedup::dedup_normalized;
+pub use dedup::remove_covered_by_ancestors;
+#[cfg(all(unix, feature = "openat"))]
+pub use dir_handle::DirHandle;
+pub use embedded_tree::EmbeddedTree;
 pub use errors::*;
+#[cfg(feature = "jsonl")]
+pub use event_log::write_path_events;
+#[cfg(feature = "jsonl")]
+pub use event_log::PathEventReader;
+pub use existing::ExistingAbsolutePathBuf;
+pub use file_system::FileSystem;
+pub use file_system::RestrictedFs;
+pub use file_system::StdFs;
+pub use fuzzy::fuzzy_match;
+#[cfg(feature = "git")]
+pub use git::GitContext;
+pub use glob::glob;
+pub use glob::GlobPattern;
+pub use glob::PathFilter;
+pub use ignore::IgnoreRules;
+pub use interner::PathId;
+pub use interner::PathInterner;
+pub use join::Join;
+pub use list_page::list_page;
+pub use list_page::ListEntry;
+pub use list_page::ListPage;
+pub use list_page::ListSort;
+pub use lock::PathLock;
+pub use log_file::LogFile;
+pub use mru_list::MruList;
+pub use path_kind::DirectoryPathBuf;
+pub use path_kind::FilePathBuf;
+pub use path_list::read_nul_delimited;
+pub use path_list::write_nul_delimited;
+pub use path_list::PathListReader;
+pub use path_map::PathMap;
+pub use path_or_stdio::PathOrStdio;
+pub use path_set::PathSet;
+pub use rebaser::Rebaser;
+pub use redacted::Redacted;
+#[cfg(feature = "rkyv")]
+pub use relative::ArchivedRelativePathBuf;
+#[cfg(feature = "regex")]
+pub use relative::RegexAnchor;
 pub use relative::RelativePath;
 pub use relative::RelativePathBuf;
+pub use render_tree::render_tree;
+pub use render_tree::render_tree_with_max_depth;
 pub use resolved_absolute::ResolvedAbsolutePathBuf;
+pub use rewrite::RewriteRules;
+pub use root_set::RootSet;
+pub use router::PathRouter;
+#[cfg(all(target_os = "linux", feature = "landlock"))]
+pub use sandbox::Sandbox;
+pub use scaffold::Scaffold;
+pub use scaffold::ScaffoldEntry;
+pub use single_instance::SingleInstance;
+pub use temp_file::AnonymousTempFile;
+pub use tenant::TenantRoots;
+pub use tenant::TenantScopedPath;
+pub use transaction::FsTransaction;
+pub use tree_builder::TreeBuilder;
+pub use unique_name::UniqueName;
+pub use utf8::Utf8AbsolutePath;
+pub use utf8::Utf8AbsolutePathBuf;
+pub use utf8::Utf8RelativePath;
+pub use utf8::Utf8RelativePathBuf;
+pub use validate::validate_all;
+pub use volumes::list_volumes;
+pub use walk::walk_project;
+pub use walk::WalkEntry;
+
+/// Generate a file name starting with `prefix` that is unlikely to collide with existing files.
+///
+/// This is not cryptographically random, so callers must still create files exclusively.
+pub(crate) fn unique_file_name(prefix: &str) -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasher;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let hash = RandomState::new().hash_one((
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        std::time::SystemTime::now(),
+    ));
+    format!("{}{:016x}", prefix, hash)
+}
+
+/// Check that `name` is a single file name that is valid on every platform, so e.g. does not
+/// contain `:`, which windows does not allow.
+fn check_portable_file_name(name: &str) -> Result<(), InvalidFileName> {
+    if name
+        .chars()
+        .any(|c| c.is_control() || r#"<>:"/\|?*"#.contains(c))
+    {
+        return Err(InvalidFileName(name.to_owned()));
+    }
+    check_file_name(OsStr::new(name))
+}
+
+/// Find the closest directory to `path` (including `path` itself) that contains `.git`.
+///
+/// `.git` is a file rather than a directory in worktrees and submodules.
+pub(crate) fn find_git_root(path: &AbsolutePath) -> Option<&AbsolutePath> {
+    let mut dir = Some(path);
+    while let Some(current) = dir {
+        if current.join(".git").is_ok_and(|git| git.exists()) {
+            return Some(current);
+        }
+        dir = current.parent();
+    }
+    None
+}
 
 /// If the path has a parent, create that parent directory and all of its parent dirs
 /// using [`std::fs::create_dir_all()`]
@@ -27,6 +232,64 @@ fn create_parent_dir<P: AsRef<Path>>(p: P) -> std::io::Result<()> {
     }
 }
 
+/// Check that `name` is a single file name, without any separators, `.` or `..`.
+fn check_file_name(name: &OsStr) -> Result<(), InvalidFileName> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(n)), None) if n == name => Ok(()),
+        _ => Err(InvalidFileName(name.to_string_lossy().to_string())),
+    }
+}
+
+/// The file name of `path` with its extension replaced, like `Path::with_extension()`.
+fn file_name_with_extension(path: &Path, extension: &OsStr) -> Result<OsString, InvalidFileName> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| InvalidFileName(path.display().to_string()))?;
+    // `Path::with_extension()` panics on separators in the extension.
+    if extension
+        .to_string_lossy()
+        .chars()
+        .any(std::path::is_separator)
+    {
+        return Err(InvalidFileName(
+            Path::new(name).display().to_string() + "." + &extension.to_string_lossy(),
+        ));
+    }
+    let name = Path::new(name).with_extension(extension).into_os_string();
+    check_file_name(&name)?;
+    Ok(name)
+}
+
+/// A JSON schema for a string holding a path, e.g. for [`schemars::JsonSchema`] impls.
+#[cfg(feature = "schemars")]
+fn path_schema(format: &str, description: &str) -> schemars::schema::Schema {
+    schemars::schema::SchemaObject {
+        instance_type: Some(schemars::schema::InstanceType::String.into()),
+        format: Some(format.to_owned()),
+        metadata: Some(Box::new(schemars::schema::Metadata {
+            description: Some(description.to_owned()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// `path` as a string, for the database and archive impls, which can only store UTF-8.
+#[cfg(any(
+    feature = "diesel",
+    feature = "rusqlite",
+    feature = "postgres",
+    feature = "rkyv",
+    feature = "sea-orm",
+    feature = "sqlx"
+))]
+fn path_to_str(path: &Path) -> Result<&str, NotUtf8> {
+    path.to_str()
+        .ok_or_else(|| NotUtf8(path.display().to_string()))
+}
+
 #[cfg(all(test, feature = "diesel"))]
 #[macro_use]
 extern crate diesel;
@@ -77,3 +340,34 @@ pub(crate) mod diesel_helpers {
         Ok(())
     }
 }
+
+#[cfg(all(
+    test,
+    any(
+        all(feature = "sqlx-sqlite", feature = "sqlx-runtime-tokio"),
+        feature = "sea-orm"
+    )
+))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("the runtime should build")
+        .block_on(future)
+}
+
+#[cfg(all(test, feature = "sqlx-sqlite", feature = "sqlx-runtime-tokio"))]
+pub(crate) mod sqlx_helpers {
+    use sqlx::Connection;
+    use sqlx::SqliteConnection;
+
+    pub(crate) async fn create_table() -> anyhow::Result<SqliteConnection> {
+        let mut connection = SqliteConnection::connect("sqlite::memory:").await?;
+        sqlx::query(
+            "CREATE TABLE test_files (id PRIMARY KEY NOT NULL, x TEXT NOT NULL, y TEXT NULL)",
+        )
+        .execute(&mut connection)
+        .await?;
+        Ok(connection)
+    }
+}
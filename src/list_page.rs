@@ -0,0 +1,223 @@
+use std::ffi::OsString;
+use std::fs::FileType;
+use std::time::SystemTime;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// The order of the entries that [`list_page`] pages through. Ties are broken by name, so the
+/// order is stable between calls.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum ListSort {
+    /// By file name.
+    #[default]
+    Name,
+    /// Directories first, then by file name. Symlinks to directories are not directories.
+    DirectoriesFirst,
+    /// Oldest modification time first.
+    Modified,
+    /// Smallest first.
+    Size,
+}
+
+/// An entry in a [`ListPage`]. Symlinks are not followed.
+#[derive(Debug, Clone)]
+pub struct ListEntry {
+    path: AbsolutePathBuf,
+    file_type: FileType,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl ListEntry {
+    /// The absolute path to this entry.
+    pub fn path(&self) -> &AbsolutePath {
+        self.path.as_absolute_path()
+    }
+
+    /// The type of this entry.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// Whether this entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    /// The size of this entry in bytes, as in [`std::fs::Metadata::len`].
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether this entry has a size of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// When this entry was last modified, if the platform supports it.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Take ownership of the absolute path to this entry.
+    pub fn into_path(self) -> AbsolutePathBuf {
+        self.path
+    }
+}
+
+/// One page of a directory's entries, from [`list_page`].
+#[derive(Debug, Clone)]
+pub struct ListPage {
+    entries: Vec<ListEntry>,
+    total: usize,
+}
+
+impl ListPage {
+    /// The entries on this page, in order.
+    pub fn entries(&self) -> &[ListEntry] {
+        &self.entries
+    }
+
+    /// The number of entries in the whole directory, e.g. to size a scroll bar.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Take ownership of the entries on this page.
+    pub fn into_entries(self) -> Vec<ListEntry> {
+        self.entries
+    }
+}
+
+/// List up to `limit` entries of the directory `dir`, starting `offset` entries in to the order
+/// given by `sort`, e.g. for file browsers that should not load every entry of huge directories.
+///
+/// Every entry still has to be read to be sorted, but only the entries on the page are
+/// returned. Sorting by name only needs the names, while the other orders also need the type,
+/// size or modification time of every entry. Entries whose metadata cannot be read sort as if
+/// they were empty and from the unix epoch, though reading the page fails if one is on it.
+pub fn list_page(
+    dir: &AbsolutePath,
+    offset: usize,
+    limit: usize,
+    sort: ListSort,
+) -> std::io::Result<ListPage> {
+    let mut names: Vec<(SortKey, OsString)> = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let key = match sort {
+            ListSort::Name => SortKey::None,
+            ListSort::DirectoriesFirst => {
+                SortKey::NotDir(!entry.file_type().is_ok_and(|t| t.is_dir()))
+            }
+            ListSort::Modified => SortKey::Modified(
+                entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH),
+            ),
+            ListSort::Size => SortKey::Size(entry.metadata().map_or(0, |m| m.len())),
+        };
+        names.push((key, entry.file_name()));
+    }
+    names.sort_unstable();
+
+    let total = names.len();
+    let entries = names
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(_, name)| {
+            let path = dir
+                .join(&name)
+                .expect("a directory entry is a plain file name");
+            let metadata = std::fs::symlink_metadata(&path)?;
+            Ok(ListEntry {
+                path,
+                file_type: metadata.file_type(),
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+            })
+        })
+        .collect::<std::io::Result<_>>()?;
+    Ok(ListPage { entries, total })
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+enum SortKey {
+    None,
+    NotDir(bool),
+    Modified(SystemTime),
+    Size(u64),
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use crate::list_page;
+    use crate::AbsolutePathBuf;
+    use crate::ListPage;
+    use crate::ListSort;
+
+    fn names(page: &ListPage) -> Vec<String> {
+        page.entries()
+            .iter()
+            .map(|e| e.path().file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn lists_pages() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let base = SystemTime::now() - Duration::from_secs(3600);
+        for (i, (name, contents)) in [("c.txt", "c"), ("a.txt", "aaa"), ("d.txt", "dd")]
+            .into_iter()
+            .enumerate()
+        {
+            let path = root.join(name)?;
+            std::fs::write(&path, contents)?;
+            std::fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(base + Duration::from_secs(i as u64))?;
+        }
+        std::fs::create_dir(root.join("b")?)?;
+        std::fs::create_dir(root.join("e")?)?;
+
+        let page = list_page(&root, 0, 2, ListSort::Name)?;
+        assert_eq!(5, page.total());
+        assert_eq!(vec!["a.txt", "b"], names(&page));
+        assert_eq!(3, page.entries()[0].len());
+        assert!(page.entries()[1].is_dir());
+        assert_eq!(
+            vec!["c.txt", "d.txt"],
+            names(&list_page(&root, 2, 2, ListSort::Name)?)
+        );
+        assert_eq!(vec!["e"], names(&list_page(&root, 4, 2, ListSort::Name)?));
+        assert!(list_page(&root, 10, 2, ListSort::Name)?
+            .entries()
+            .is_empty());
+
+        assert_eq!(
+            vec!["b", "e", "a.txt", "c.txt", "d.txt"],
+            names(&list_page(&root, 0, 10, ListSort::DirectoriesFirst)?)
+        );
+        assert_eq!(
+            vec!["c.txt", "a.txt", "d.txt"],
+            names(&list_page(&root, 0, 3, ListSort::Modified)?)
+        );
+        // Directory sizes depend on the filesystem.
+        std::fs::remove_dir(root.join("b")?)?;
+        std::fs::remove_dir(root.join("e")?)?;
+        assert_eq!(
+            vec!["c.txt", "d.txt", "a.txt"],
+            names(&list_page(&root, 0, 3, ListSort::Size)?)
+        );
+        assert!(list_page(&root.join("missing")?, 0, 1, ListSort::Name).is_err());
+        Ok(())
+    }
+}
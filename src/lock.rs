@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::fs::TryLockError;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// An advisory OS lock on a file, released when this guard is dropped.
+///
+/// This uses `flock` on unix and `LockFileEx` on windows (via [`std::fs::File::lock`]), so it
+/// only coordinates between processes that also take these locks. The file is created if it
+/// does not exist, and is not removed when the lock is released.
+#[derive(Debug)]
+pub struct PathLock {
+    file: File,
+    path: AbsolutePathBuf,
+}
+
+impl PathLock {
+    /// Take an exclusive lock on `path`, blocking until it is available.
+    pub fn exclusive(path: &AbsolutePath) -> std::io::Result<Self> {
+        let file = Self::open(path)?;
+        file.lock()?;
+        Ok(Self {
+            file,
+            path: path.into(),
+        })
+    }
+
+    /// Take a shared lock on `path`, blocking until it is available.
+    pub fn shared(path: &AbsolutePath) -> std::io::Result<Self> {
+        let file = Self::open(path)?;
+        file.lock_shared()?;
+        Ok(Self {
+            file,
+            path: path.into(),
+        })
+    }
+
+    /// Attempt to take an exclusive lock on `path`, returning `None` if it is already held.
+    pub fn try_exclusive(path: &AbsolutePath) -> std::io::Result<Option<Self>> {
+        let file = Self::open(path)?;
+        Self::from_try_lock(path, file.try_lock(), file)
+    }
+
+    /// Attempt to take a shared lock on `path`, returning `None` if an exclusive lock is held.
+    pub fn try_shared(path: &AbsolutePath) -> std::io::Result<Option<Self>> {
+        let file = Self::open(path)?;
+        Self::from_try_lock(path, file.try_lock_shared(), file)
+    }
+
+    /// The path that is locked.
+    pub fn path(&self) -> &AbsolutePath {
+        self.path.as_absolute_path()
+    }
+
+    /// The open handle to the locked file.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    fn open(path: &AbsolutePath) -> std::io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+    }
+
+    fn from_try_lock(
+        path: &AbsolutePath,
+        result: Result<(), TryLockError>,
+        file: File,
+    ) -> std::io::Result<Option<Self>> {
+        match result {
+            Ok(()) => Ok(Some(Self {
+                file,
+                path: path.into(),
+            })),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+impl Drop for PathLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::PathLock;
+
+    #[test]
+    fn exclusive_blocks_other_locks() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let path = AbsolutePathBuf::try_new(temp.path().join("lock"))?;
+
+        let lock = PathLock::exclusive(&path)?;
+        assert_eq!(path.as_absolute_path(), lock.path());
+        assert!(path.exists());
+        assert!(PathLock::try_exclusive(&path)?.is_none());
+        assert!(PathLock::try_shared(&path)?.is_none());
+
+        drop(lock);
+        assert!(PathLock::try_exclusive(&path)?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_locks_coexist() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let path = AbsolutePathBuf::try_new(temp.path().join("lock"))?;
+
+        let first = PathLock::shared(&path)?;
+        let second = PathLock::try_shared(&path)?;
+        assert!(second.is_some());
+        assert!(PathLock::try_exclusive(&path)?.is_none());
+
+        drop(first);
+        drop(second);
+        assert!(PathLock::try_exclusive(&path)?.is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,316 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::SystemTime;
+
+use crate::fs::Operation;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A log file that lines are appended to, which is rotated once it gets too big or old.
+///
+/// Rotating renames `app.log` to `app.log.1`, after moving any older `app.log.1` to `app.log.2`
+/// and so on, and removing the oldest file beyond [`LogFile::keep`]. If the `gzip` feature is
+/// enabled and [`LogFile::compress`] is set, rotated files are compressed to `app.log.1.gz` etc.
+///
+/// Nothing is rotated unless [`LogFile::max_size`] or [`LogFile::daily`] is set. Days are in
+/// UTC.
+#[derive(Debug)]
+pub struct LogFile {
+    path: AbsolutePathBuf,
+    /// Closed while rotating, as open files cannot be renamed on windows.
+    file: Option<File>,
+    size: u64,
+    /// The day (since the unix epoch) that the current file was started on.
+    day: u64,
+    max_size: Option<u64>,
+    daily: bool,
+    keep: usize,
+    compress: bool,
+}
+
+impl LogFile {
+    /// Open `path` for appending, creating it if it does not exist.
+    ///
+    /// By default, nothing is rotated, and up to 5 rotated files are kept.
+    pub fn open(path: &AbsolutePath) -> std::io::Result<Self> {
+        let (file, size, day) = Self::open_file(path)?;
+        Ok(Self {
+            path: path.into(),
+            file: Some(file),
+            size,
+            day,
+            max_size: None,
+            daily: false,
+            keep: 5,
+            compress: false,
+        })
+    }
+
+    /// Rotate before a line would make the file larger than `bytes`.
+    ///
+    /// A single line larger than this is still written, to an otherwise empty file.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Rotate before writing the first line on a new day.
+    pub fn daily(mut self) -> Self {
+        self.daily = true;
+        self
+    }
+
+    /// Keep at most `count` rotated files, deleting older ones.
+    pub fn keep(mut self, count: usize) -> Self {
+        self.keep = count;
+        self
+    }
+
+    /// Compress rotated files with gzip.
+    #[cfg(feature = "gzip")]
+    pub fn compress(mut self) -> Self {
+        self.compress = true;
+        self
+    }
+
+    /// The path of the current log file.
+    pub fn path(&self) -> &AbsolutePath {
+        self.path.as_absolute_path()
+    }
+
+    /// The path that the `n`th most recent rotated file has, starting at 1.
+    pub fn rotated_path(&self, n: usize) -> AbsolutePathBuf {
+        let mut name = self
+            .path
+            .file_name()
+            .expect("opened files always have a file name")
+            .as_os_str()
+            .to_os_string();
+        name.push(format!(".{}", n));
+        if self.compress {
+            name.push(".gz");
+        }
+        self.path
+            .sibling(&name)
+            .expect("a file name with a suffix is still a file name")
+    }
+
+    /// Append `line` and a trailing newline, rotating first if needed.
+    pub fn append_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        let too_big = self
+            .max_size
+            .is_some_and(|max| self.size > 0 && self.size + len > max);
+        let new_day = self.daily && current_day() != self.day;
+        if too_big || new_day {
+            self.rotate()?;
+        }
+        let mut buf = Vec::with_capacity(line.len() + 1);
+        buf.extend_from_slice(line.as_bytes());
+        buf.push(b'\n');
+        let file = match &mut self.file {
+            Some(file) => file,
+            // A previous rotation failed part way through.
+            None => {
+                let (file, size, day) = Self::open_file(&self.path)?;
+                self.size = size;
+                self.day = day;
+                self.file.insert(file)
+            }
+        };
+        file.write_all(&buf)?;
+        self.size += len;
+        Ok(())
+    }
+
+    /// Flush the file.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Rotate the file now, regardless of its size or age.
+    pub fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        if self.keep == 0 {
+            remove_if_exists(&self.path)?;
+        } else {
+            remove_if_exists(&self.rotated_path(self.keep))?;
+            for n in (1..self.keep).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    rename(&from, &self.rotated_path(n + 1))?;
+                }
+            }
+            self.move_to_first_rotated()?;
+        }
+        let (file, size, day) = Self::open_file(&self.path)?;
+        self.file = Some(file);
+        self.size = size;
+        self.day = day;
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    fn move_to_first_rotated(&self) -> std::io::Result<()> {
+        if !self.compress {
+            return rename(&self.path, &self.rotated_path(1));
+        }
+        let dest = self.rotated_path(1);
+        crate::fs::audit(&Operation::Write(dest.clone()), None)?;
+        let mut src = File::open(&self.path)?;
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&dest)?, flate2::Compression::default());
+        std::io::copy(&mut src, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+        remove(&self.path)
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn move_to_first_rotated(&self) -> std::io::Result<()> {
+        rename(&self.path, &self.rotated_path(1))
+    }
+
+    fn open_file(path: &AbsolutePath) -> std::io::Result<(File, u64, u64)> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+        // An existing file was started no later than it was last written.
+        let day = metadata
+            .modified()
+            .ok()
+            .filter(|_| metadata.len() > 0)
+            .map_or_else(current_day, day_of);
+        Ok((file, metadata.len(), day))
+    }
+}
+
+/// Remove the file at `path`, after running the audit hooks.
+fn remove(path: &AbsolutePath) -> std::io::Result<()> {
+    crate::fs::audit(&Operation::Remove(path.into()), None)?;
+    std::fs::remove_file(path)
+}
+
+fn remove_if_exists(path: &AbsolutePath) -> std::io::Result<()> {
+    match remove(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Rename `from` to `to`, after running the audit hooks.
+fn rename(from: &AbsolutePath, to: &AbsolutePath) -> std::io::Result<()> {
+    let operation = Operation::Rename {
+        from: from.into(),
+        to: to.into(),
+    };
+    crate::fs::audit(&operation, None)?;
+    std::fs::rename(from, to)
+}
+
+fn day_of(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / SECONDS_PER_DAY)
+}
+
+fn current_day() -> u64 {
+    day_of(SystemTime::now())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use crate::AbsolutePathBuf;
+    use crate::LogFile;
+
+    #[test]
+    fn rotates_by_size() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = root.join("app.log")?;
+
+        let mut log = LogFile::open(&path)?.max_size(10).keep(2);
+        assert_eq!(root.join("app.log.1")?, log.rotated_path(1));
+        for line in ["one", "two", "three", "four", "a line longer than the max"] {
+            log.append_line(line)?;
+        }
+        log.flush()?;
+        assert_eq!(
+            "a line longer than the max\n",
+            std::fs::read_to_string(&path)?
+        );
+        assert_eq!("four\n", std::fs::read_to_string(log.rotated_path(1))?);
+        assert_eq!("three\n", std::fs::read_to_string(log.rotated_path(2))?);
+        assert!(!log.rotated_path(3).exists());
+
+        // Existing contents count towards the size when reopening.
+        drop(log);
+        let mut log = LogFile::open(&path)?.max_size(28).keep(0);
+        log.append_line("x")?;
+        assert_eq!("x\n", std::fs::read_to_string(&path)?);
+        // With nothing to keep, the old file is discarded without touching earlier rotations.
+        assert_eq!("four\n", std::fs::read_to_string(root.join("app.log.1")?)?);
+
+        // The file may have been removed by something else, e.g. logrotate. Open files cannot be
+        // removed on windows.
+        #[cfg(unix)]
+        {
+            std::fs::remove_file(&path)?;
+            log.rotate()?;
+            log.append_line("y")?;
+            assert_eq!("y\n", std::fs::read_to_string(&path)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rotates_daily() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = root.join("app.log")?;
+        std::fs::write(&path, "yesterday\n")?;
+        std::fs::File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60))?;
+
+        let mut log = LogFile::open(&path)?.daily();
+        log.append_line("today")?;
+        log.append_line("still today")?;
+        assert_eq!("today\nstill today\n", std::fs::read_to_string(&path)?);
+        assert_eq!("yesterday\n", std::fs::read_to_string(log.rotated_path(1))?);
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compresses_rotated_files() -> anyhow::Result<()> {
+        use std::io::Read;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = root.join("app.log")?;
+
+        let mut log = LogFile::open(&path)?.compress();
+        log.append_line("first")?;
+        log.rotate()?;
+        log.append_line("second")?;
+        log.rotate()?;
+
+        assert_eq!(root.join("app.log.1.gz")?, log.rotated_path(1));
+        let mut contents = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(log.rotated_path(2))?)
+            .read_to_string(&mut contents)?;
+        assert_eq!("first\n", contents);
+        assert_eq!(0, std::fs::metadata(&path)?.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// A list of recently used paths, most recent first, e.g. for an editor's "open recent" menu
+/// or a file picker.
+///
+/// The list holds at most [`MruList::capacity`] paths, dropping the least recently used ones
+/// when it is full. Each path appears at most once.
+///
+/// With the `serde` feature, lists serialize as `{"capacity": 10, "paths": ["/a", "/b"]}`, so
+/// they can be saved in whatever format the application uses. Paths that no longer exist are
+/// left out when a list is deserialized.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "RawMruList", into = "RawMruList")
+)]
+pub struct MruList {
+    paths: VecDeque<AbsolutePathBuf>,
+    capacity: usize,
+}
+
+impl MruList {
+    /// Create an empty list that holds up to `capacity` paths.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            paths: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Mark `path` as the most recently used.
+    pub fn push(&mut self, path: &AbsolutePath) {
+        self.remove(path);
+        self.paths.push_front(path.into());
+        self.paths.truncate(self.capacity);
+    }
+
+    /// Remove `path` from the list, returning whether it was there.
+    pub fn remove(&mut self, path: &AbsolutePath) -> bool {
+        match self.paths.iter().position(|p| p.as_absolute_path() == path) {
+            Some(index) => {
+                self.paths.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove paths that no longer exist, e.g. because they were deleted since they were used.
+    pub fn prune_missing(&mut self) {
+        self.paths.retain(|p| p.exists());
+    }
+
+    /// The most recently used path, if any.
+    pub fn most_recent(&self) -> Option<&AbsolutePath> {
+        self.paths.front().map(|p| p.as_absolute_path())
+    }
+
+    /// Iterate over the paths, most recently used first.
+    pub fn iter(&self) -> impl Iterator<Item = &AbsolutePath> {
+        self.paths.iter().map(|p| p.as_absolute_path())
+    }
+
+    /// The number of paths in the list.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Whether the list has no paths.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// The most paths that the list holds.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the most paths that the list holds, dropping the least recently used paths if
+    /// there are too many.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.paths.truncate(capacity);
+    }
+}
+
+/// The serialized form of an [`MruList`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMruList {
+    capacity: usize,
+    paths: Vec<AbsolutePathBuf>,
+}
+
+#[cfg(feature = "serde")]
+impl From<RawMruList> for MruList {
+    fn from(raw: RawMruList) -> Self {
+        // The capacity comes from the input, so it only bounds the allocation.
+        let mut list = MruList {
+            paths: VecDeque::with_capacity(raw.capacity.min(raw.paths.len())),
+            capacity: raw.capacity,
+        };
+        // Pushing the least recent first drops duplicates and extra paths, like using them
+        // in that order would.
+        for path in raw.paths.iter().rev() {
+            list.push(path);
+        }
+        list.prune_missing();
+        list
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<MruList> for RawMruList {
+    fn from(list: MruList) -> Self {
+        RawMruList {
+            capacity: list.capacity,
+            paths: list.paths.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePath;
+    use crate::AbsolutePathBuf;
+    use crate::MruList;
+
+    #[test]
+    fn tracks_recent_paths() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let (a, b, c) = (cwd.join("a")?, cwd.join("b")?, cwd.join("c")?);
+
+        let mut list = MruList::new(2);
+        assert!(list.is_empty());
+        list.push(&a);
+        list.push(&b);
+        list.push(&a);
+        assert_eq!(vec![&*a, &*b], list.iter().collect::<Vec<&AbsolutePath>>());
+        list.push(&c);
+        assert_eq!(vec![&*c, &*a], list.iter().collect::<Vec<_>>());
+        assert_eq!(Some(c.as_absolute_path()), list.most_recent());
+
+        assert!(list.remove(&c));
+        assert!(!list.remove(&b));
+        assert_eq!(1, list.len());
+        list.set_capacity(0);
+        assert!(list.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn prunes_missing_paths_on_load() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let (a, b, c) = (root.join("a")?, root.join("b")?, root.join("c")?);
+        for path in [&a, &b, &c] {
+            std::fs::write(path, "")?;
+        }
+
+        let mut list = MruList::new(3);
+        for path in [&a, &b, &c] {
+            list.push(path);
+        }
+        let serialized = serde_json::to_string(&list)?;
+        assert_eq!(
+            serde_json::json!({"capacity": 3, "paths": [&c, &b, &a]}),
+            serde_json::from_str::<serde_json::Value>(&serialized)?
+        );
+
+        std::fs::remove_file(&b)?;
+        let loaded: MruList = serde_json::from_str(&serialized)?;
+        assert_eq!(vec![&*c, &*a], loaded.iter().collect::<Vec<_>>());
+        assert_eq!(3, loaded.capacity());
+
+        let capped: MruList =
+            serde_json::from_value(serde_json::json!({"capacity": 1, "paths": [&a, &c, &a]}))?;
+        assert_eq!(vec![&*a], capped.iter().collect::<Vec<_>>());
+
+        let huge: MruList =
+            serde_json::from_str(r#"{"capacity": 18446744073709551615, "paths": []}"#)?;
+        assert_eq!(usize::MAX, huge.capacity());
+        assert!(huge.is_empty());
+        Ok(())
+    }
+}
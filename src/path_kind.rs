@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::NormalizationFailed;
+use crate::RelativePath;
+
+/// An [`AbsolutePathBuf`] to a directory, so that APIs can require a directory rather than a
+/// file in their signatures.
+///
+/// Unlike [`AbsolutePathBuf`], this does not deref to [`AbsolutePath`], so that only
+/// directories can be joined to. Use [`DirectoryPathBuf::as_absolute_path`] for everything
+/// else.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Ord, PartialOrd)]
+pub struct DirectoryPathBuf(AbsolutePathBuf);
+
+impl DirectoryPathBuf {
+    /// Create a [`DirectoryPathBuf`], checking that `path` is an existing directory (following
+    /// symlinks).
+    pub fn try_new(path: AbsolutePathBuf) -> std::io::Result<Self> {
+        if std::fs::metadata(&path)?.is_dir() {
+            Ok(Self(path))
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotADirectory,
+                format!("`{}` is not a directory", path.display()),
+            ))
+        }
+    }
+
+    /// Create a [`DirectoryPathBuf`] without checking the filesystem, e.g. for a directory that
+    /// is about to be created.
+    pub fn new_unchecked(path: AbsolutePathBuf) -> Self {
+        Self(path)
+    }
+
+    /// Get a new [`AbsolutePath`] referencing the internal Path object.
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        &self.0
+    }
+
+    /// Get a reference to the internal Path object.
+    pub fn as_path(&self) -> &Path {
+        self.0.as_path()
+    }
+
+    /// Join a relative path to this directory, like [`AbsolutePath::join_relative`].
+    pub fn join(&self, path: &RelativePath) -> Result<AbsolutePathBuf, NormalizationFailed> {
+        self.0.join_relative(path)
+    }
+
+    /// Join a relative path to this directory, and check that the result is an existing
+    /// directory.
+    pub fn join_dir(&self, path: &RelativePath) -> std::io::Result<DirectoryPathBuf> {
+        DirectoryPathBuf::try_new(self.join(path).map_err(invalid_input)?)
+    }
+
+    /// Join a relative path to this directory, and check that the result is an existing file.
+    pub fn join_file(&self, path: &RelativePath) -> std::io::Result<FilePathBuf> {
+        FilePathBuf::try_new(self.join(path).map_err(invalid_input)?)
+    }
+}
+
+/// An [`AbsolutePathBuf`] to a file (or anything else that is not a directory), so that APIs can
+/// require a file rather than a directory in their signatures.
+///
+/// Files cannot be joined to, so like [`DirectoryPathBuf`], this does not deref to
+/// [`AbsolutePath`].
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Ord, PartialOrd)]
+pub struct FilePathBuf(AbsolutePathBuf);
+
+impl FilePathBuf {
+    /// Create a [`FilePathBuf`], checking that `path` exists and is not a directory (following
+    /// symlinks).
+    pub fn try_new(path: AbsolutePathBuf) -> std::io::Result<Self> {
+        if std::fs::metadata(&path)?.is_dir() {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::IsADirectory,
+                format!("`{}` is a directory", path.display()),
+            ))
+        } else {
+            Ok(Self(path))
+        }
+    }
+
+    /// Create a [`FilePathBuf`] without checking the filesystem, e.g. for a file that is about to
+    /// be written.
+    pub fn new_unchecked(path: AbsolutePathBuf) -> Self {
+        Self(path)
+    }
+
+    /// Get a new [`AbsolutePath`] referencing the internal Path object.
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        &self.0
+    }
+
+    /// Get a reference to the internal Path object.
+    pub fn as_path(&self) -> &Path {
+        self.0.as_path()
+    }
+
+    /// The directory that contains this file.
+    pub fn parent(&self) -> DirectoryPathBuf {
+        DirectoryPathBuf(
+            self.0
+                .parent()
+                .expect("files are beneath the filesystem root")
+                .into(),
+        )
+    }
+
+    /// The name of this file, per [`AbsolutePath::file_name`].
+    pub fn file_name(&self) -> &RelativePath {
+        self.0
+            .file_name()
+            .expect("files are beneath the filesystem root")
+    }
+}
+
+fn invalid_input(e: NormalizationFailed) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, e)
+}
+
+impl TryFrom<AbsolutePathBuf> for DirectoryPathBuf {
+    type Error = std::io::Error;
+
+    fn try_from(value: AbsolutePathBuf) -> Result<Self, Self::Error> {
+        DirectoryPathBuf::try_new(value)
+    }
+}
+
+impl TryFrom<AbsolutePathBuf> for FilePathBuf {
+    type Error = std::io::Error;
+
+    fn try_from(value: AbsolutePathBuf) -> Result<Self, Self::Error> {
+        FilePathBuf::try_new(value)
+    }
+}
+
+impl From<DirectoryPathBuf> for AbsolutePathBuf {
+    fn from(value: DirectoryPathBuf) -> Self {
+        value.0
+    }
+}
+
+impl From<FilePathBuf> for AbsolutePathBuf {
+    fn from(value: FilePathBuf) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<Path> for DirectoryPathBuf {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<AbsolutePath> for DirectoryPathBuf {
+    fn as_ref(&self) -> &AbsolutePath {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for FilePathBuf {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+impl AsRef<AbsolutePath> for FilePathBuf {
+    fn as_ref(&self) -> &AbsolutePath {
+        &self.0
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for DirectoryPathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for FilePathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::DirectoryPathBuf;
+    use crate::FilePathBuf;
+    use crate::RelativePath;
+
+    #[test]
+    fn checks_path_kinds() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::create_dir(root.join("dir")?)?;
+        std::fs::write(root.join("dir/file.txt")?, "")?;
+
+        let root = DirectoryPathBuf::try_new(root)?;
+        let dir = root.join_dir(RelativePath::new_unchecked("dir"))?;
+        let file = dir.join_file(RelativePath::new_unchecked("file.txt"))?;
+        assert_eq!(dir, file.parent());
+        assert_eq!(RelativePath::new_unchecked("file.txt"), file.file_name());
+        assert_eq!(
+            root.join(RelativePath::new_unchecked("dir/file.txt"))?,
+            AbsolutePathBuf::from(file.clone())
+        );
+
+        assert_eq!(
+            std::io::ErrorKind::NotADirectory,
+            DirectoryPathBuf::try_from(AbsolutePathBuf::from(file))
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            std::io::ErrorKind::IsADirectory,
+            root.join_file(RelativePath::new_unchecked("dir"))
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            root.join_file(RelativePath::new_unchecked("missing.txt"))
+                .unwrap_err()
+                .kind()
+        );
+        let missing =
+            FilePathBuf::new_unchecked(root.join(RelativePath::new_unchecked("new.txt"))?);
+        assert!(!missing.as_path().exists());
+        Ok(())
+    }
+}
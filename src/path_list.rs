@@ -0,0 +1,221 @@
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::CombinedPathBuf;
+use crate::PathListError;
+
+/// Reads a list of paths, one per line, such as the output of `find` or the input to tar's
+/// `--files-from`.
+///
+/// Each path is validated as a [`CombinedPathBuf`], and errors report the line they were found
+/// on. Empty lines are skipped, and `\r\n` line endings are accepted. For lists that may contain
+/// paths with newlines in them (e.g. from `find -print0`), use
+/// [`PathListReader::nul_delimited`]. Iteration stops after the first error from the reader.
+#[derive(Debug)]
+pub struct PathListReader<R> {
+    reader: R,
+    delimiter: u8,
+    line: usize,
+    done: bool,
+}
+
+impl<R: BufRead> PathListReader<R> {
+    /// Read newline delimited paths from `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            delimiter: b'\n',
+            line: 0,
+            done: false,
+        }
+    }
+
+    /// Split paths on NUL bytes instead of newlines.
+    pub fn nul_delimited(mut self) -> Self {
+        self.delimiter = b'\0';
+        self
+    }
+
+    fn parse(&self, mut bytes: Vec<u8>) -> Result<CombinedPathBuf, PathListError> {
+        if bytes.last() == Some(&self.delimiter) {
+            bytes.pop();
+        }
+        if self.delimiter == b'\n' && bytes.last() == Some(&b'\r') {
+            bytes.pop();
+        }
+        let path = bytes_to_path(bytes).map_err(|e| PathListError::NotUtf8(self.line, e))?;
+        CombinedPathBuf::try_new(path).map_err(|e| PathListError::NormalizationFailed(self.line, e))
+    }
+}
+
+impl<R: BufRead> Iterator for PathListReader<R> {
+    type Item = Result<CombinedPathBuf, PathListError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.line += 1;
+            let mut bytes = vec![];
+            match self.reader.read_until(self.delimiter, &mut bytes) {
+                Ok(0) => self.done = true,
+                Ok(_) if bytes == [self.delimiter] || bytes == b"\r\n" => {}
+                Ok(_) => return Some(self.parse(bytes)),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(PathListError::Io(self.line, e)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Read NUL delimited paths, e.g. from `find -print0`.
+///
+/// This is the same as `PathListReader::new(reader).nul_delimited()`.
+pub fn read_nul_delimited<R: BufRead>(reader: R) -> PathListReader<R> {
+    PathListReader::new(reader).nul_delimited()
+}
+
+/// Write `paths` to `w`, each followed by a NUL byte, for tools like `xargs -0` and
+/// [`read_nul_delimited`].
+///
+/// Unlike newlines, NUL cannot appear in paths, so any path can be written. On platforms other
+/// than unix, paths that are not valid unicode cannot be written, and return an error.
+pub fn write_nul_delimited<I, W>(paths: I, mut w: W) -> std::io::Result<()>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+    W: Write,
+{
+    for path in paths {
+        w.write_all(path_to_bytes(path.as_ref())?)?;
+        w.write_all(b"\0")?;
+    }
+    w.flush()
+}
+
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> std::io::Result<&[u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_to_bytes(path: &Path) -> std::io::Result<&[u8]> {
+    path.to_str().map(str::as_bytes).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("`{}` is not valid unicode", path.display()),
+        )
+    })
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: Vec<u8>) -> Result<PathBuf, String> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(std::ffi::OsString::from_vec(bytes).into())
+}
+
+#[cfg(not(unix))]
+fn bytes_to_path(bytes: Vec<u8>) -> Result<PathBuf, String> {
+    String::from_utf8(bytes)
+        .map(PathBuf::from)
+        .map_err(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+    use std::io::Read;
+
+    use crate::read_nul_delimited;
+    use crate::write_nul_delimited;
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+    use crate::NormalizationFailed;
+    use crate::PathListError;
+    use crate::PathListReader;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn reads_path_lists() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let root = cwd.ancestors().last().expect("a root");
+        let input = format!(
+            "foo/bar\r\n\n{}\n{}\nbaz",
+            cwd.join("qux")?.as_path().display(),
+            root.as_path().join("..").display(),
+        );
+        let results: Vec<_> = PathListReader::new(input.as_bytes()).collect();
+        assert_eq!(4, results.len());
+        assert_eq!(
+            &CombinedPathBuf::from(RelativePathBuf::try_new("foo/bar")?),
+            results[0].as_ref().unwrap()
+        );
+        assert_eq!(
+            &CombinedPathBuf::from(cwd.join("qux")?),
+            results[1].as_ref().unwrap()
+        );
+        match &results[2] {
+            Err(PathListError::NormalizationFailed(4, NormalizationFailed(path))) => {
+                assert_eq!(&root.as_path().join("..").display().to_string(), path)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(
+            &CombinedPathBuf::from(RelativePathBuf::try_new("baz")?),
+            results[3].as_ref().unwrap()
+        );
+
+        let paths = PathListReader::new(BufReader::new("a\nb\0c\0\0".as_bytes()))
+            .nul_delimited()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            vec![
+                CombinedPathBuf::from(RelativePathBuf::try_new("a\nb")?),
+                RelativePathBuf::try_new("c")?.into(),
+            ],
+            paths
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips_nul_delimited() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let paths = vec![
+            CombinedPathBuf::from(RelativePathBuf::try_new("with\nnewline")?),
+            cwd.join("foo bar")?.into(),
+        ];
+        let mut out = vec![];
+        write_nul_delimited(&paths, &mut out)?;
+        assert_eq!(Some(&b'\0'), out.last());
+        assert_eq!(
+            paths,
+            read_nul_delimited(out.as_slice()).collect::<Result<Vec<_>, _>>()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stops_on_read_errors() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::PermissionDenied.into())
+            }
+        }
+
+        let mut reader = PathListReader::new(BufReader::new(Failing));
+        match reader.next() {
+            Some(Err(e @ PathListError::Io(..))) => {
+                assert_eq!(1, e.line());
+                assert_eq!("io", e.code());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(reader.next().is_none());
+    }
+}
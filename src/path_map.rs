@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// A map from absolute paths to values that can also look up the entries for a path's
+/// ancestors and descendants, e.g. for mount tables, or configuration set per directory.
+///
+/// ```
+/// use paths::AbsolutePath;
+/// use paths::AbsolutePathBuf;
+/// use paths::PathMap;
+///
+/// let mut mounts = PathMap::new();
+/// mounts.insert(AbsolutePathBuf::try_new("/").unwrap(), "root");
+/// mounts.insert(AbsolutePathBuf::try_new("/home").unwrap(), "home");
+/// let (mount, name) = mounts
+///     .longest_prefix_match(AbsolutePath::new_unchecked("/home/alice/notes.txt"))
+///     .unwrap();
+/// assert_eq!((AbsolutePath::new_unchecked("/home"), &"home"), (mount, name));
+/// ```
+///
+/// Paths are compared component by component, so everything beneath a directory is stored next
+/// to it, and [`PathMap::range_under`] does not have to check every entry.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct PathMap<V> {
+    entries: BTreeMap<AbsolutePathBuf, V>,
+}
+
+impl<V> Default for PathMap<V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> PathMap<V> {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `value` for `path`, returning the previous value for it, if any.
+    pub fn insert(&mut self, path: AbsolutePathBuf, value: V) -> Option<V> {
+        self.entries.insert(path, value)
+    }
+
+    /// Remove the value for `path`, returning it if it was present.
+    pub fn remove(&mut self, path: &AbsolutePath) -> Option<V> {
+        self.entries.remove(path)
+    }
+
+    /// The value for exactly `path`.
+    pub fn get(&self, path: &AbsolutePath) -> Option<&V> {
+        self.entries.get(path)
+    }
+
+    /// A mutable reference to the value for exactly `path`.
+    pub fn get_mut(&mut self, path: &AbsolutePath) -> Option<&mut V> {
+        self.entries.get_mut(path)
+    }
+
+    /// Whether there is a value for exactly `path`.
+    pub fn contains_key(&self, path: &AbsolutePath) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// The entry for the closest of `path` and its ancestors that has one, e.g. the mount point
+    /// that contains `path`.
+    pub fn longest_prefix_match(&self, path: &AbsolutePath) -> Option<(&AbsolutePath, &V)> {
+        path.ancestors().find_map(|ancestor| {
+            self.entries
+                .get_key_value(ancestor)
+                .map(|(path, value)| (path.as_absolute_path(), value))
+        })
+    }
+
+    /// The entries for `path` and everything beneath it, in sorted order.
+    pub fn range_under<'a>(
+        &'a self,
+        path: &'a AbsolutePath,
+    ) -> impl Iterator<Item = (&'a AbsolutePath, &'a V)> + 'a {
+        self.entries
+            .range::<AbsolutePath, _>((Bound::Included(path), Bound::Unbounded))
+            .take_while(move |(entry, _)| entry.starts_with(path))
+            .map(|(entry, value)| (entry.as_absolute_path(), value))
+    }
+
+    /// All of the entries, in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = (&AbsolutePath, &V)> {
+        self.entries
+            .iter()
+            .map(|(path, value)| (path.as_absolute_path(), value))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<V> FromIterator<(AbsolutePathBuf, V)> for PathMap<V> {
+    fn from_iter<T: IntoIterator<Item = (AbsolutePathBuf, V)>>(iter: T) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<V> Extend<(AbsolutePathBuf, V)> for PathMap<V> {
+    fn extend<T: IntoIterator<Item = (AbsolutePathBuf, V)>>(&mut self, iter: T) {
+        self.entries.extend(iter)
+    }
+}
+
+impl<V> IntoIterator for PathMap<V> {
+    type Item = (AbsolutePathBuf, V);
+    type IntoIter = std::collections::btree_map::IntoIter<AbsolutePathBuf, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::PathMap;
+
+    #[test]
+    fn looks_up_hierarchically() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let map: PathMap<u32> = [
+            (cwd.clone(), 0),
+            (cwd.join("a")?, 1),
+            (cwd.join("a/b/c")?, 2),
+            (cwd.join("a-b")?, 3),
+            (cwd.join("d")?, 4),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(Some(&1), map.get(&cwd.join("a")?));
+        assert_eq!(None, map.get(&cwd.join("a/b")?));
+        let longest = |p: &str| -> anyhow::Result<_> {
+            Ok(map
+                .longest_prefix_match(&cwd.join(p)?)
+                .map(|(path, value)| (path.to_owned(), *value)))
+        };
+        assert_eq!(Some((cwd.join("a/b/c")?, 2)), longest("a/b/c/d.txt")?);
+        assert_eq!(Some((cwd.join("a")?, 1)), longest("a/b")?);
+        assert_eq!(Some((cwd.join("a")?, 1)), longest("a")?);
+        assert_eq!(Some((cwd.clone(), 0)), longest("e")?);
+        assert_eq!(None, map.longest_prefix_match(cwd.parent().unwrap()));
+
+        let a = cwd.join("a")?;
+        assert_eq!(
+            vec![1, 2],
+            map.range_under(&a).map(|(_, v)| *v).collect::<Vec<_>>()
+        );
+        assert_eq!(0, map.range_under(&cwd.join("b")?).count());
+        assert_eq!(5, map.range_under(&cwd).count());
+        assert_eq!(
+            map.iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+        Ok(())
+    }
+}
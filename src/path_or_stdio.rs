@@ -0,0 +1,185 @@
+use std::io::Read;
+use std::io::Write;
+#[cfg(any(feature = "display", feature = "serde"))]
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::CombinedPathBuf;
+use crate::NormalizationFailed;
+
+/// A path given on the command line, where `-` means stdin or stdout instead of a file.
+///
+/// This implements [`FromStr`], so it can be used directly as a `clap` argument type.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PathOrStdio {
+    /// `-`, i.e. stdin when reading, or stdout when writing.
+    Stdio,
+    Path(CombinedPathBuf),
+}
+
+impl PathOrStdio {
+    /// Whether this is `-`.
+    pub fn is_stdio(&self) -> bool {
+        matches!(self, PathOrStdio::Stdio)
+    }
+
+    /// Get the path, unless this is `-`.
+    pub fn as_path(&self) -> Option<&CombinedPathBuf> {
+        match self {
+            PathOrStdio::Stdio => None,
+            PathOrStdio::Path(p) => Some(p),
+        }
+    }
+
+    /// Whether this is a relative path to a file named `-`, which has to be written as `./-` so
+    /// that it is not parsed as [`PathOrStdio::Stdio`].
+    #[cfg(any(feature = "display", feature = "serde"))]
+    fn is_dash_file(&self) -> bool {
+        matches!(self, PathOrStdio::Path(p) if p.as_path() == Path::new("-"))
+    }
+
+    /// Open stdin, or the file for reading. Relative paths are relative to the cwd.
+    pub fn open_read(&self) -> std::io::Result<Box<dyn Read>> {
+        match self {
+            PathOrStdio::Stdio => Ok(Box::new(std::io::stdin())),
+            PathOrStdio::Path(p) => Ok(Box::new(std::fs::File::open(p)?)),
+        }
+    }
+
+    /// Open stdout, or create (or truncate) the file for writing. Relative paths are relative to
+    /// the cwd.
+    pub fn open_write(&self) -> std::io::Result<Box<dyn Write>> {
+        match self {
+            PathOrStdio::Stdio => Ok(Box::new(std::io::stdout())),
+            PathOrStdio::Path(p) => Ok(Box::new(std::fs::File::create(p)?)),
+        }
+    }
+}
+
+impl From<CombinedPathBuf> for PathOrStdio {
+    fn from(p: CombinedPathBuf) -> Self {
+        PathOrStdio::Path(p)
+    }
+}
+
+impl TryFrom<PathBuf> for PathOrStdio {
+    type Error = NormalizationFailed;
+
+    fn try_from(value: PathBuf) -> Result<Self, Self::Error> {
+        if value.as_os_str() == "-" {
+            Ok(PathOrStdio::Stdio)
+        } else {
+            Ok(PathOrStdio::Path(CombinedPathBuf::try_new(value)?))
+        }
+    }
+}
+
+impl FromStr for PathOrStdio {
+    type Err = NormalizationFailed;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PathOrStdio::try_from(PathBuf::from(s))
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for PathOrStdio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathOrStdio::Stdio => f.write_str("-"),
+            PathOrStdio::Path(_) if self.is_dash_file() => f.write_str("./-"),
+            PathOrStdio::Path(p) => p.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PathOrStdio {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            PathOrStdio::Stdio => crate::serde_path::serialize(Path::new("-"), serializer),
+            PathOrStdio::Path(_) if self.is_dash_file() => {
+                crate::serde_path::serialize(Path::new("./-"), serializer)
+            }
+            PathOrStdio::Path(p) => p.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PathOrStdio {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let path = crate::serde_path::deserialize(deserializer)?;
+        PathOrStdio::try_from(path).map_err(|e| D::Error::custom(format!("{}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::io::Write;
+
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+    use crate::PathOrStdio;
+
+    #[test]
+    fn parses_dash_as_stdio() -> anyhow::Result<()> {
+        assert_eq!(PathOrStdio::Stdio, "-".parse()?);
+        assert!(PathOrStdio::Stdio.is_stdio());
+        assert_eq!(None, PathOrStdio::Stdio.as_path());
+
+        let relative: PathOrStdio = "./-".parse()?;
+        assert_eq!(Some(&CombinedPathBuf::try_new("-")?), relative.as_path());
+        assert!("/..".parse::<PathOrStdio>().is_err());
+
+        #[cfg(feature = "display")]
+        {
+            assert_eq!("-", PathOrStdio::Stdio.to_string());
+            assert_eq!("./-", relative.to_string());
+            assert_eq!(relative, relative.to_string().parse()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn opens_paths() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let path = PathOrStdio::from(CombinedPathBuf::from(root.join("out.txt")?));
+
+        path.open_write()?.write_all(b"hello")?;
+        let mut contents = String::new();
+        path.open_read()?.read_to_string(&mut contents)?;
+        assert_eq!("hello", contents);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes() -> anyhow::Result<()> {
+        assert_eq!("\"-\"", serde_json::to_string(&PathOrStdio::Stdio)?);
+        assert_eq!(
+            PathOrStdio::Stdio,
+            serde_json::from_str::<PathOrStdio>("\"-\"")?
+        );
+        assert_eq!(
+            PathOrStdio::Path(CombinedPathBuf::try_new("foo/bar")?),
+            serde_json::from_str::<PathOrStdio>("\"foo/bar\"")?
+        );
+
+        let dash = PathOrStdio::Path(CombinedPathBuf::try_new("-")?);
+        let serialized = serde_json::to_string(&dash)?;
+        assert_eq!("\"./-\"", serialized);
+        assert_eq!(dash, serde_json::from_str::<PathOrStdio>(&serialized)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// A set of absolute paths that are stored one component at a time, like a trie, so that
+/// everything beneath a directory can be found without checking every path.
+///
+/// ```
+/// use paths::AbsolutePath;
+/// use paths::PathSet;
+///
+/// let paths: PathSet = ["/var/data/a", "/var/data/b/c", "/var/log"]
+///     .map(AbsolutePath::new_unchecked)
+///     .into_iter()
+///     .collect();
+/// let data = AbsolutePath::new_unchecked("/var/data");
+/// assert_eq!(2, paths.descendants_of(data).count());
+/// assert!(!paths.contains(data));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PathSet {
+    root: Node,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+struct Node {
+    /// Whether the path ending at this node is in the set, rather than just one of its
+    /// ancestors.
+    present: bool,
+    children: BTreeMap<OsString, Node>,
+}
+
+impl PathSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `path` to the set, returning whether it was not already present.
+    pub fn insert(&mut self, path: &AbsolutePath) -> bool {
+        let mut node = &mut self.root;
+        for component in path.as_path().components() {
+            node = node
+                .children
+                .entry(component.as_os_str().to_owned())
+                .or_default();
+        }
+        let inserted = !node.present;
+        node.present = true;
+        self.len += usize::from(inserted);
+        inserted
+    }
+
+    /// Remove `path` from the set, returning whether it was present. Its descendants are kept.
+    pub fn remove(&mut self, path: &AbsolutePath) -> bool {
+        let components: Vec<&OsStr> = path.as_path().components().map(|c| c.as_os_str()).collect();
+        let removed = Self::remove_from(&mut self.root, &components);
+        self.len -= usize::from(removed);
+        removed
+    }
+
+    /// Remove the path made of `components` beneath `node`, pruning nodes that are left empty.
+    fn remove_from(node: &mut Node, components: &[&OsStr]) -> bool {
+        let Some((first, rest)) = components.split_first() else {
+            return std::mem::replace(&mut node.present, false);
+        };
+        let Some(child) = node.children.get_mut(*first) else {
+            return false;
+        };
+        let removed = Self::remove_from(child, rest);
+        if !child.present && child.children.is_empty() {
+            node.children.remove(*first);
+        }
+        removed
+    }
+
+    /// Whether `path` is in the set.
+    pub fn contains(&self, path: &AbsolutePath) -> bool {
+        self.find(path).is_some_and(|node| node.present)
+    }
+
+    /// The paths in the set that are beneath `prefix`, not including `prefix` itself, in sorted
+    /// order.
+    ///
+    /// This only visits the paths beneath `prefix`, so e.g. `descendants_of(dir).next()` is a
+    /// cheap way to check whether anything in the set is inside of a directory.
+    pub fn descendants_of(
+        &self,
+        prefix: &AbsolutePath,
+    ) -> impl Iterator<Item = AbsolutePathBuf> + '_ {
+        let mut iter = Iter { stack: vec![] };
+        if let Some(node) = self.find(prefix) {
+            iter.push_children(prefix.as_path().to_owned(), node);
+        }
+        iter
+    }
+
+    /// All of the paths in the set, in sorted order.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = AbsolutePathBuf> + '_ {
+        let mut iter = Iter { stack: vec![] };
+        iter.push_children(PathBuf::new(), &self.root);
+        iter
+    }
+
+    /// The number of paths in the set.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn find(&self, path: &AbsolutePath) -> Option<&Node> {
+        path.as_path()
+            .components()
+            .try_fold(&self.root, |node, component| {
+                node.children.get(component.as_os_str())
+            })
+    }
+}
+
+/// A depth first walk of a [`PathSet`], which visits parents before their children, and
+/// children in order, so that paths come out sorted.
+struct Iter<'a> {
+    stack: Vec<(PathBuf, &'a Node)>,
+}
+
+impl<'a> Iter<'a> {
+    fn push_children(&mut self, path: PathBuf, node: &'a Node) {
+        for (name, child) in node.children.iter().rev() {
+            self.stack.push((path.join(name), child));
+        }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = AbsolutePathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, node)) = self.stack.pop() {
+            self.push_children(path.clone(), node);
+            if node.present {
+                return Some(AbsolutePathBuf::new_unchecked(path));
+            }
+        }
+        None
+    }
+}
+
+impl<'a> FromIterator<&'a AbsolutePath> for PathSet {
+    fn from_iter<T: IntoIterator<Item = &'a AbsolutePath>>(iter: T) -> Self {
+        let mut set = PathSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a> Extend<&'a AbsolutePath> for PathSet {
+    fn extend<T: IntoIterator<Item = &'a AbsolutePath>>(&mut self, iter: T) {
+        for path in iter {
+            self.insert(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::PathSet;
+
+    #[test]
+    fn queries_prefixes() -> anyhow::Result<()> {
+        let root = AbsolutePathBuf::current_dir();
+        let path = |p: &str| root.join(p);
+        let mut set = PathSet::new();
+        for p in ["data/b", "data/a/x", "data", "log/today", "data-other"] {
+            assert!(set.insert(&path(p)?));
+        }
+        assert!(!set.insert(&path("data/b")?));
+        assert_eq!(5, set.len());
+        assert!(set.contains(&path("data")?));
+        assert!(!set.contains(&path("data/a")?));
+        assert!(!set.contains(&path("missing/a")?));
+
+        assert_eq!(
+            vec![path("data/a/x")?, path("data/b")?],
+            set.descendants_of(&path("data")?).collect::<Vec<_>>()
+        );
+        assert_eq!(0, set.descendants_of(&path("data/b")?).count());
+        assert_eq!(0, set.descendants_of(&path("missing")?).count());
+        assert_eq!(
+            vec![
+                path("data")?,
+                path("data/a/x")?,
+                path("data/b")?,
+                path("data-other")?,
+                path("log/today")?
+            ],
+            set.iter_sorted().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            set.iter_sorted().collect::<Vec<_>>(),
+            set.descendants_of(root.ancestors().last().unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        assert!(set.remove(&path("data/a/x")?));
+        assert!(!set.remove(&path("data/a/x")?));
+        assert!(!set.remove(&path("data/a")?));
+        assert!(set.remove(&path("data")?));
+        assert_eq!(
+            vec![path("data/b")?],
+            set.descendants_of(&path("data")?).collect::<Vec<_>>()
+        );
+        assert_eq!(3, set.len());
+        Ok(())
+    }
+}
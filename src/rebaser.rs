@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::NotBeneathBase;
+use crate::RelativePath;
+
+/// Converts paths between absolute paths and paths relative to a base directory, e.g. a
+/// server's document root for the duration of a request.
+///
+/// Both conversions fail for paths that are not beneath the base, so relative paths from
+/// untrusted input cannot use `..` to escape it. The base itself is the empty relative path.
+/// This only checks paths lexically, so symlinks beneath the base can still lead outside of it.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Rebaser {
+    base: AbsolutePathBuf,
+}
+
+impl Rebaser {
+    /// Convert paths beneath `base`.
+    pub fn new(base: AbsolutePathBuf) -> Self {
+        Self { base }
+    }
+
+    /// The directory that relative paths are relative to.
+    pub fn base(&self) -> &AbsolutePath {
+        self.base.as_absolute_path()
+    }
+
+    /// Get `path` relative to the base, borrowing it rather than allocating, e.g. `a/b` for
+    /// `/base/a/b`.
+    pub fn to_relative<'a>(
+        &self,
+        path: &'a AbsolutePath,
+    ) -> Result<&'a RelativePath, NotBeneathBase> {
+        path.strip_prefix(&self.base)
+            .map_err(|_| self.not_beneath(path.as_path()))
+    }
+
+    /// Get the absolute path for `path` relative to the base, e.g. `/base/a/b` for `a/./b`.
+    pub fn to_absolute(&self, path: &RelativePath) -> Result<AbsolutePathBuf, NotBeneathBase> {
+        self.base
+            .join_relative(path)
+            .ok()
+            .filter(|joined| joined.starts_with(&self.base))
+            .ok_or_else(|| self.not_beneath(path.as_ref()))
+    }
+
+    fn not_beneath(&self, path: &Path) -> NotBeneathBase {
+        NotBeneathBase(path.display().to_string(), self.base.display().to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::Rebaser;
+    use crate::RelativePath;
+
+    #[test]
+    fn rebases_paths() -> anyhow::Result<()> {
+        let base = AbsolutePathBuf::current_dir().join("srv/www")?;
+        let rebaser = Rebaser::new(base.clone());
+        assert_eq!(base.as_absolute_path(), rebaser.base());
+
+        let file = base.join("css/site.css")?;
+        assert_eq!(
+            RelativePath::try_new("css/site.css")?,
+            rebaser.to_relative(&file)?
+        );
+        assert!(rebaser.to_relative(&base)?.as_os_str().is_empty());
+        assert!(rebaser.to_relative(&base.join("../www2")?).is_err());
+
+        assert_eq!(
+            file,
+            rebaser.to_absolute(RelativePath::try_new("css/./site.css")?)?
+        );
+        assert_eq!(
+            file,
+            rebaser.to_absolute(RelativePath::try_new("img/../css/site.css")?)?
+        );
+        assert_eq!(base, rebaser.to_absolute(RelativePath::try_new("")?)?);
+        let escaped = rebaser
+            .to_absolute(RelativePath::try_new("../secret")?)
+            .unwrap_err();
+        assert_eq!("not_beneath_base", escaped.code());
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::AbsolutePathBuf;
+
+/// A path that only shows its file name (or a hash of the whole path) when it is formatted, for
+/// logging where full paths would leak e.g. usernames or tenant IDs.
+///
+/// The path is still available with [`Redacted::as_inner`], or through [`Deref`].
+///
+/// ```
+/// use paths::AbsolutePathBuf;
+/// use paths::Redacted;
+///
+/// let path = AbsolutePathBuf::try_new("/home/alice/.config/secrets.toml").unwrap();
+/// assert_eq!("secrets.toml", Redacted::new(path.clone()).to_string());
+/// assert_eq!("Redacted(\"secrets.toml\")", format!("{:?}", Redacted::new(path.clone())));
+/// assert!(Redacted::hashed(path).to_string().starts_with('#'));
+/// ```
+#[derive(Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Redacted<P = AbsolutePathBuf> {
+    path: P,
+    hashed: bool,
+}
+
+impl<P: AsRef<Path>> Redacted<P> {
+    /// Show only the path's file name. Paths without one, e.g. `/`, are shown as a hash.
+    pub fn new(path: P) -> Self {
+        Self {
+            path,
+            hashed: false,
+        }
+    }
+
+    /// Show a hash of the whole path, e.g. `#af63bd4c8601b7df`, so that log lines about the same
+    /// path can be correlated without revealing it. The hash is the same between runs, so it is
+    /// not a secret either; it only stops paths being read casually.
+    pub fn hashed(path: P) -> Self {
+        Self { path, hashed: true }
+    }
+
+    /// Get a reference to the full path.
+    pub fn as_inner(&self) -> &P {
+        &self.path
+    }
+
+    /// Take ownership of the full path.
+    pub fn into_inner(self) -> P {
+        self.path
+    }
+
+    /// The text that is shown instead of the path.
+    fn redacted(&self) -> String {
+        let path = self.path.as_ref();
+        match path.file_name() {
+            Some(name) if !self.hashed => name.to_string_lossy().into_owned(),
+            _ => format!("#{:016x}", fnv1a(path.as_os_str().as_encoded_bytes())),
+        }
+    }
+}
+
+/// 64 bit FNV-1a, which unlike [`std::hash::DefaultHasher`] is the same in every build. Also used
+/// by [`crate::CacheKey::stable_hash`].
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl<P: AsRef<Path>> From<P> for Redacted<P> {
+    fn from(value: P) -> Self {
+        Redacted::new(value)
+    }
+}
+
+impl<P> Deref for Redacted<P> {
+    type Target = P;
+
+    fn deref(&self) -> &Self::Target {
+        &self.path
+    }
+}
+
+impl<P: AsRef<Path>> AsRef<Path> for Redacted<P> {
+    fn as_ref(&self) -> &Path {
+        self.path.as_ref()
+    }
+}
+
+impl<P: AsRef<Path>> Display for Redacted<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.redacted())
+    }
+}
+
+impl<P: AsRef<Path>> Debug for Redacted<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Redacted").field(&self.redacted()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::Redacted;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn redacts_paths() -> anyhow::Result<()> {
+        let path = AbsolutePathBuf::try_new("/home/alice/tenant-1234/config.toml")?;
+        let redacted = Redacted::new(path.clone());
+        assert_eq!("config.toml", redacted.to_string());
+        assert_eq!("Redacted(\"config.toml\")", format!("{:?}", redacted));
+        assert!(!format!("{:#?}", redacted).contains("alice"));
+        assert_eq!(&path, redacted.as_inner());
+        assert_eq!(path.parent(), redacted.parent());
+
+        let hashed = Redacted::hashed(path.clone());
+        assert_eq!(17, hashed.to_string().len());
+        assert!(hashed.to_string().starts_with('#'));
+        assert_eq!(
+            hashed.to_string(),
+            Redacted::hashed(path.clone()).to_string()
+        );
+        let other = AbsolutePathBuf::try_new("/home/bob/tenant-1234/config.toml")?;
+        assert_ne!(hashed.to_string(), Redacted::hashed(other).to_string());
+        assert_eq!(path, hashed.into_inner());
+
+        let root = Redacted::new(AbsolutePathBuf::try_new("/")?);
+        assert!(root.to_string().starts_with('#'));
+        let relative = Redacted::from(RelativePathBuf::try_new("foo/bar.txt")?);
+        assert_eq!("bar.txt", relative.to_string());
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::ffi::OsStr;
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::path::Path;
@@ -10,6 +11,7 @@ use crate::errors::JoinedAbsolute;
 use crate::errors::NotRelative;
 use crate::AbsolutePath;
 use crate::AbsolutePathBuf;
+use crate::InvalidFileName;
 use crate::NormalizationFailed;
 
 /// A relative path. This is not normalized until joined to an absolute path.
@@ -71,6 +73,62 @@ impl RelativePath {
         abs.join_relative(self)
     }
 
+    /// Like `Path::parent()`, but returns a [`RelativePath`].
+    ///
+    /// As with `Path`, the parent of a single component is the empty path, which has no parent.
+    pub fn parent(&self) -> Option<&RelativePath> {
+        self.0.parent().map(RelativePath::new_unchecked)
+    }
+
+    /// Like `Path::ancestors()`, but yields [`RelativePath`]s.
+    ///
+    /// This starts with the path itself, and ends with the empty path.
+    pub fn ancestors(&self) -> impl Iterator<Item = &RelativePath> {
+        self.0.ancestors().map(RelativePath::new_unchecked)
+    }
+
+    /// Like `Path::starts_with()`, but only accepts a [`RelativePath`]. Only whole components are
+    /// compared, so `foo/bar` starts with `foo`, but not with `fo`.
+    pub fn starts_with(&self, base: &RelativePath) -> bool {
+        self.0.starts_with(&base.0)
+    }
+
+    /// Like `Path::ends_with()`, but only accepts a [`RelativePath`]. Only whole components are
+    /// compared.
+    pub fn ends_with(&self, child: &RelativePath) -> bool {
+        self.0.ends_with(&child.0)
+    }
+
+    /// Like `Path::file_name()`, but returns a [`RelativePath`].
+    ///
+    /// This is `None` for empty paths, and paths ending in `..`.
+    pub fn file_name(&self) -> Option<&RelativePath> {
+        self.0.file_name().map(RelativePath::new_unchecked)
+    }
+
+    /// Like `Path::with_file_name()`, but returns a [`RelativePathBuf`].
+    ///
+    /// `name` must be a single file name, without any separators, `.` or `..`.
+    pub fn with_file_name<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        name: &S,
+    ) -> Result<RelativePathBuf, InvalidFileName> {
+        let name = name.as_ref();
+        crate::check_file_name(name)?;
+        Ok(RelativePathBuf(self.0.with_file_name(name)))
+    }
+
+    /// Like `Path::with_extension()`, but returns a [`RelativePathBuf`].
+    ///
+    /// Fails if this path has no file name, or the extension contains a separator.
+    pub fn with_extension<S: AsRef<OsStr> + ?Sized>(
+        &self,
+        extension: &S,
+    ) -> Result<RelativePathBuf, InvalidFileName> {
+        let name = crate::file_name_with_extension(&self.0, extension.as_ref())?;
+        Ok(RelativePathBuf(self.0.with_file_name(name)))
+    }
+
     /// Like `Path::to_string_lossy()`, but returns an owned string.
     pub fn to_lossy_string(&self) -> String {
         self.0.to_string_lossy().to_string()
@@ -80,6 +138,45 @@ impl RelativePath {
     pub fn ensure_parent_exists(&self) -> std::io::Result<()> {
         crate::create_parent_dir(self)
     }
+
+    /// Whether `regex` matches the portable form of this path, which uses `/` as the separator
+    /// on every platform.
+    ///
+    /// The regex is not anchored unless it was written that way, or compiled with
+    /// [`RegexAnchor::compile`].
+    #[cfg(feature = "regex")]
+    pub fn matches_regex(&self, regex: &regex::Regex) -> bool {
+        let portable = self.to_lossy_string();
+        if std::path::MAIN_SEPARATOR == '/' {
+            regex.is_match(&portable)
+        } else {
+            regex.is_match(&portable.replace(std::path::MAIN_SEPARATOR, "/"))
+        }
+    }
+}
+
+/// How a regex used with [`RelativePath::matches_regex`] is anchored to the path.
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RegexAnchor {
+    /// The regex may match anywhere in the path.
+    Unanchored,
+    /// The regex must match at the start of the path.
+    Start,
+    /// The regex must match the whole path.
+    Full,
+}
+
+#[cfg(feature = "regex")]
+impl RegexAnchor {
+    /// Compile `pattern` into a regex that is anchored as specified.
+    pub fn compile(self, pattern: &str) -> Result<regex::Regex, regex::Error> {
+        match self {
+            RegexAnchor::Unanchored => regex::Regex::new(pattern),
+            RegexAnchor::Start => regex::Regex::new(&format!("\\A(?:{})", pattern)),
+            RegexAnchor::Full => regex::Regex::new(&format!("\\A(?:{})\\z", pattern)),
+        }
+    }
 }
 
 impl AsRef<Path> for RelativePath {
@@ -88,6 +185,12 @@ impl AsRef<Path> for RelativePath {
     }
 }
 
+impl AsRef<OsStr> for RelativePath {
+    fn as_ref(&self) -> &OsStr {
+        self.0.as_os_str()
+    }
+}
+
 impl AsRef<RelativePath> for RelativePath {
     fn as_ref(&self) -> &RelativePath {
         self
@@ -115,7 +218,22 @@ impl serde::Serialize for RelativePath {
     where
         S: serde::Serializer,
     {
-        self.0.serialize(serializer)
+        crate::serde_path::serialize(&self.0, serializer)
+    }
+}
+
+/// Borrows the path from the input without allocating, e.g. for large manifests.
+///
+/// The input must allow borrowing, e.g. a JSON string without any escapes.
+#[cfg(feature = "serde")]
+impl<'de: 'a, 'a> serde::Deserialize<'de> for &'a RelativePath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let path = crate::serde_path::deserialize_borrowed(deserializer)?;
+        RelativePath::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
     }
 }
 
@@ -129,7 +247,7 @@ where
         &'b self,
         out: &mut diesel::serialize::Output<'b, '_, DB>,
     ) -> diesel::serialize::Result {
-        self.0.to_str().expect("paths should be utf8").to_sql(out)
+        crate::path_to_str(&self.0)?.to_sql(out)
     }
 }
 
@@ -146,7 +264,7 @@ impl RelativePathBuf {
     /// Attempt to create an instance of [`RelativePathBuf`].
     ///
     /// This will fail if the provided path is absolute.
-    pub fn try_new<P: Into<PathBuf> + ?Sized>(path: P) -> Result<Self, NotRelative> {
+    pub fn try_new<P: Into<PathBuf>>(path: P) -> Result<Self, NotRelative> {
         let p = path.into();
         if p.is_absolute() {
             Err(NotRelative(p.display().to_string()))
@@ -186,7 +304,7 @@ impl RelativePathBuf {
     ///
     /// This is mostly used for paths that are known ahead of time (e.g. static strings) to be
     /// valid.
-    pub fn new_unchecked<P: Into<PathBuf> + ?Sized>(path: P) -> Self {
+    pub fn new_unchecked<P: Into<PathBuf>>(path: P) -> Self {
         Self::try_new(path).expect("a relative path")
     }
 
@@ -225,6 +343,28 @@ impl RelativePathBuf {
         abs.join_relative(self.as_relative_path())
     }
 
+    /// Like `PathBuf::set_file_name()`, but fails if `name` is not a single file name.
+    pub fn set_file_name<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        name: &S,
+    ) -> Result<(), InvalidFileName> {
+        let name = name.as_ref();
+        crate::check_file_name(name)?;
+        self.0.set_file_name(name);
+        Ok(())
+    }
+
+    /// Like `PathBuf::set_extension()`, but fails if this path has no file name, or the
+    /// extension contains a separator.
+    pub fn set_extension<S: AsRef<OsStr> + ?Sized>(
+        &mut self,
+        extension: &S,
+    ) -> Result<(), InvalidFileName> {
+        let name = crate::file_name_with_extension(&self.0, extension.as_ref())?;
+        self.0.set_file_name(name);
+        Ok(())
+    }
+
     /// Like `Path::to_string_lossy()`, but returns an owned string.
     pub fn to_lossy_string(&self) -> String {
         self.0.to_string_lossy().to_string()
@@ -234,6 +374,11 @@ impl RelativePathBuf {
     pub fn ensure_parent_exists(&self) -> std::io::Result<()> {
         crate::create_parent_dir(self)
     }
+
+    /// The number of bytes allocated on the heap for this path.
+    pub fn heap_size(&self) -> usize {
+        self.0.capacity()
+    }
 }
 
 impl From<&RelativePath> for RelativePathBuf {
@@ -291,7 +436,7 @@ impl serde::Serialize for RelativePathBuf {
     where
         S: serde::Serializer,
     {
-        self.0.serialize(serializer)
+        crate::serde_path::serialize(&self.0, serializer)
     }
 }
 
@@ -302,7 +447,7 @@ impl<'de> serde::Deserialize<'de> for RelativePathBuf {
         D: serde::Deserializer<'de>,
     {
         use serde::de::Error;
-        let path = PathBuf::deserialize(deserializer)?;
+        let path = crate::serde_path::deserialize(deserializer)?;
         RelativePathBuf::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
     }
 }
@@ -317,7 +462,7 @@ where
         &'b self,
         out: &mut diesel::serialize::Output<'b, '_, DB>,
     ) -> diesel::serialize::Result {
-        self.0.to_str().expect("paths should be utf8").to_sql(out)
+        crate::path_to_str(&self.0)?.to_sql(out)
     }
 }
 
@@ -327,11 +472,327 @@ where
     DB: diesel::backend::Backend,
     String: diesel::deserialize::FromSql<diesel::sql_types::Text, DB>,
 {
-    fn from_sql(bytes: diesel::backend::RawValue<DB>) -> diesel::deserialize::Result<Self> {
+    fn from_sql(
+        bytes: <DB as diesel::backend::Backend>::RawValue<'_>,
+    ) -> diesel::deserialize::Result<Self> {
         String::from_sql(bytes).and_then(|s| Ok(RelativePathBuf::try_new(s)?))
     }
 }
 
+#[cfg(feature = "sqlx")]
+impl<DB> sqlx::Type<DB> for RelativePathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        String::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        String::compatible(ty)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'q, DB> sqlx::Encode<'q, DB> for RelativePathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        crate::path_to_str(&self.0)?.to_owned().encode(buf)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'r, DB> sqlx::Decode<'r, DB> for RelativePathBuf
+where
+    DB: sqlx::Database,
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(RelativePathBuf::try_new(String::decode(value)?)?)
+    }
+}
+
+/// Fails if the path is not UTF-8, as only strings can be stored.
+#[cfg(feature = "sea-orm")]
+impl TryFrom<RelativePathBuf> for sea_orm::sea_query::Value {
+    type Error = crate::NotUtf8;
+
+    fn try_from(path: RelativePathBuf) -> Result<Self, Self::Error> {
+        let path = crate::path_to_str(path.as_path())?.to_owned();
+        Ok(sea_orm::sea_query::Value::String(Some(Box::new(path))))
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::TryGetable for RelativePathBuf {
+    fn try_get_by<I: sea_orm::ColIdx>(
+        res: &sea_orm::QueryResult,
+        index: I,
+    ) -> Result<Self, sea_orm::TryGetError> {
+        RelativePathBuf::try_new(String::try_get_by(res, index)?)
+            .map_err(|e| sea_orm::TryGetError::DbErr(sea_orm::DbErr::Type(e.to_string())))
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::sea_query::ValueType for RelativePathBuf {
+    fn try_from(v: sea_orm::sea_query::Value) -> Result<Self, sea_orm::sea_query::ValueTypeErr> {
+        match v {
+            sea_orm::sea_query::Value::String(Some(path)) => {
+                RelativePathBuf::try_new(*path).map_err(|_| sea_orm::sea_query::ValueTypeErr)
+            }
+            _ => Err(sea_orm::sea_query::ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "RelativePathBuf".to_owned()
+    }
+
+    fn array_type() -> sea_orm::sea_query::ArrayType {
+        sea_orm::sea_query::ArrayType::String
+    }
+
+    fn column_type() -> sea_orm::sea_query::ColumnType {
+        sea_orm::sea_query::ColumnType::String(None)
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+impl sea_orm::sea_query::Nullable for RelativePathBuf {
+    fn null() -> sea_orm::sea_query::Value {
+        sea_orm::sea_query::Value::String(None)
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::ToSql for RelativePath {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        crate::path_to_str(&self.0)
+            .map(Into::into)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::ToSql for RelativePathBuf {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        crate::path_to_str(&self.0)
+            .map(Into::into)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl rusqlite::types::FromSql for RelativePathBuf {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        RelativePathBuf::try_new(String::column_result(value)?)
+            .map_err(|e| rusqlite::types::FromSqlError::Other(Box::new(e)))
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl postgres_types::ToSql for RelativePathBuf {
+    fn to_sql(
+        &self,
+        ty: &postgres_types::Type,
+        out: &mut bytes::BytesMut,
+    ) -> Result<postgres_types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        crate::path_to_str(&self.0)?.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &postgres_types::Type) -> bool {
+        <&str as postgres_types::ToSql>::accepts(ty)
+    }
+
+    postgres_types::to_sql_checked!();
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> postgres_types::FromSql<'a> for RelativePathBuf {
+    fn from_sql(
+        ty: &postgres_types::Type,
+        raw: &'a [u8],
+    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(RelativePathBuf::try_new(
+            <&str as postgres_types::FromSql>::from_sql(ty, raw)?,
+        )?)
+    }
+
+    fn accepts(ty: &postgres_types::Type) -> bool {
+        <&str as postgres_types::FromSql>::accepts(ty)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for RelativePathBuf {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "RelativePathBuf".to_owned()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("paths::RelativePathBuf")
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        crate::path_schema(
+            "relative-path",
+            "A relative path, e.g. `src/lib.rs`. `.` and `..` components are normalized away, except for leading `..` components.",
+        )
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for RelativePathBuf {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut path = PathBuf::new();
+        // Leading `..` components are kept when normalizing.
+        if u.ratio(1, 4)? {
+            for _ in 0..u.int_in_range(1..=2)? {
+                path.push("..");
+            }
+        }
+        path.extend(crate::fuzzing::components(u)?);
+        RelativePathBuf::try_new(path).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+/// An archived [`RelativePathBuf`], which can be used as a [`RelativePath`] without copying it.
+///
+/// When accessed with validation, e.g. by `rkyv::access`, the path is checked to be a valid
+/// [`RelativePath`].
+#[cfg(feature = "rkyv")]
+#[derive(rkyv::Portable, rkyv::bytecheck::CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck, verify)]
+#[repr(transparent)]
+pub struct ArchivedRelativePathBuf(rkyv::string::ArchivedString);
+
+#[cfg(feature = "rkyv")]
+impl ArchivedRelativePathBuf {
+    /// Get the archived path as a [`RelativePath`].
+    pub fn as_relative_path(&self) -> &RelativePath {
+        RelativePath::ref_cast(Path::new(self.0.as_str()))
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl std::fmt::Debug for ArchivedRelativePathBuf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_relative_path().fmt(f)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+unsafe impl<C> rkyv::bytecheck::Verify<C> for ArchivedRelativePathBuf
+where
+    C: rkyv::rancor::Fallible + ?Sized,
+    C::Error: rkyv::rancor::Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        RelativePath::try_new(self.0.as_str())
+            .map(|_| ())
+            .map_err(<C::Error as rkyv::rancor::Source>::new)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl rkyv::Archive for RelativePathBuf {
+    type Archived = ArchivedRelativePathBuf;
+    type Resolver = rkyv::string::StringResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        // SAFETY: `ArchivedRelativePathBuf` is a transparent wrapper around an `ArchivedString`.
+        let out = unsafe { out.cast_unchecked::<rkyv::string::ArchivedString>() };
+        rkyv::string::ArchivedString::resolve_from_str(
+            self.0
+                .to_str()
+                .expect("`serialize` checked that the path is utf8"),
+            resolver,
+            out,
+        );
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<S> rkyv::Serialize<S> for RelativePathBuf
+where
+    S: rkyv::rancor::Fallible + ?Sized,
+    S::Error: rkyv::rancor::Source,
+    str: rkyv::SerializeUnsized<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let path = crate::path_to_str(&self.0).map_err(<S::Error as rkyv::rancor::Source>::new)?;
+        rkyv::string::ArchivedString::serialize_from_str(path, serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<D> rkyv::Deserialize<RelativePathBuf, D> for ArchivedRelativePathBuf
+where
+    D: rkyv::rancor::Fallible + ?Sized,
+{
+    fn deserialize(&self, _: &mut D) -> Result<RelativePathBuf, D::Error> {
+        Ok(self.as_relative_path().into())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl RelativePath {
+    /// Get this path as a [`camino::Utf8Path`], or `None` if it is not valid UTF-8.
+    pub fn as_utf8(&self) -> Option<&camino::Utf8Path> {
+        camino::Utf8Path::from_path(&self.0)
+    }
+}
+
+#[cfg(feature = "camino")]
+impl<'a> TryFrom<&'a camino::Utf8Path> for &'a RelativePath {
+    type Error = NotRelative;
+
+    fn try_from(value: &'a camino::Utf8Path) -> Result<Self, Self::Error> {
+        RelativePath::try_new(value.as_std_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<&camino::Utf8Path> for RelativePathBuf {
+    type Error = NotRelative;
+
+    fn try_from(value: &camino::Utf8Path) -> Result<Self, Self::Error> {
+        RelativePathBuf::try_new(value.as_std_path())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<camino::Utf8PathBuf> for RelativePathBuf {
+    type Error = NotRelative;
+
+    fn try_from(value: camino::Utf8PathBuf) -> Result<Self, Self::Error> {
+        RelativePathBuf::try_new(value.into_std_path_buf())
+    }
+}
+
+#[cfg(feature = "camino")]
+impl TryFrom<RelativePathBuf> for camino::Utf8PathBuf {
+    type Error = camino::FromPathBufError;
+
+    fn try_from(value: RelativePathBuf) -> Result<Self, Self::Error> {
+        camino::Utf8PathBuf::try_from(value.0)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::path::Path;
@@ -440,6 +901,64 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn path_file_name() -> anyhow::Result<()> {
+        let path = RelativePath::try_new("foo/bar.txt")?;
+        assert_eq!(Some(RelativePath::try_new("bar.txt")?), path.file_name());
+        assert_eq!(None, RelativePath::try_new("foo/..")?.file_name());
+        assert_eq!(None, RelativePath::try_new("")?.file_name());
+        Ok(())
+    }
+
+    #[test]
+    fn path_buf_with_extension() -> anyhow::Result<()> {
+        let mut path = RelativePathBuf::try_new("docs/index.md")?;
+
+        assert_eq!(
+            RelativePathBuf::try_new("docs/index.html")?,
+            path.with_extension("html")?
+        );
+        assert_eq!(
+            RelativePathBuf::try_new("docs/README")?,
+            path.with_file_name("README")?
+        );
+        assert!(path.with_file_name("a/b").is_err());
+        assert!(RelativePath::new_unchecked("..")
+            .with_extension("md")
+            .is_err());
+
+        path.set_extension("html")?;
+        assert_eq!(RelativePathBuf::try_new("docs/index.html")?, path);
+        path.set_file_name("other.txt")?;
+        assert_eq!(RelativePathBuf::try_new("docs/other.txt")?, path);
+        assert!(path.set_extension("x/y").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn path_starts_and_ends_with() -> anyhow::Result<()> {
+        let path = RelativePath::try_new("foo/bar/baz")?;
+        assert!(path.starts_with(RelativePath::try_new("foo/bar")?));
+        assert!(!path.starts_with(RelativePath::try_new("fo")?));
+        assert!(path.ends_with(RelativePath::try_new("bar/baz")?));
+        assert!(!path.ends_with(RelativePath::try_new("az")?));
+        Ok(())
+    }
+
+    #[test]
+    fn path_ancestors() -> anyhow::Result<()> {
+        let path = RelativePath::try_new("a/b/c")?;
+        assert_eq!(Some(RelativePath::try_new("a/b")?), path.parent());
+        assert_eq!(
+            vec!["a/b/c", "a/b", "a", ""],
+            path.ancestors()
+                .map(|a| a.to_lossy_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, RelativePath::new_unchecked("").parent());
+        Ok(())
+    }
+
     #[test]
     fn path_buf_try_into_absolute() -> anyhow::Result<()> {
         let cwd = std::env::current_dir()?;
@@ -519,6 +1038,28 @@ mod test {
         assert!(relative_not_existing.is_dir());
         Ok(())
     }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn path_matches_regex() -> anyhow::Result<()> {
+        use crate::RegexAnchor;
+
+        let path = RelativePathBuf::try_new(Path::new("src").join("nested").join("lib.rs"))?;
+        let matches = |anchor: RegexAnchor, pattern: &str| -> anyhow::Result<bool> {
+            Ok(path.matches_regex(&anchor.compile(pattern)?))
+        };
+
+        assert!(matches(RegexAnchor::Unanchored, "nested/lib")?);
+        assert!(matches(RegexAnchor::Unanchored, r"\.rs$")?);
+        assert!(!matches(RegexAnchor::Unanchored, r"\.toml$")?);
+        assert!(matches(RegexAnchor::Start, "src/")?);
+        assert!(!matches(RegexAnchor::Start, "nested/")?);
+        assert!(matches(RegexAnchor::Full, r"src/.*\.rs")?);
+        assert!(!matches(RegexAnchor::Full, "src/nested")?);
+        assert!(matches(RegexAnchor::Full, "a|src/nested/lib.rs")?);
+        assert!(RegexAnchor::Full.compile("(").is_err());
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "serde"))]
@@ -554,6 +1095,23 @@ mod serde_tests {
         assert!(serde_json::from_str::<RelativePathBuf>(&serialized_absolute).is_err());
         Ok(())
     }
+
+    #[test]
+    fn path_deserializes_borrowed() -> anyhow::Result<()> {
+        let cwd = std::env::current_dir()?;
+        let serialized_absolute = format!("\"{}\"", cwd.display());
+
+        let paths: Vec<&RelativePath> = serde_json::from_str("[\"foo/bar\", \"../baz\"]")?;
+        assert_eq!(
+            vec![
+                RelativePath::try_new("foo/bar")?,
+                RelativePath::try_new("../baz")?
+            ],
+            paths
+        );
+        assert!(serde_json::from_str::<&RelativePath>(&serialized_absolute).is_err());
+        Ok(())
+    }
 }
 
 #[cfg(all(test, feature = "diesel"))]
@@ -710,4 +1268,351 @@ mod test_diesel {
 
         Ok(())
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut connection = create_table()?;
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let file = TestFile {
+            id: 1,
+            x: RelativePathBuf::try_new(name)?,
+            y: None,
+        };
+        assert!(
+            diesel::insert_into(crate::diesel_helpers::schema::test_files::table)
+                .values(&file)
+                .execute(&mut connection)
+                .is_err()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sqlx-sqlite", feature = "sqlx-runtime-tokio"))]
+mod test_sqlx {
+    use crate::block_on;
+    use crate::sqlx_helpers::create_table;
+    use crate::AbsolutePathBuf;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        block_on(async {
+            let mut connection = create_table().await?;
+            let x = RelativePathBuf::try_new("foo/bar.txt")?;
+            let y = RelativePathBuf::try_new("bar/baz.txt")?;
+            for (id, y) in [(1, None), (2, Some(&y))] {
+                sqlx::query("INSERT INTO test_files (id, x, y) VALUES (?, ?, ?)")
+                    .bind(id)
+                    .bind(&x)
+                    .bind(y)
+                    .execute(&mut connection)
+                    .await?;
+            }
+            sqlx::query("INSERT INTO test_files (id, x, y) VALUES (3, ?, NULL)")
+                .bind(AbsolutePathBuf::current_dir().display().to_string())
+                .execute(&mut connection)
+                .await?;
+
+            let rows: Vec<(RelativePathBuf, Option<RelativePathBuf>)> =
+                sqlx::query_as("SELECT x, y FROM test_files WHERE id < 3 ORDER BY id")
+                    .fetch_all(&mut connection)
+                    .await?;
+            assert_eq!(vec![(x.clone(), None), (x, Some(y))], rows);
+            assert!(sqlx::query_as::<_, (RelativePathBuf,)>(
+                "SELECT x FROM test_files WHERE id = 3"
+            )
+            .fetch_one(&mut connection)
+            .await
+            .is_err());
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        block_on(async {
+            let mut connection = create_table().await?;
+            let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+            let path = RelativePathBuf::try_new(name)?;
+            assert!(sqlx::query("INSERT INTO test_files (id, x) VALUES (1, ?)")
+                .bind(&path)
+                .execute(&mut connection)
+                .await
+                .is_err());
+            Ok(())
+        })
+    }
+}
+
+#[cfg(all(test, feature = "sea-orm"))]
+mod test_sea_orm {
+    use std::collections::BTreeMap;
+
+    use sea_orm::sea_query::Value;
+    use sea_orm::sea_query::ValueType;
+    use sea_orm::ConnectionTrait;
+    use sea_orm::DbBackend;
+    use sea_orm::MockDatabase;
+    use sea_orm::Statement;
+
+    use crate::block_on;
+    use crate::AbsolutePathBuf;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = RelativePathBuf::try_new("foo/bar.txt")?;
+        let value = Value::try_from(path.clone())?;
+        assert_eq!(Value::from(path.display().to_string()), value);
+        assert_eq!(
+            Some(path.clone()),
+            <RelativePathBuf as ValueType>::try_from(value.clone()).ok()
+        );
+        assert!(<RelativePathBuf as ValueType>::try_from(Value::from(
+            AbsolutePathBuf::current_dir().display().to_string()
+        ))
+        .is_err());
+
+        block_on(async {
+            let db = MockDatabase::new(DbBackend::Sqlite)
+                .append_query_results([[
+                    BTreeMap::from([("x", value.clone()), ("y", Value::String(None))]),
+                    BTreeMap::from([
+                        (
+                            "x",
+                            Value::from(AbsolutePathBuf::current_dir().display().to_string()),
+                        ),
+                        ("y", value),
+                    ]),
+                ]])
+                .into_connection();
+            let rows = db
+                .query_all(Statement::from_string(
+                    DbBackend::Sqlite,
+                    "SELECT x, y FROM test_files".to_owned(),
+                ))
+                .await?;
+
+            assert_eq!(path, rows[0].try_get::<RelativePathBuf>("", "x")?);
+            assert_eq!(None, rows[0].try_get::<Option<RelativePathBuf>>("", "y")?);
+            assert!(rows[1].try_get::<RelativePathBuf>("", "x").is_err());
+            assert_eq!(
+                Some(path),
+                rows[1].try_get::<Option<RelativePathBuf>>("", "y")?
+            );
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = RelativePathBuf::try_new(name)?;
+        assert!(Value::try_from(path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rusqlite"))]
+mod test_rusqlite {
+    use rusqlite::params;
+
+    use crate::AbsolutePathBuf;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let connection = rusqlite::Connection::open_in_memory()?;
+        connection.execute(
+            "CREATE TABLE test_files (id PRIMARY KEY NOT NULL, x TEXT NOT NULL, y TEXT NULL)",
+            [],
+        )?;
+        let x = RelativePathBuf::try_new("foo/bar.txt")?;
+        let y = RelativePathBuf::try_new("bar/baz.txt")?;
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (?1, ?2, ?3)",
+            params![1, x.as_relative_path(), None::<RelativePathBuf>],
+        )?;
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (?1, ?2, ?3)",
+            params![2, x, y],
+        )?;
+
+        let mut statement = connection.prepare("SELECT x, y FROM test_files ORDER BY id")?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(RelativePathBuf, Option<RelativePathBuf>)>>>()?;
+        assert_eq!(vec![(x.clone(), None), (x, Some(y))], rows);
+
+        connection.execute(
+            "INSERT INTO test_files (id, x, y) VALUES (3, ?1, NULL)",
+            [AbsolutePathBuf::current_dir().display().to_string()],
+        )?;
+        assert!(connection
+            .query_row("SELECT x FROM test_files WHERE id = 3", [], |row| {
+                row.get::<_, RelativePathBuf>(0)
+            })
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        use rusqlite::ToSql;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = RelativePathBuf::try_new(name)?;
+        assert!(matches!(
+            path.to_sql(),
+            Err(rusqlite::Error::ToSqlConversionFailure(_))
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "postgres"))]
+mod test_postgres {
+    use bytes::BytesMut;
+    use postgres_types::FromSql;
+    use postgres_types::ToSql;
+    use postgres_types::Type;
+
+    use crate::AbsolutePathBuf;
+    use crate::NotUtf8;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = RelativePathBuf::try_new("foo/bar.txt")?;
+        let mut buf = BytesMut::new();
+        path.to_sql_checked(&Type::TEXT, &mut buf).unwrap();
+        assert_eq!(path, RelativePathBuf::from_sql(&Type::TEXT, &buf).unwrap());
+        assert!(RelativePathBuf::from_sql(
+            &Type::TEXT,
+            AbsolutePathBuf::current_dir()
+                .display()
+                .to_string()
+                .as_bytes()
+        )
+        .is_err());
+        assert!(<RelativePathBuf as FromSql>::accepts(&Type::VARCHAR));
+        assert!(path.to_sql_checked(&Type::INT4, &mut buf).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = RelativePathBuf::try_new(name)?;
+        assert!(matches!(
+            path.to_sql_checked(&Type::TEXT, &mut BytesMut::new()),
+            Err(e) if e.is::<NotUtf8>()
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "schemars"))]
+mod test_schemars {
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn path_buf_schema() -> anyhow::Result<()> {
+        let schema = serde_json::to_value(schemars::schema_for!(RelativePathBuf))?;
+        assert_eq!("string", schema["type"]);
+        assert_eq!("relative-path", schema["format"]);
+        assert!(schema["description"].is_string());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod test_arbitrary {
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn generates_valid_paths() -> anyhow::Result<()> {
+        let paths: Vec<RelativePathBuf> = crate::fuzzing::generate(200)?;
+        for path in &paths {
+            assert_eq!(path, &RelativePathBuf::try_new(path.as_path())?);
+        }
+        assert!(paths.iter().any(|p| p.as_path().components().count() > 2));
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "rkyv"))]
+mod test_rkyv {
+    use rkyv::rancor::Error;
+
+    use crate::AbsolutePathBuf;
+    use crate::ArchivedRelativePathBuf;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn path_buf_round_trips() -> anyhow::Result<()> {
+        let path = RelativePathBuf::try_new("foo/bar.txt")?;
+        let bytes = rkyv::to_bytes::<Error>(&path)?;
+        let archived = rkyv::access::<ArchivedRelativePathBuf, Error>(&bytes)?;
+        assert_eq!(&*path, archived.as_relative_path());
+        assert_eq!(path, rkyv::deserialize::<RelativePathBuf, Error>(archived)?);
+
+        let invalid =
+            rkyv::to_bytes::<Error>(&AbsolutePathBuf::current_dir().to_lossy_string().to_owned())?;
+        assert!(rkyv::access::<ArchivedRelativePathBuf, Error>(&invalid).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"invalid\xff.txt");
+        let path = RelativePathBuf::try_new(name)?;
+        assert!(rkyv::to_bytes::<Error>(&path).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "camino"))]
+mod test_camino {
+    use camino::Utf8Path;
+    use camino::Utf8PathBuf;
+
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn converts_utf8_paths() -> anyhow::Result<()> {
+        let path = RelativePathBuf::try_new("foo/bar.txt")?;
+        let utf8 = path.as_utf8().expect("the path is utf8");
+        assert_eq!(Utf8Path::new("foo/bar.txt"), utf8);
+
+        assert_eq!(path.as_relative_path(), <&RelativePath>::try_from(utf8)?);
+        assert_eq!(path, RelativePathBuf::try_from(utf8)?);
+        assert_eq!(path, RelativePathBuf::try_from(utf8.to_path_buf())?);
+        assert_eq!(utf8, Utf8PathBuf::try_from(path.clone())?);
+
+        let cwd = Utf8PathBuf::try_from(std::env::current_dir()?)?;
+        assert!(<&RelativePath>::try_from(cwd.as_path()).is_err());
+        assert!(RelativePathBuf::try_from(cwd).is_err());
+        Ok(())
+    }
 }
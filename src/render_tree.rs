@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::Component;
+
+use crate::RelativePath;
+
+/// Render `paths` as a tree, like the output of `tree --charset=ascii`, e.g. for a CLI summary
+/// of the files that a command changed.
+///
+/// ```
+/// use paths::RelativePath;
+///
+/// let paths = ["src/main.rs", "Cargo.toml", "src/lib.rs"].map(RelativePath::new_unchecked);
+/// assert_eq!(
+///     ".\n|-- Cargo.toml\n`-- src\n    |-- lib.rs\n    `-- main.rs\n",
+///     paths::render_tree(paths),
+/// );
+/// ```
+///
+/// Entries are sorted by name, and duplicate paths and `.` components are ignored. `..`
+/// components are normalized away, so `a/../b` is rendered as `b`. Paths that still start with
+/// `..` afterwards are outside of `.`, and are rendered beneath a `..` entry. Names that are not
+/// valid UTF-8 are rendered lossily.
+pub fn render_tree<'a>(paths: impl IntoIterator<Item = &'a RelativePath>) -> String {
+    render(paths, None)
+}
+
+/// Like [`render_tree`], but only renders entries up to `max_depth` components deep, like
+/// `tree -L`. Anything deeper is left out, so e.g. a `max_depth` of `1` only renders the top
+/// level entries.
+pub fn render_tree_with_max_depth<'a>(
+    paths: impl IntoIterator<Item = &'a RelativePath>,
+    max_depth: usize,
+) -> String {
+    render(paths, Some(max_depth))
+}
+
+#[derive(Default)]
+struct Node<'a> {
+    children: BTreeMap<&'a OsStr, Node<'a>>,
+}
+
+fn render<'a>(
+    paths: impl IntoIterator<Item = &'a RelativePath>,
+    max_depth: Option<usize>,
+) -> String {
+    let mut root = Node::default();
+    for path in paths {
+        let mut names: Vec<&OsStr> = vec![];
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir if names.last().is_some_and(|n| *n != "..") => {
+                    names.pop();
+                }
+                component => names.push(component.as_os_str()),
+            }
+        }
+        let mut node = &mut root;
+        for name in names {
+            node = node.children.entry(name).or_default();
+        }
+    }
+
+    let mut out = ".\n".to_owned();
+    render_children(&root, &mut String::new(), max_depth, &mut out);
+    out
+}
+
+/// Render `node`'s children, with each line starting with `prefix`.
+fn render_children(node: &Node, prefix: &mut String, max_depth: Option<usize>, out: &mut String) {
+    if max_depth == Some(0) {
+        return;
+    }
+    let mut children = node.children.iter().peekable();
+    while let Some((name, child)) = children.next() {
+        let last = children.peek().is_none();
+        out.push_str(prefix);
+        out.push_str(if last { "`-- " } else { "|-- " });
+        out.push_str(&name.to_string_lossy());
+        out.push('\n');
+
+        let len = prefix.len();
+        prefix.push_str(if last { "    " } else { "|   " });
+        render_children(child, prefix, max_depth.map(|d| d - 1), out);
+        prefix.truncate(len);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::render_tree;
+    use crate::render_tree_with_max_depth;
+    use crate::RelativePath;
+
+    #[test]
+    fn renders_trees() {
+        let paths = [
+            "src/lib.rs",
+            "README.md",
+            "./src/bin/main.rs",
+            "src/lib.rs",
+            "tests/fixtures/a.txt",
+            "src",
+        ]
+        .map(RelativePath::new_unchecked);
+
+        assert_eq!(
+            concat!(
+                ".\n",
+                "|-- README.md\n",
+                "|-- src\n",
+                "|   |-- bin\n",
+                "|   |   `-- main.rs\n",
+                "|   `-- lib.rs\n",
+                "`-- tests\n",
+                "    `-- fixtures\n",
+                "        `-- a.txt\n",
+            ),
+            render_tree(paths)
+        );
+        assert_eq!(
+            concat!(
+                ".\n",
+                "|-- README.md\n",
+                "|-- src\n",
+                "|   |-- bin\n",
+                "|   `-- lib.rs\n",
+                "`-- tests\n",
+                "    `-- fixtures\n",
+            ),
+            render_tree_with_max_depth(paths, 2)
+        );
+        assert_eq!(".\n", render_tree_with_max_depth(paths, 0));
+        assert_eq!(".\n", render_tree([RelativePath::new_unchecked("")]));
+    }
+
+    #[test]
+    fn normalizes_parent_components() {
+        let paths =
+            ["a/../b/c.txt", "b/./d/..", "../x", "a/../../y"].map(RelativePath::new_unchecked);
+
+        assert_eq!(
+            concat!(
+                ".\n",
+                "|-- ..\n",
+                "|   |-- x\n",
+                "|   `-- y\n",
+                "`-- b\n",
+                "    `-- c.txt\n",
+            ),
+            render_tree(paths)
+        );
+    }
+}
@@ -20,7 +20,7 @@ impl ResolvedAbsolutePathBuf {
     ///
     /// This will fail if the provided path is relative, or if, when normalizing, the path would
     /// traverse beyond the root of the filesystem.
-    pub fn try_new<P: Into<PathBuf> + ?Sized>(path: P) -> Result<Self, AbsolutePathBufNewError> {
+    pub fn try_new<P: Into<PathBuf>>(path: P) -> Result<Self, AbsolutePathBufNewError> {
         let combined = CombinedPathBuf::try_new(path)?;
 
         Ok(Self(combined.try_into_absolute_in_cwd()?))
@@ -30,7 +30,7 @@ impl ResolvedAbsolutePathBuf {
     ///
     /// This is mostly used for paths that are known ahead of time (e.g. static strings) to be
     /// valid.
-    pub fn new_unchecked<P: Into<PathBuf> + ?Sized>(path: P) -> Self {
+    pub fn new_unchecked<P: Into<PathBuf>>(path: P) -> Self {
         Self::try_new(path).expect("an absolute path")
     }
 
@@ -92,7 +92,7 @@ impl<'de> serde::Deserialize<'de> for ResolvedAbsolutePathBuf {
         D: serde::Deserializer<'de>,
     {
         use serde::de::Error;
-        let path = PathBuf::deserialize(deserializer)?;
+        let path = crate::serde_path::deserialize(deserializer)?;
         ResolvedAbsolutePathBuf::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
     }
 }
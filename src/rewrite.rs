@@ -0,0 +1,330 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::GlobPattern;
+use crate::InvalidRewriteRule;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// An ordered list of rules that map relative paths to new relative paths, e.g. to decide where
+/// the output for each input of an asset pipeline should go.
+///
+/// Each rule matches either a path prefix or a glob pattern (see [`crate::glob`]), and has a
+/// template for the new path. The first rule that matches a path is used. Templates may contain
+/// these placeholders:
+/// - `{path}`: The whole path.
+/// - `{rest}`: The part of the path after the prefix, or the whole path for glob rules.
+/// - `{dir}`: The path's parent directory.
+/// - `{name}`, `{stem}`, `{ext}`: The path's file name, and that name without or with only its
+///   extension.
+///
+/// `{{` and `}}` produce literal braces.
+///
+/// With the `serde` feature, rules serialize as a list of
+/// `{"prefix": "...", "to": "..."}` or `{"glob": "...", "to": "..."}` objects.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct RewriteRules {
+    rules: Vec<RewriteRule>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawRewriteRule", into = "RawRewriteRule")
+)]
+struct RewriteRule {
+    matcher: Matcher,
+    template: String,
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Matcher {
+    Prefix(RelativePathBuf),
+    Glob(String, GlobPattern),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Placeholder {
+    Path,
+    Rest,
+    Dir,
+    Name,
+    Stem,
+    Ext,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum TemplatePart {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+impl RewriteRules {
+    /// Create an empty set of rules, which matches no paths.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule for paths that start with `prefix`.
+    pub fn prefix(
+        mut self,
+        prefix: &RelativePath,
+        template: &str,
+    ) -> Result<Self, InvalidRewriteRule> {
+        self.rules
+            .push(RewriteRule::new(Matcher::Prefix(prefix.into()), template)?);
+        Ok(self)
+    }
+
+    /// Add a rule for paths that match the glob `pattern`.
+    pub fn glob(mut self, pattern: &str, template: &str) -> Result<Self, InvalidRewriteRule> {
+        let compiled =
+            GlobPattern::new(pattern).map_err(|e| InvalidRewriteRule(pattern.to_owned(), e.1))?;
+        self.rules.push(RewriteRule::new(
+            Matcher::Glob(pattern.to_owned(), compiled),
+            template,
+        )?);
+        Ok(self)
+    }
+
+    /// Rewrite `path` with the first rule that matches it, or `None` if no rules match.
+    pub fn apply(&self, path: &RelativePath) -> Option<RelativePathBuf> {
+        self.rules.iter().find_map(|rule| rule.apply(path))
+    }
+}
+
+impl RewriteRule {
+    fn new(matcher: Matcher, template: &str) -> Result<Self, InvalidRewriteRule> {
+        let invalid = |reason: &str| InvalidRewriteRule(template.to_owned(), reason.to_owned());
+        if template.starts_with('/') || Path::new(template).is_absolute() {
+            return Err(invalid("templates must be relative"));
+        }
+
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(invalid("unclosed placeholder")),
+                        }
+                    }
+                    let placeholder = match name.as_str() {
+                        "path" => Placeholder::Path,
+                        "rest" => Placeholder::Rest,
+                        "dir" => Placeholder::Dir,
+                        "name" => Placeholder::Name,
+                        "stem" => Placeholder::Stem,
+                        "ext" => Placeholder::Ext,
+                        _ => return Err(invalid("unknown placeholder")),
+                    };
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Placeholder(placeholder));
+                }
+                '}' => return Err(invalid("unmatched `}`")),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+
+        Ok(Self {
+            matcher,
+            template: template.to_owned(),
+            parts,
+        })
+    }
+
+    fn apply(&self, path: &RelativePath) -> Option<RelativePathBuf> {
+        let rest = match &self.matcher {
+            Matcher::Prefix(prefix) => path.strip_prefix(prefix).ok()?,
+            Matcher::Glob(_, pattern) if pattern.matches_relative(path) => path.as_path(),
+            Matcher::Glob(..) => return None,
+        };
+
+        let lossy = |p: Option<&std::ffi::OsStr>| {
+            p.map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => rendered.push_str(literal),
+                TemplatePart::Placeholder(placeholder) => rendered.push_str(&match placeholder {
+                    Placeholder::Path => path.to_lossy_string(),
+                    Placeholder::Rest => rest.to_string_lossy().to_string(),
+                    Placeholder::Dir => lossy(path.parent().map(|p| p.as_os_str())),
+                    Placeholder::Name => lossy(path.file_name().map(|n| n.as_os_str())),
+                    Placeholder::Stem => lossy(path.file_stem()),
+                    Placeholder::Ext => lossy(path.extension()),
+                }),
+            }
+        }
+        // Drop the empty components left by empty placeholders, e.g. `out/{dir}/{name}`.
+        let rewritten: PathBuf = Path::new(&rendered).components().collect();
+        RelativePathBuf::try_new(rewritten).ok()
+    }
+}
+
+/// The serialized form of a [`RewriteRule`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRewriteRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<RelativePathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    glob: Option<String>,
+    to: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawRewriteRule> for RewriteRule {
+    type Error = InvalidRewriteRule;
+
+    fn try_from(raw: RawRewriteRule) -> Result<Self, Self::Error> {
+        match (raw.prefix, raw.glob) {
+            (Some(prefix), None) => RewriteRule::new(Matcher::Prefix(prefix), &raw.to),
+            (None, Some(glob)) => {
+                let compiled =
+                    GlobPattern::new(&glob).map_err(|e| InvalidRewriteRule(glob.clone(), e.1))?;
+                RewriteRule::new(Matcher::Glob(glob, compiled), &raw.to)
+            }
+            _ => Err(InvalidRewriteRule(
+                raw.to,
+                "exactly one of `prefix` or `glob` must be set".to_owned(),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<RewriteRule> for RawRewriteRule {
+    fn from(rule: RewriteRule) -> Self {
+        let (prefix, glob) = match rule.matcher {
+            Matcher::Prefix(prefix) => (Some(prefix), None),
+            Matcher::Glob(glob, _) => (None, Some(glob)),
+        };
+        Self {
+            prefix,
+            glob,
+            to: rule.template,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+    use crate::RewriteRules;
+
+    #[test]
+    fn rewrites_paths() -> anyhow::Result<()> {
+        let rules = RewriteRules::new()
+            .prefix(RelativePath::try_new("assets/static")?, "public/{rest}")?
+            .glob("**/*.scss", "css/{stem}.css")?
+            .glob("**/*.{png,jpg}", "img/{dir}/{name}")?
+            .glob("**/*.md", "docs/{{{ext}}}/{path}")?;
+        let apply = |path: &str| -> anyhow::Result<Option<RelativePathBuf>> {
+            Ok(rules.apply(RelativePath::try_new(path)?))
+        };
+        let expected = |path: &str| -> anyhow::Result<Option<RelativePathBuf>> {
+            Ok(Some(RelativePathBuf::try_new(path)?))
+        };
+
+        assert_eq!(
+            expected("public/fonts/a.woff")?,
+            apply("assets/static/fonts/a.woff")?
+        );
+        // The prefix rule comes first.
+        assert_eq!(
+            expected("public/site.scss")?,
+            apply("assets/static/site.scss")?
+        );
+        assert_eq!(expected("css/site.css")?, apply("styles/site.scss")?);
+        assert_eq!(expected("img/a/b/logo.png")?, apply("a/b/logo.png")?);
+        assert_eq!(expected("img/logo.jpg")?, apply("logo.jpg")?);
+        assert_eq!(expected("docs/{md}/README.md")?, apply("README.md")?);
+        assert_eq!(None, apply("assets/statics/foo.txt")?);
+        assert_eq!(None, apply("src/lib.rs")?);
+        assert_eq!(
+            None,
+            RewriteRules::new().apply(RelativePath::try_new("foo")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_rules() -> anyhow::Result<()> {
+        let prefix = RelativePath::try_new("foo")?;
+        assert!(RewriteRules::new().prefix(prefix, "out/{unknown}").is_err());
+        assert!(RewriteRules::new().prefix(prefix, "out/{rest").is_err());
+        assert!(RewriteRules::new().prefix(prefix, "out/rest}").is_err());
+        assert!(RewriteRules::new().prefix(prefix, "/out/{rest}").is_err());
+        assert!(RewriteRules::new().glob("[abc", "out/{rest}").is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+    use crate::RewriteRules;
+
+    #[test]
+    fn rules_round_trip() -> anyhow::Result<()> {
+        let serialized = r#"[{"prefix":"assets","to":"public/{rest}"},{"glob":"**/*.scss","to":"css/{stem}.css"}]"#;
+        let rules = serde_json::from_str::<RewriteRules>(serialized)?;
+
+        assert_eq!(
+            RewriteRules::new()
+                .prefix(RelativePath::try_new("assets")?, "public/{rest}")?
+                .glob("**/*.scss", "css/{stem}.css")?,
+            rules
+        );
+        assert_eq!(serialized, serde_json::to_string(&rules)?);
+        assert_eq!(
+            Some(RelativePathBuf::try_new("css/site.css")?),
+            rules.apply(RelativePath::try_new("site.scss")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for invalid in [
+            r#"[{"to":"out"}]"#,
+            r#"[{"prefix":"a","glob":"*","to":"out"}]"#,
+            r#"[{"glob":"[a","to":"out"}]"#,
+            r#"[{"prefix":"/abs","to":"out"}]"#,
+            r#"[{"prefix":"a","to":"{bad}"}]"#,
+        ] {
+            assert!(serde_json::from_str::<RewriteRules>(invalid).is_err());
+        }
+    }
+}
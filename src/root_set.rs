@@ -0,0 +1,155 @@
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::OverlappingRoots;
+use crate::RelativePathBuf;
+
+/// A set of root directories that do not overlap, like the folders of a multi-root workspace.
+///
+/// Because no root is inside of another, every path belongs to at most one root.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct RootSet {
+    /// Kept sorted.
+    roots: Vec<AbsolutePathBuf>,
+}
+
+impl RootSet {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `root` to the set.
+    ///
+    /// Fails if `root` is already in the set, or is inside of or contains an existing root.
+    pub fn insert(&mut self, root: AbsolutePathBuf) -> Result<(), OverlappingRoots> {
+        let idx = match self.roots.binary_search(&root) {
+            Ok(idx) => return Err(overlap(&root, &self.roots[idx])),
+            Err(idx) => idx,
+        };
+        // Sorting puts any root that contains `root` before it, and any that `root` contains
+        // directly after it.
+        if let Some(existing) = self.roots[..idx].iter().find(|r| root.starts_with(r)) {
+            return Err(overlap(&root, existing));
+        }
+        if let Some(existing) = self.roots.get(idx).filter(|r| r.starts_with(&root)) {
+            return Err(overlap(&root, existing));
+        }
+        self.roots.insert(idx, root);
+        Ok(())
+    }
+
+    /// Remove `root` from the set, returning whether it was present.
+    pub fn remove(&mut self, root: &AbsolutePath) -> bool {
+        match self
+            .roots
+            .binary_search_by(|r| r.as_absolute_path().cmp(root))
+        {
+            Ok(idx) => {
+                self.roots.remove(idx);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// An estimate of the number of bytes allocated on the heap for the set.
+    pub fn heap_size(&self) -> usize {
+        self.roots.capacity() * std::mem::size_of::<AbsolutePathBuf>()
+            + self.roots.iter().map(|r| r.heap_size()).sum::<usize>()
+    }
+
+    /// Find the root that contains `path` (which may be the root itself), and the path relative
+    /// to it.
+    pub fn owning_root(&self, path: &AbsolutePath) -> Option<(&AbsolutePath, RelativePathBuf)> {
+        self.roots.iter().find_map(|root| {
+            path.strip_prefix(root)
+                .ok()
+                .map(|rel| (root.as_absolute_path(), rel.into()))
+        })
+    }
+
+    /// The roots, in sorted order.
+    pub fn iter(&self) -> impl Iterator<Item = &AbsolutePath> {
+        self.roots.iter().map(|r| r.as_absolute_path())
+    }
+
+    /// The number of roots.
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Whether there are no roots.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+}
+
+fn overlap(root: &AbsolutePath, existing: &AbsolutePath) -> OverlappingRoots {
+    OverlappingRoots(
+        root.as_path().display().to_string(),
+        existing.as_path().display().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::OverlappingRoots;
+    use crate::RelativePathBuf;
+    use crate::RootSet;
+
+    #[test]
+    fn finds_owning_roots() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let mut roots = RootSet::new();
+        roots.insert(cwd.join("web")?)?;
+        roots.insert(cwd.join("api")?)?;
+        roots.insert(cwd.join("web-extra")?)?;
+
+        let overlap = |root: &str, existing: &str| -> anyhow::Result<OverlappingRoots> {
+            Ok(OverlappingRoots(
+                cwd.join(root)?.as_path().display().to_string(),
+                cwd.join(existing)?.as_path().display().to_string(),
+            ))
+        };
+        assert_eq!(
+            overlap("web/src", "web")?,
+            roots.insert(cwd.join("web/src")?).unwrap_err()
+        );
+        assert_eq!(
+            overlap("api", "api")?,
+            roots.insert(cwd.join("api")?).unwrap_err()
+        );
+        assert_eq!(
+            OverlappingRoots(
+                cwd.as_path().display().to_string(),
+                cwd.join("api")?.as_path().display().to_string()
+            ),
+            roots.insert(cwd.clone()).unwrap_err()
+        );
+        assert_eq!(
+            vec![cwd.join("api")?, cwd.join("web")?, cwd.join("web-extra")?],
+            roots
+                .iter()
+                .map(|r| r.into())
+                .collect::<Vec<AbsolutePathBuf>>()
+        );
+
+        let (root, relative) = roots
+            .owning_root(&cwd.join("web/src/index.ts")?)
+            .expect("a root");
+        assert_eq!(cwd.join("web")?.as_absolute_path(), root);
+        assert_eq!(RelativePathBuf::try_new("src/index.ts")?, relative);
+        let (root, relative) = roots.owning_root(&cwd.join("web-extra")?).expect("a root");
+        assert_eq!(cwd.join("web-extra")?.as_absolute_path(), root);
+        assert_eq!(RelativePathBuf::try_new("")?, relative);
+        assert_eq!(None, roots.owning_root(&cwd.join("other/file")?));
+
+        assert!(roots.remove(&cwd.join("web")?));
+        assert!(!roots.remove(&cwd.join("web")?));
+        assert_eq!(None, roots.owning_root(&cwd.join("web/src/index.ts")?));
+        roots.insert(cwd.join("web/src")?)?;
+        assert_eq!(3, roots.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::GlobPattern;
+use crate::InvalidGlobPattern;
+
+/// Dispatches paths (e.g. of changed files) to the subscribers that are interested in them, such
+/// as the build targets that need to be rebuilt.
+///
+/// Subscribers register either a directory prefix, which matches everything beneath it, or a
+/// glob relative to a root directory, using the syntax of [`crate::glob`].
+#[derive(Debug, Clone)]
+pub struct PathRouter<T> {
+    prefixes: BTreeMap<AbsolutePathBuf, Vec<T>>,
+    globs: Vec<(AbsolutePathBuf, GlobPattern, T)>,
+}
+
+impl<T> Default for PathRouter<T> {
+    fn default() -> Self {
+        Self {
+            prefixes: BTreeMap::new(),
+            globs: vec![],
+        }
+    }
+}
+
+impl<T> PathRouter<T> {
+    /// Create a router with no subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route `prefix`, and every path beneath it, to `subscriber`.
+    pub fn subscribe_prefix(&mut self, prefix: AbsolutePathBuf, subscriber: T) {
+        self.prefixes.entry(prefix).or_default().push(subscriber);
+    }
+
+    /// Route paths beneath `root` that match `pattern` (relative to `root`) to `subscriber`.
+    pub fn subscribe_glob(
+        &mut self,
+        root: AbsolutePathBuf,
+        pattern: &str,
+        subscriber: T,
+    ) -> Result<(), InvalidGlobPattern> {
+        self.globs
+            .push((root, GlobPattern::new(pattern)?, subscriber));
+        Ok(())
+    }
+
+    /// Every subscriber interested in `path`.
+    ///
+    /// Prefix subscribers come first, from the longest (most specific) prefix to the shortest,
+    /// followed by glob subscribers in the order they subscribed.
+    pub fn route(&self, path: &AbsolutePath) -> Vec<&T> {
+        let mut subscribers: Vec<&T> = self.prefix_matches(path).into_iter().flatten().collect();
+        subscribers.extend(
+            self.globs
+                .iter()
+                .filter(|(root, pattern, _)| match path.strip_prefix(root) {
+                    Ok(rest) => pattern.matches_relative(rest),
+                    Err(_) => false,
+                })
+                .map(|(_, _, subscriber)| subscriber),
+        );
+        subscribers
+    }
+
+    /// The subscribers with the longest prefix containing `path`, ignoring globs.
+    ///
+    /// This is useful when nested prefixes override their parents, e.g. a package inside of
+    /// another package.
+    pub fn most_specific(&self, path: &AbsolutePath) -> &[T] {
+        self.prefix_matches(path).first().copied().unwrap_or(&[])
+    }
+
+    /// The subscribers for each prefix of `path`, longest first.
+    fn prefix_matches(&self, path: &AbsolutePath) -> Vec<&[T]> {
+        path.ancestors()
+            .filter_map(|ancestor| self.prefixes.get(ancestor).map(|s| s.as_slice()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::PathRouter;
+
+    #[test]
+    fn routes_paths() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let mut router = PathRouter::new();
+        router.subscribe_prefix(cwd.join("app")?, "app");
+        router.subscribe_prefix(cwd.join("app/vendor/lib")?, "lib");
+        router.subscribe_prefix(cwd.join("app/vendor/lib")?, "lib-docs");
+        router.subscribe_glob(cwd.clone(), "**/*.proto", "protos")?;
+        router.subscribe_glob(cwd.join("app")?, "*.toml", "config")?;
+        assert!(router.subscribe_glob(cwd.clone(), "[", "invalid").is_err());
+
+        assert_eq!(
+            vec![&"lib", &"lib-docs", &"app", &"protos"],
+            router.route(&cwd.join("app/vendor/lib/api.proto")?)
+        );
+        assert_eq!(
+            vec![&"app", &"config"],
+            router.route(&cwd.join("app/Cargo.toml")?)
+        );
+        assert_eq!(vec![&"app"], router.route(&cwd.join("app/src/Cargo.toml")?));
+        assert_eq!(vec![&"protos"], router.route(&cwd.join("other/a.proto")?));
+        assert!(router.route(&cwd.join("other/a.rs")?).is_empty());
+
+        assert_eq!(
+            &["lib", "lib-docs"],
+            router.most_specific(&cwd.join("app/vendor/lib/api.proto")?)
+        );
+        assert_eq!(&["app"], router.most_specific(&cwd.join("app")?));
+        assert!(router.most_specific(&cwd.join("other")?).is_empty());
+        Ok(())
+    }
+}
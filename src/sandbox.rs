@@ -0,0 +1,176 @@
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::AbsolutePath;
+
+const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+/// `LANDLOCK_ACCESS_FS_EXECUTE`, `WRITE_FILE`, `READ_FILE`, `TRUNCATE` and `IOCTL_DEV`, which are
+/// the only rights that apply to a file rather than a directory.
+const FILE_ACCESS: u64 = (1 << 0) | (1 << 1) | (1 << 2) | (1 << 14) | (1 << 15);
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: libc::c_int,
+}
+
+/// Kernel enforced restrictions on which files the current thread can access, using Landlock on
+/// linux.
+///
+/// Unlike checking typed paths before using them, this also covers code that does not go through
+/// this crate, e.g. untrusted plugins, and cannot be escaped with symlinks.
+#[derive(Debug)]
+pub struct Sandbox;
+
+impl Sandbox {
+    /// Restrict the current thread, and any threads or processes it starts afterwards, to only
+    /// accessing files beneath `roots`. Each root may be a directory or a file.
+    ///
+    /// Everything the running kernel's Landlock version can restrict is denied outside of the
+    /// roots, and allowed beneath them. This cannot be undone, and further calls can only
+    /// restrict access further. Other threads that are already running are not affected.
+    ///
+    /// Fails with [`std::io::ErrorKind::Unsupported`] if Landlock is not available, e.g. on
+    /// kernels before 5.13, or if it is disabled.
+    pub fn restrict_to(roots: &[&AbsolutePath]) -> std::io::Result<()> {
+        let handled = handled_access(abi_version()?);
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        // SAFETY: `attr` is a valid `landlock_ruleset_attr` of the given size.
+        let ruleset = unsafe {
+            check(libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0,
+            ))?
+        };
+        // SAFETY: The syscall returned a new file descriptor that nothing else owns.
+        let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as libc::c_int) };
+
+        for root in roots {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+                .open(root)?;
+            let allowed = if root.is_dir() {
+                handled
+            } else {
+                handled & FILE_ACCESS
+            };
+            let rule = PathBeneathAttr {
+                allowed_access: allowed,
+                parent_fd: file.as_raw_fd(),
+            };
+            // SAFETY: `rule` is a valid `landlock_path_beneath_attr`, and both fds are open.
+            unsafe {
+                check(libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset.as_raw_fd(),
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0,
+                ))?;
+            }
+        }
+
+        // SAFETY: These are plain syscalls on integer arguments and an open fd.
+        unsafe {
+            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) as libc::c_long)?;
+            check(libc::syscall(
+                libc::SYS_landlock_restrict_self,
+                ruleset.as_raw_fd(),
+                0,
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// The Landlock ABI version that the kernel supports.
+fn abi_version() -> std::io::Result<libc::c_long> {
+    // SAFETY: Querying the version takes no pointers.
+    unsafe {
+        check(libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        ))
+    }
+}
+
+/// Every filesystem access right that the given ABI version can restrict.
+fn handled_access(abi: libc::c_long) -> u64 {
+    let last_right = match abi {
+        1 => 12,
+        // `REFER`
+        2 => 13,
+        // `TRUNCATE`
+        3 | 4 => 14,
+        // `IOCTL_DEV`
+        _ => 15,
+    };
+    (1 << (last_right + 1)) - 1
+}
+
+fn check(result: libc::c_long) -> std::io::Result<libc::c_long> {
+    if result >= 0 {
+        return Ok(result);
+    }
+    let e = std::io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::ENOSYS) | Some(libc::EOPNOTSUPP) => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "landlock is not supported by this kernel",
+        )),
+        _ => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::Sandbox;
+
+    #[test]
+    fn restricts_access() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let allowed = root.join("allowed")?;
+        std::fs::create_dir(&allowed)?;
+        std::fs::write(allowed.join("a.txt")?, "a")?;
+        std::fs::write(root.join("denied.txt")?, "denied")?;
+
+        // Only the thread that is restricted, and threads it starts, are affected.
+        std::thread::spawn(move || -> anyhow::Result<()> {
+            match Sandbox::restrict_to(&[&allowed]) {
+                Err(e) if e.kind() == std::io::ErrorKind::Unsupported => return Ok(()),
+                result => result?,
+            }
+            assert_eq!("a", std::fs::read_to_string(allowed.join("a.txt")?)?);
+            std::fs::write(allowed.join("b.txt")?, "b")?;
+            assert_eq!(
+                std::io::ErrorKind::PermissionDenied,
+                std::fs::read(root.join("denied.txt")?).unwrap_err().kind()
+            );
+            assert!(std::fs::write(root.join("new.txt")?, "").is_err());
+            Ok(())
+        })
+        .join()
+        .unwrap()?;
+
+        assert!(std::fs::read(temp.path().join("denied.txt")).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+use std::path::Path;
+
+use crate::fs::Mode;
+use crate::fs::Operation;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::CombinedPathBuf;
+use crate::RelativePathBuf;
+
+/// One entry in a [`Scaffold`] manifest. Paths are relative to the root that the scaffold is
+/// created in.
+///
+/// With the `serde` feature, entries are (de)serialized as e.g. `{"dir": "src"}`,
+/// `{"file": "src/main.rs"}` or `{"symlink": {"path": "latest", "target": "v1"}}`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ScaffoldEntry {
+    /// A directory, and any missing parents.
+    Dir(RelativePathBuf),
+    /// An empty file. Existing files are left as they are.
+    File(RelativePathBuf),
+    /// A symlink at `path`. A relative `target` is relative to the directory containing the
+    /// link, as usual.
+    Symlink {
+        path: RelativePathBuf,
+        target: CombinedPathBuf,
+    },
+}
+
+impl ScaffoldEntry {
+    /// The path that this entry creates, relative to the root.
+    pub fn path(&self) -> &RelativePathBuf {
+        match self {
+            ScaffoldEntry::Dir(path)
+            | ScaffoldEntry::File(path)
+            | ScaffoldEntry::Symlink { path, .. } => path,
+        }
+    }
+}
+
+/// Creates directories, empty files and symlinks described by a manifest beneath a root
+/// directory, e.g. for project templates.
+///
+/// [`Scaffold::plan`] can be used as a dry run, to list the paths that [`Scaffold::create`]
+/// would create.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Scaffold {
+    entries: Vec<ScaffoldEntry>,
+}
+
+impl Scaffold {
+    /// Create a scaffold from `entries`, e.g. deserialized from a template's manifest file.
+    ///
+    /// Entries are created in order, though missing parent directories are always created.
+    pub fn from_manifest(entries: Vec<ScaffoldEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// The entries in the manifest.
+    pub fn entries(&self) -> &[ScaffoldEntry] {
+        &self.entries
+    }
+
+    /// Get the absolute path of each entry beneath `root`, in order, without touching the
+    /// filesystem.
+    ///
+    /// Fails if any entry is not beneath `root`, e.g. because it has too many `..` components,
+    /// or if a symlink's target is absolute or is not beneath `root`.
+    pub fn plan(&self, root: &AbsolutePath) -> std::io::Result<Vec<AbsolutePathBuf>> {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let path = root
+                    .join_relative(entry.path())
+                    .ok()
+                    .filter(|path| path.starts_with(root) && path.as_absolute_path() != root)
+                    .ok_or_else(|| {
+                        invalid_input(format!(
+                            "`{}` is not a path beneath `{}`",
+                            entry.path().display(),
+                            root.display()
+                        ))
+                    })?;
+                if let ScaffoldEntry::Symlink { target, .. } = entry {
+                    let resolved = match (target, path.parent()) {
+                        (CombinedPathBuf::Relative(target), Some(parent)) => {
+                            parent.join_relative(target).ok()
+                        }
+                        _ => None,
+                    };
+                    if !resolved.is_some_and(|resolved| resolved.starts_with(root)) {
+                        return Err(invalid_input(format!(
+                            "the target of `{}`, `{}`, is not a relative path beneath `{}`",
+                            entry.path().display(),
+                            target.display(),
+                            root.display()
+                        )));
+                    }
+                }
+                Ok(path)
+            })
+            .collect()
+    }
+
+    /// Create each entry beneath `root`, returning the paths that were created, per
+    /// [`Scaffold::plan`].
+    ///
+    /// Nothing is created if any entry is not beneath `root`. Entries are never created through
+    /// a symlink beneath `root`, whether it was created by an earlier entry or already existed,
+    /// so that they cannot end up outside of it. Stops at the first error, leaving earlier
+    /// entries in place.
+    pub fn create(&self, root: &AbsolutePath) -> std::io::Result<Vec<AbsolutePathBuf>> {
+        let planned = self.plan(root)?;
+        for (entry, path) in self.entries.iter().zip(&planned) {
+            match entry {
+                ScaffoldEntry::Symlink { .. } => {
+                    reject_symlinks(root, path.parent().unwrap_or(path))?
+                }
+                _ => reject_symlinks(root, path)?,
+            }
+            match entry {
+                ScaffoldEntry::Dir(_) => {
+                    crate::fs::create_dir_all(path, Mode::Execute)?;
+                }
+                ScaffoldEntry::File(_) => {
+                    create_parent(path)?;
+                    if !path.exists() {
+                        crate::fs::audit(&Operation::Write(path.clone()), None)?;
+                    }
+                    std::fs::OpenOptions::new()
+                        .create(true)
+                        .truncate(false)
+                        .write(true)
+                        .open(path)?;
+                }
+                ScaffoldEntry::Symlink { target, .. } => {
+                    create_parent(path)?;
+                    crate::fs::audit(&Operation::Write(path.clone()), None)?;
+                    symlink(target, path)?;
+                }
+            }
+        }
+        Ok(planned)
+    }
+}
+
+/// Create the missing parents of `path` with the audited helpers in [`crate::fs`].
+fn create_parent(path: &AbsolutePath) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        crate::fs::create_dir_all(parent, Mode::Execute)?;
+    }
+    Ok(())
+}
+
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+/// Fail if `path`, or any of its ancestors beneath `root`, is a symlink.
+fn reject_symlinks(root: &AbsolutePath, path: &AbsolutePath) -> std::io::Result<()> {
+    for ancestor in path.ancestors().take_while(|ancestor| *ancestor != root) {
+        if std::fs::symlink_metadata(ancestor).is_ok_and(|metadata| metadata.is_symlink()) {
+            return Err(invalid_input(format!(
+                "`{}` would be created through the symlink `{}`",
+                path.display(),
+                ancestor.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &AbsolutePath) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &AbsolutePath) -> std::io::Result<()> {
+    let resolved = match link.parent() {
+        Some(parent) => parent.as_path().join(target),
+        None => target.to_path_buf(),
+    };
+    if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+    use crate::RelativePathBuf;
+    use crate::Scaffold;
+    use crate::ScaffoldEntry;
+
+    fn relative(path: &str) -> RelativePathBuf {
+        RelativePathBuf::new_unchecked(path)
+    }
+
+    #[test]
+    fn plans_and_creates() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::create_dir(root.join("src")?)?;
+        std::fs::write(root.join("src/main.rs")?, "existing")?;
+
+        let scaffold = Scaffold::from_manifest(vec![
+            ScaffoldEntry::Dir(relative("target/debug")),
+            ScaffoldEntry::File(relative("src/main.rs")),
+            ScaffoldEntry::File(relative("tests/it.rs")),
+            ScaffoldEntry::Symlink {
+                path: relative("latest"),
+                target: CombinedPathBuf::try_new("target/debug")?,
+            },
+        ]);
+        let expected = vec![
+            root.join("target/debug")?,
+            root.join("src/main.rs")?,
+            root.join("tests/it.rs")?,
+            root.join("latest")?,
+        ];
+
+        assert_eq!(expected, scaffold.plan(&root)?);
+        assert!(!root.join("tests")?.exists());
+
+        assert_eq!(expected, scaffold.create(&root)?);
+        assert!(root.join("target/debug")?.is_dir());
+        assert_eq!(
+            "existing",
+            std::fs::read_to_string(root.join("src/main.rs")?)?
+        );
+        assert_eq!(0, std::fs::metadata(root.join("tests/it.rs")?)?.len());
+        assert!(root.join("latest")?.symlink_metadata()?.is_symlink());
+        assert!(root.join("latest")?.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_escaping_entries() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?.join("project")?;
+
+        let scaffold = Scaffold::from_manifest(vec![
+            ScaffoldEntry::Dir(relative("ok")),
+            ScaffoldEntry::File(relative("../escape")),
+        ]);
+        assert!(scaffold.plan(&root).is_err());
+        assert!(scaffold.create(&root).is_err());
+        assert!(!root.exists());
+
+        for target in ["../escape", "sub/../../escape"] {
+            let scaffold = Scaffold::from_manifest(vec![ScaffoldEntry::Symlink {
+                path: relative("link"),
+                target: CombinedPathBuf::try_new(target)?,
+            }]);
+            assert!(scaffold.plan(&root).is_err());
+        }
+        let scaffold = Scaffold::from_manifest(vec![ScaffoldEntry::Symlink {
+            path: relative("link"),
+            target: CombinedPathBuf::Absolute(AbsolutePathBuf::try_new(temp.path())?),
+        }]);
+        assert!(scaffold.plan(&root).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_create_through_symlinks() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let base = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let root = base.join("project")?;
+        let outside = base.join("outside")?;
+        std::fs::create_dir(&root)?;
+        std::fs::create_dir(&outside)?;
+
+        let scaffold = Scaffold::from_manifest(vec![
+            ScaffoldEntry::Dir(relative("dir")),
+            ScaffoldEntry::Symlink {
+                path: relative("link"),
+                target: CombinedPathBuf::try_new("dir")?,
+            },
+            ScaffoldEntry::File(relative("link/file.txt")),
+        ]);
+        assert!(scaffold.create(&root).is_err());
+        assert!(root.join("link")?.symlink_metadata()?.is_symlink());
+        assert!(!root.join("dir/file.txt")?.exists());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("existing")?)?;
+            for entry in [
+                ScaffoldEntry::File(relative("existing/file.txt")),
+                ScaffoldEntry::Dir(relative("existing/dir")),
+            ] {
+                assert!(Scaffold::from_manifest(vec![entry]).create(&root).is_err());
+            }
+            assert_eq!(0, std::fs::read_dir(&outside)?.count());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializes_manifest() -> anyhow::Result<()> {
+        let entries: Vec<ScaffoldEntry> = serde_json::from_str(
+            r#"[
+                {"dir": "src"},
+                {"file": "src/lib.rs"},
+                {"symlink": {"path": "docs", "target": "../shared/docs"}}
+            ]"#,
+        )?;
+        assert_eq!(
+            vec![
+                ScaffoldEntry::Dir(relative("src")),
+                ScaffoldEntry::File(relative("src/lib.rs")),
+                ScaffoldEntry::Symlink {
+                    path: relative("docs"),
+                    target: CombinedPathBuf::try_new("../shared/docs")?,
+                },
+            ],
+            entries
+        );
+        Ok(())
+    }
+}
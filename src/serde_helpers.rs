@@ -0,0 +1,283 @@
+//! Helpers for `#[serde(with = "...")]` on path fields, for conventions that are common in
+//! config files.
+
+/// An optional path, where an empty string (or `null`) means `None`.
+///
+/// `None` is serialized as an empty string. Use with `#[serde(default)]` to also allow the field
+/// to be missing. Formats that are not human readable, e.g. bincode, have no need for this, so
+/// an ordinary `Option` is used for them.
+///
+/// ```
+/// # use paths::AbsolutePathBuf;
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     #[serde(with = "paths::serde_helpers::empty_as_none", default)]
+///     cache_dir: Option<AbsolutePathBuf>,
+/// }
+/// ```
+pub mod empty_as_none {
+    use serde::de::IntoDeserializer;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    pub fn serialize<T, S>(path: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        match path {
+            Some(path) if serializer.is_human_readable() => path.serialize(serializer),
+            None if serializer.is_human_readable() => serializer.serialize_str(""),
+            _ => path.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return Option::<T>::deserialize(deserializer);
+        }
+        match Option::<String>::deserialize(deserializer)? {
+            Some(s) if !s.is_empty() => T::deserialize(s.into_deserializer()).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A directory that defaults to the current working directory if it is `null` or missing.
+///
+/// Formats that are not human readable, e.g. bincode, always contain the path.
+///
+/// ```
+/// # use paths::AbsolutePathBuf;
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     #[serde(
+///         with = "paths::serde_helpers::or_current_dir",
+///         default = "paths::serde_helpers::or_current_dir::default"
+///     )]
+///     root: AbsolutePathBuf,
+/// }
+/// ```
+pub mod or_current_dir {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    use crate::AbsolutePathBuf;
+
+    /// The current working directory, for `#[serde(default = "...")]`.
+    pub fn default() -> AbsolutePathBuf {
+        AbsolutePathBuf::current_dir()
+    }
+
+    pub fn serialize<S>(path: &AbsolutePathBuf, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        path.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<AbsolutePathBuf, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Formats that are not human readable cannot tell a path from `null` without a tag.
+        if !deserializer.is_human_readable() {
+            return AbsolutePathBuf::deserialize(deserializer);
+        }
+        Ok(Option::<AbsolutePathBuf>::deserialize(deserializer)?.unwrap_or_else(default))
+    }
+}
+
+/// A collection of paths, e.g. a `HashSet` or `Vec`, that is serialized in sorted order whatever
+/// its order in memory, so that files like lockfiles are reproducible byte for byte.
+///
+/// Paths are sorted component by component, like [`std::path::Path`]'s `Ord`, so `a/b` sorts
+/// before `a-b` regardless of which separators are used. Deserializing keeps the order of the
+/// input.
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use paths::RelativePathBuf;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Lockfile {
+///     #[serde(with = "paths::serde_helpers::sorted")]
+///     inputs: HashSet<RelativePathBuf>,
+/// }
+/// ```
+pub mod sorted {
+    use std::path::Path;
+
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    pub fn serialize<'a, C, T, S>(paths: &'a C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a C: IntoIterator<Item = &'a T>,
+        T: AsRef<Path> + Serialize + 'a,
+        S: Serializer,
+    {
+        let mut sorted: Vec<&T> = paths.into_iter().collect();
+        sorted.sort_by(|l, r| l.as_ref().cmp(r.as_ref()));
+        serializer.collect_seq(sorted)
+    }
+
+    pub fn deserialize<'de, C, D>(deserializer: D) -> Result<C, D::Error>
+    where
+        C: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        C::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use serde::Deserialize;
+    use serde::Serialize;
+
+    use crate::AbsolutePathBuf;
+    use crate::RelativePathBuf;
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Config {
+        #[serde(with = "crate::serde_helpers::empty_as_none", default)]
+        cache: Option<AbsolutePathBuf>,
+        #[serde(with = "crate::serde_helpers::empty_as_none", default)]
+        output: Option<RelativePathBuf>,
+        #[serde(
+            with = "crate::serde_helpers::or_current_dir",
+            default = "crate::serde_helpers::or_current_dir::default"
+        )]
+        root: AbsolutePathBuf,
+    }
+
+    #[test]
+    fn deserializes_helpers() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let cache = cwd.join("cache")?;
+
+        let config: Config = serde_json::from_str("{}")?;
+        assert_eq!(
+            Config {
+                cache: None,
+                output: None,
+                root: cwd.clone(),
+            },
+            config
+        );
+
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "cache": cache.as_path(),
+            "output": "",
+            "root": null,
+        }))?;
+        assert_eq!(
+            Config {
+                cache: Some(cache.clone()),
+                output: None,
+                root: cwd.clone(),
+            },
+            config
+        );
+
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "cache": null,
+            "output": "out",
+            "root": cache.as_path(),
+        }))?;
+        assert_eq!(
+            Config {
+                cache: None,
+                output: Some(RelativePathBuf::try_new("out")?),
+                root: cache.clone(),
+            },
+            config
+        );
+
+        assert!(serde_json::from_str::<Config>(r#"{"cache": "relative"}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"root": "relative"}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn serializes_helpers() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let config = Config {
+            cache: None,
+            output: Some(RelativePathBuf::try_new("out")?),
+            root: cwd.clone(),
+        };
+        assert_eq!(
+            serde_json::json!({
+                "cache": "",
+                "output": "out",
+                "root": cwd.as_path(),
+            }),
+            serde_json::to_value(&config)?
+        );
+
+        let bytes = bincode::serialize(&config)?;
+        assert_eq!(config, bincode::deserialize::<Config>(&bytes)?);
+        let config = Config {
+            cache: Some(cwd.join("cache")?),
+            output: None,
+            root: cwd.clone(),
+        };
+        let bytes = bincode::serialize(&config)?;
+        assert_eq!(config, bincode::deserialize::<Config>(&bytes)?);
+        Ok(())
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct Lockfile {
+        #[serde(with = "crate::serde_helpers::sorted")]
+        inputs: HashSet<RelativePathBuf>,
+        #[serde(with = "crate::serde_helpers::sorted")]
+        outputs: Vec<AbsolutePathBuf>,
+    }
+
+    #[test]
+    fn serializes_sorted() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let names = ["b.txt", "a-b", "a/b", "c/d/e", "a"];
+        let lockfile = Lockfile {
+            inputs: names
+                .into_iter()
+                .map(RelativePathBuf::try_new)
+                .collect::<Result<_, _>>()?,
+            outputs: names
+                .into_iter()
+                .rev()
+                .map(|name| cwd.join(name))
+                .collect::<Result<_, _>>()?,
+        };
+        let sorted = ["a", "a/b", "a-b", "b.txt", "c/d/e"];
+        let outputs = sorted
+            .into_iter()
+            .map(|name| cwd.join(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            serde_json::json!({"inputs": sorted, "outputs": outputs}),
+            serde_json::to_value(&lockfile)?
+        );
+
+        let serialized = serde_json::to_string(&lockfile)?;
+        assert_eq!(serialized, serde_json::to_string(&lockfile)?);
+        let round_tripped: Lockfile = serde_json::from_str(&serialized)?;
+        assert_eq!(lockfile.inputs, round_tripped.inputs);
+        assert_eq!(outputs, round_tripped.outputs);
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! How the path types are represented with serde.
+//!
+//! Human readable formats like JSON use strings, so paths have to be valid UTF-8. Other formats,
+//! like bincode, use the raw bytes of the path on unix, so that any path round trips exactly.
+//! Elsewhere, the bytes are UTF-8, as there is no portable byte form of other platforms' paths.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::de::Error;
+use serde::de::Visitor;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+pub(crate) fn serialize<S>(path: &Path, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        path.serialize(serializer)
+    } else {
+        use serde::ser::Error;
+        serializer.serialize_bytes(path_to_bytes(path).map_err(S::Error::custom)?)
+    }
+}
+
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        serde::Deserialize::deserialize(deserializer)
+    } else {
+        deserializer.deserialize_byte_buf(PathBufVisitor)
+    }
+}
+
+/// Borrows the path from the input, which has to allow borrowing.
+pub(crate) fn deserialize_borrowed<'de, D>(deserializer: D) -> Result<&'de Path, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        serde::Deserialize::deserialize(deserializer)
+    } else {
+        deserializer.deserialize_bytes(BorrowedPathVisitor)
+    }
+}
+
+struct PathBufVisitor;
+
+impl<'de> Visitor<'de> for PathBufVisitor {
+    type Value = PathBuf;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("path bytes")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_string<E: Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(v.into())
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        bytes_to_path(v).map(Path::to_path_buf).map_err(E::custom)
+    }
+
+    fn visit_byte_buf<E: Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        bytes_to_path_buf(v).map_err(E::custom)
+    }
+}
+
+struct BorrowedPathVisitor;
+
+impl<'de> Visitor<'de> for BorrowedPathVisitor {
+    type Value = &'de Path;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("borrowed path bytes")
+    }
+
+    fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        Ok(Path::new(v))
+    }
+
+    fn visit_borrowed_bytes<E: Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+        bytes_to_path(v).map_err(E::custom)
+    }
+}
+
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Result<&[u8], String> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_to_bytes(path: &Path) -> Result<&[u8], String> {
+    path.to_str()
+        .map(str::as_bytes)
+        .ok_or_else(|| format!("`{}` is not valid unicode", path.display()))
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: &[u8]) -> Result<&Path, String> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(Path::new(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn bytes_to_path(bytes: &[u8]) -> Result<&Path, String> {
+    std::str::from_utf8(bytes)
+        .map(Path::new)
+        .map_err(|_| format!("`{}` is not valid unicode", String::from_utf8_lossy(bytes)))
+}
+
+#[cfg(unix)]
+fn bytes_to_path_buf(bytes: Vec<u8>) -> Result<PathBuf, String> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(std::ffi::OsString::from_vec(bytes).into())
+}
+
+#[cfg(not(unix))]
+fn bytes_to_path_buf(bytes: Vec<u8>) -> Result<PathBuf, String> {
+    String::from_utf8(bytes).map(PathBuf::from).map_err(|e| {
+        format!(
+            "`{}` is not valid unicode",
+            String::from_utf8_lossy(e.as_bytes())
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePath;
+    use crate::AbsolutePathBuf;
+    use crate::CombinedPathBuf;
+    use crate::RelativePath;
+    use crate::RelativePathBuf;
+
+    #[test]
+    fn binary_formats_use_bytes() -> anyhow::Result<()> {
+        let absolute = AbsolutePathBuf::current_dir().join("foo/bar.txt")?;
+        let relative = RelativePathBuf::try_new("foo/bar.txt")?;
+        let combined = CombinedPathBuf::try_new("../foo")?;
+
+        let bytes = bincode::serialize(&absolute)?;
+        assert_eq!(absolute, bincode::deserialize::<AbsolutePathBuf>(&bytes)?);
+        assert_eq!(&*absolute, bincode::deserialize::<&AbsolutePath>(&bytes)?);
+        let bytes = bincode::serialize(&relative)?;
+        assert_eq!(relative, bincode::deserialize::<RelativePathBuf>(&bytes)?);
+        assert_eq!(&*relative, bincode::deserialize::<&RelativePath>(&bytes)?);
+        assert!(bincode::deserialize::<AbsolutePathBuf>(&bytes).is_err());
+        let bytes = bincode::serialize(&combined)?;
+        assert_eq!(combined, bincode::deserialize::<CombinedPathBuf>(&bytes)?);
+
+        // Human readable formats still use strings.
+        assert_eq!(
+            serde_json::json!("foo/bar.txt"),
+            serde_json::to_value(&relative)?
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn binary_formats_round_trip_non_utf8() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"caf\xe9.txt");
+        let absolute = AbsolutePathBuf::current_dir().join(name)?;
+        let relative = RelativePathBuf::try_new(name)?;
+
+        let bytes = bincode::serialize(&absolute)?;
+        assert_eq!(absolute, bincode::deserialize::<AbsolutePathBuf>(&bytes)?);
+        let bytes = bincode::serialize(&relative)?;
+        assert_eq!(relative, bincode::deserialize::<RelativePathBuf>(&bytes)?);
+        assert_eq!(&*relative, bincode::deserialize::<&RelativePath>(&bytes)?);
+
+        assert!(serde_json::to_string(&relative).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use crate::AbsolutePath;
+use crate::AlreadyRunning;
+use crate::PathLock;
+use crate::SingleInstanceError;
+
+/// Ensures only one instance of a program runs at a time, using a locked pidfile.
+///
+/// The pidfile is held with an exclusive [`PathLock`] for as long as this guard is alive. As the
+/// OS drops the lock when a process exits, a pidfile left behind by a crashed process is
+/// detected as stale rather than blocking startup. On drop the pidfile is emptied, but not
+/// removed, so that another process cannot end up locking an unlinked file.
+#[derive(Debug)]
+pub struct SingleInstance {
+    lock: PathLock,
+    stale_pid: Option<u32>,
+}
+
+impl SingleInstance {
+    /// Acquire the pidfile at `path`, writing the current process id to it.
+    ///
+    /// The parent directory is created if needed. This fails with
+    /// [`SingleInstanceError::AlreadyRunning`] if another live process holds the pidfile.
+    pub fn acquire(path: &AbsolutePath) -> Result<Self, SingleInstanceError> {
+        path.ensure_parent_exists()?;
+        let lock = match PathLock::try_exclusive(path)? {
+            Some(lock) => lock,
+            None => {
+                let pid = std::fs::read_to_string(path)
+                    .ok()
+                    .and_then(|contents| contents.trim().parse().ok());
+                return Err(AlreadyRunning(path.display().to_string(), pid).into());
+            }
+        };
+
+        let mut file = lock.file();
+        let mut previous = String::new();
+        file.read_to_string(&mut previous)?;
+        let stale_pid = previous.trim().parse().ok();
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+
+        Ok(Self { lock, stale_pid })
+    }
+
+    /// The path to the pidfile.
+    pub fn path(&self) -> &AbsolutePath {
+        self.lock.path()
+    }
+
+    /// The pid left in the pidfile by a previous holder that exited without cleaning up, if any.
+    pub fn stale_pid(&self) -> Option<u32> {
+        self.stale_pid
+    }
+}
+
+impl Drop for SingleInstance {
+    fn drop(&mut self) {
+        let _ = self.lock.file().set_len(0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::AlreadyRunning;
+    use crate::SingleInstance;
+    use crate::SingleInstanceError;
+
+    #[test]
+    fn only_one_instance() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let path = AbsolutePathBuf::try_new(temp.path().join("run/app.pid"))?;
+
+        let instance = SingleInstance::acquire(&path)?;
+        assert_eq!(path.as_absolute_path(), instance.path());
+        assert_eq!(None, instance.stale_pid());
+        assert_eq!(
+            std::process::id().to_string(),
+            std::fs::read_to_string(&path)?
+        );
+
+        match SingleInstance::acquire(&path).unwrap_err() {
+            SingleInstanceError::AlreadyRunning(e) => assert_eq!(
+                AlreadyRunning(path.display().to_string(), Some(std::process::id())),
+                e
+            ),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        drop(instance);
+        assert_eq!("", std::fs::read_to_string(&path)?);
+        assert!(SingleInstance::acquire(&path).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn detects_stale_pidfile() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let path = AbsolutePathBuf::try_new(temp.path().join("app.pid"))?;
+        std::fs::write(&path, "12345\n")?;
+
+        let instance = SingleInstance::acquire(&path)?;
+        assert_eq!(Some(12345), instance.stale_pid());
+        assert_eq!(
+            std::process::id().to_string(),
+            std::fs::read_to_string(&path)?
+        );
+        Ok(())
+    }
+}
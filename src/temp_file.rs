@@ -0,0 +1,284 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use crate::fs::Operation;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// A temporary file with no name, created by [`AbsolutePath::create_anonymous_temp`].
+///
+/// On linux this uses `O_TMPFILE` where the filesystem supports it, so the file never appears
+/// in its directory, and is cleaned up by the OS if the process exits without persisting it.
+/// Elsewhere, the file is created with a random hidden name in the directory and removed when
+/// this is dropped. Either way, on unix the file is only readable and writable by its owner.
+///
+/// Once all data has been written, [`AnonymousTempFile::persist_as`] gives the file its final
+/// name, atomically replacing any existing file there. The destination must be on the same
+/// filesystem as the directory the temporary file was created in.
+#[derive(Debug)]
+pub struct AnonymousTempFile {
+    file: File,
+    dir: AbsolutePathBuf,
+    named: Option<NamedTempPath>,
+}
+
+/// The permissions that temporary files are created with, however they are created, so that
+/// other users cannot read them before they are persisted.
+#[cfg(unix)]
+const TEMP_MODE: u32 = 0o600;
+
+/// Removes the fallback temporary file on drop, unless it has been persisted.
+#[derive(Debug)]
+struct NamedTempPath {
+    path: AbsolutePathBuf,
+    persisted: bool,
+}
+
+impl Drop for NamedTempPath {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl AnonymousTempFile {
+    pub(crate) fn create_in(dir: &AbsolutePath) -> std::io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            match OpenOptions::new()
+                .read(true)
+                .write(true)
+                .mode(TEMP_MODE)
+                .custom_flags(libc::O_TMPFILE)
+                .open(dir)
+            {
+                Ok(file) => {
+                    return Ok(Self {
+                        file,
+                        dir: dir.into(),
+                        named: None,
+                    });
+                }
+                // Returned by older kernels and filesystems that do not support O_TMPFILE.
+                Err(e)
+                    if matches!(
+                        e.raw_os_error(),
+                        Some(libc::EOPNOTSUPP) | Some(libc::EISDIR) | Some(libc::EINVAL)
+                    ) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Self::create_named_in(dir)
+    }
+
+    fn create_named_in(dir: &AbsolutePath) -> std::io::Result<Self> {
+        loop {
+            let path = dir
+                .join(crate::unique_file_name(".tmp-"))
+                .expect("a plain file name can always be joined");
+            let mut options = OpenOptions::new();
+            options.read(true).write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+
+                options.mode(TEMP_MODE);
+            }
+            match options.open(&path) {
+                Ok(file) => {
+                    return Ok(Self {
+                        file,
+                        dir: dir.into(),
+                        named: Some(NamedTempPath {
+                            path,
+                            persisted: false,
+                        }),
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The directory that this file was created in.
+    pub fn dir(&self) -> &AbsolutePath {
+        self.dir.as_absolute_path()
+    }
+
+    /// Get a reference to the underlying file.
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Get a mutable reference to the underlying file.
+    pub fn as_file_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+
+    /// Give the file a name at `dest`, replacing any file that is already there.
+    pub fn persist_as(self, dest: &AbsolutePath) -> std::io::Result<File> {
+        crate::fs::audit(&Operation::Write(dest.into()), None)?;
+        self.persist_as_unaudited(dest)
+    }
+
+    /// [`AnonymousTempFile::persist_as`], for callers that have already run the audit hooks.
+    pub(crate) fn persist_as_unaudited(mut self, dest: &AbsolutePath) -> std::io::Result<File> {
+        self.file.flush()?;
+        match &mut self.named {
+            Some(named) => {
+                std::fs::rename(&named.path, dest)?;
+                named.persisted = true;
+            }
+            None => self.link_anonymous(dest)?,
+        }
+        Ok(self.file)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn link_anonymous(&self, dest: &AbsolutePath) -> std::io::Result<()> {
+        use std::ffi::CString;
+        use std::os::fd::AsRawFd;
+        use std::os::unix::ffi::OsStrExt;
+
+        // linkat refuses to replace an existing file, so link to a temporary name beside the
+        // destination, then rename over it.
+        let parent = dest.parent().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot persist to the filesystem root",
+            )
+        })?;
+        let fd_path = CString::new(format!("/proc/self/fd/{}", self.file.as_raw_fd()))
+            .expect("no nul bytes in fd path");
+        loop {
+            let temp_dest = parent
+                .join(crate::unique_file_name(".tmp-"))
+                .expect("a plain file name can always be joined");
+            let c_temp_dest = CString::new(temp_dest.as_os_str().as_bytes())
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            // SAFETY: Both paths are valid nul terminated strings.
+            let res = unsafe {
+                libc::linkat(
+                    libc::AT_FDCWD,
+                    fd_path.as_ptr(),
+                    libc::AT_FDCWD,
+                    c_temp_dest.as_ptr(),
+                    libc::AT_SYMLINK_FOLLOW,
+                )
+            };
+            if res != 0 {
+                let e = std::io::Error::last_os_error();
+                if e.kind() == std::io::ErrorKind::AlreadyExists {
+                    continue;
+                }
+                return Err(e);
+            }
+            return std::fs::rename(&temp_dest, dest).inspect_err(|_| {
+                let _ = std::fs::remove_file(&temp_dest);
+            });
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn link_anonymous(&self, _dest: &AbsolutePath) -> std::io::Result<()> {
+        unreachable!("anonymous files are only created on linux")
+    }
+}
+
+impl Read for AnonymousTempFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for AnonymousTempFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for AnonymousTempFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use crate::temp_file::AnonymousTempFile;
+    use crate::AbsolutePathBuf;
+
+    fn entries(dir: &AbsolutePathBuf) -> anyhow::Result<Vec<String>> {
+        let mut entries = std::fs::read_dir(dir)?
+            .map(|e| Ok(e?.file_name().to_string_lossy().to_string()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    #[test]
+    fn persists_anonymous_file() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let dest = root.join("out.txt")?;
+        std::fs::write(&dest, "old")?;
+
+        let mut temp_file = root.create_anonymous_temp()?;
+        assert_eq!(root.as_absolute_path(), temp_file.dir());
+        temp_file.write_all(b"new")?;
+        temp_file.persist_as(&dest)?;
+
+        assert_eq!("new", std::fs::read_to_string(&dest)?);
+        assert_eq!(vec!["out.txt".to_owned()], entries(&root)?);
+        Ok(())
+    }
+
+    #[test]
+    fn named_fallback() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let dest = root.join("out.txt")?;
+
+        let mut temp_file = AnonymousTempFile::create_named_in(&root)?;
+        temp_file.write_all(b"new")?;
+        assert_eq!(1, entries(&root)?.len());
+        temp_file.persist_as(&dest)?;
+        assert_eq!("new", std::fs::read_to_string(&dest)?);
+        assert_eq!(vec!["out.txt".to_owned()], entries(&root)?);
+
+        drop(AnonymousTempFile::create_named_in(&root)?);
+        assert_eq!(vec!["out.txt".to_owned()], entries(&root)?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_owner_can_access() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        for temp_file in [
+            root.create_anonymous_temp()?,
+            AnonymousTempFile::create_named_in(&root)?,
+        ] {
+            let mode = temp_file.as_file().metadata()?.permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::path::Path;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::OverlappingRoots;
+use crate::RelativePath;
+use crate::RootSet;
+use crate::TenantScopeError;
+
+/// The root directory of each tenant of a multi-tenant service, which [`TenantScopedPath`]s are
+/// checked against.
+///
+/// Like a [`RootSet`], roots cannot overlap, so every path belongs to at most one tenant.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct TenantRoots {
+    roots: RootSet,
+    tenants: BTreeMap<String, AbsolutePathBuf>,
+}
+
+impl TenantRoots {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `root` for `tenant`, replacing any existing root for it.
+    ///
+    /// Fails if `root` is inside of or contains another tenant's root.
+    pub fn insert(
+        &mut self,
+        tenant: impl Into<String>,
+        root: AbsolutePathBuf,
+    ) -> Result<(), OverlappingRoots> {
+        let tenant = tenant.into();
+        let previous = self.tenants.remove(&tenant);
+        if let Some(previous) = &previous {
+            self.roots.remove(previous);
+        }
+        match self.roots.insert(root.clone()) {
+            Ok(()) => {
+                self.tenants.insert(tenant, root);
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(previous) = previous {
+                    self.roots
+                        .insert(previous.clone())
+                        .expect("the previous root did not overlap");
+                    self.tenants.insert(tenant, previous);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// The root of `tenant`, if it is registered.
+    pub fn root(&self, tenant: &str) -> Option<&AbsolutePath> {
+        self.tenants.get(tenant).map(|root| root.as_absolute_path())
+    }
+
+    /// Check that `path` is beneath (or is) the root of `tenant`.
+    pub fn scope(
+        &self,
+        tenant: &str,
+        path: &AbsolutePath,
+    ) -> Result<TenantScopedPath, TenantScopeError> {
+        let root = self
+            .tenants
+            .get(tenant)
+            .ok_or_else(|| TenantScopeError::UnknownTenant(tenant.to_owned()))?;
+        if path.starts_with(root) {
+            Ok(TenantScopedPath {
+                tenant: tenant.to_owned(),
+                root: root.clone(),
+                path: path.to_owned(),
+            })
+        } else {
+            Err(TenantScopeError::OutsideTenant(
+                path.to_string_lossy().to_string(),
+                tenant.to_owned(),
+            ))
+        }
+    }
+}
+
+/// An [`AbsolutePathBuf`] that is beneath the root of a tenant in a [`TenantRoots`], so that a
+/// request for one tenant cannot reach another tenant's files.
+///
+/// This only checks the path itself, so symlinks beneath a tenant's root can still point
+/// elsewhere. Use [`crate::Sandbox`] to have the kernel enforce access as well.
+///
+/// Deserializing needs the registry to check against, so it is done through
+/// [`serde::de::DeserializeSeed`] on `&TenantRoots`, e.g.
+/// `serde::de::DeserializeSeed::deserialize(&roots, deserializer)`.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Ord, PartialOrd)]
+pub struct TenantScopedPath {
+    tenant: String,
+    root: AbsolutePathBuf,
+    path: AbsolutePathBuf,
+}
+
+impl TenantScopedPath {
+    /// The tenant that this path belongs to.
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// The root of the tenant when this path was scoped.
+    pub fn root(&self) -> &AbsolutePath {
+        &self.root
+    }
+
+    /// Get a new [`AbsolutePath`] referencing the internal Path object.
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        &self.path
+    }
+
+    /// The path relative to the tenant's root.
+    pub fn relative_path(&self) -> &RelativePath {
+        self.path
+            .strip_prefix(&self.root)
+            .expect("scoped paths are beneath their root")
+    }
+
+    /// Join `path` onto this path, checking that the result is still within the tenant, e.g.
+    /// that any `..` components do not leave it.
+    pub fn join(&self, path: &RelativePath) -> Result<TenantScopedPath, TenantScopeError> {
+        let joined = self.path.join_relative(path)?;
+        if joined.starts_with(&self.root) {
+            Ok(TenantScopedPath {
+                tenant: self.tenant.clone(),
+                root: self.root.clone(),
+                path: joined,
+            })
+        } else {
+            Err(TenantScopeError::OutsideTenant(
+                joined.to_string_lossy().to_string(),
+                self.tenant.clone(),
+            ))
+        }
+    }
+
+    /// Take ownership of the path as an [`AbsolutePathBuf`].
+    pub fn into_absolute_path_buf(self) -> AbsolutePathBuf {
+        self.path
+    }
+}
+
+impl From<TenantScopedPath> for AbsolutePathBuf {
+    fn from(value: TenantScopedPath) -> Self {
+        value.path
+    }
+}
+
+impl AsRef<Path> for TenantScopedPath {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<AbsolutePath> for TenantScopedPath {
+    fn as_ref(&self) -> &AbsolutePath {
+        &self.path
+    }
+}
+
+impl Deref for TenantScopedPath {
+    type Target = AbsolutePath;
+
+    fn deref(&self) -> &Self::Target {
+        self.path.deref()
+    }
+}
+
+#[cfg(feature = "display")]
+impl std::fmt::Display for TenantScopedPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.path.display().fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedTenantPath<T, P> {
+    tenant: T,
+    path: P,
+}
+
+/// Serializes the tenant and the path, but not the root, which comes from the [`TenantRoots`]
+/// when deserializing.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TenantScopedPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        SerializedTenantPath {
+            tenant: &self.tenant,
+            path: &self.path,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Checks the path against the tenant's current root, so paths that were moved out of a tenant,
+/// or whose tenant was removed, since they were serialized fail to deserialize.
+#[cfg(feature = "serde")]
+impl<'de> serde::de::DeserializeSeed<'de> for &TenantRoots {
+    type Value = TenantScopedPath;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        use serde::Deserialize;
+        let value = SerializedTenantPath::<String, AbsolutePathBuf>::deserialize(deserializer)?;
+        self.scope(&value.tenant, &value.path)
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::RelativePath;
+    use crate::TenantRoots;
+    use crate::TenantScopeError;
+
+    #[test]
+    fn scopes_paths_to_tenants() -> anyhow::Result<()> {
+        let mut roots = TenantRoots::new();
+        roots.insert("acme", AbsolutePathBuf::try_new("/srv/tenants/acme")?)?;
+        roots.insert("globex", AbsolutePathBuf::try_new("/srv/tenants/globex")?)?;
+        assert_eq!(
+            "overlapping_roots",
+            roots
+                .insert(
+                    "initech",
+                    AbsolutePathBuf::try_new("/srv/tenants/acme/sub")?
+                )
+                .unwrap_err()
+                .code()
+        );
+        assert!(roots.root("initech").is_none());
+
+        let path = roots.scope(
+            "acme",
+            &AbsolutePathBuf::try_new("/srv/tenants/acme/a.txt")?,
+        )?;
+        assert_eq!("acme", path.tenant());
+        assert_eq!(RelativePath::try_new("a.txt")?, path.relative_path());
+        assert_eq!(Some(path.root()), roots.root("acme"),);
+
+        let other = AbsolutePathBuf::try_new("/srv/tenants/globex/a.txt")?;
+        assert_eq!(
+            TenantScopeError::OutsideTenant(other.to_string_lossy().to_string(), "acme".to_owned()),
+            roots.scope("acme", &other).unwrap_err()
+        );
+        assert_eq!(
+            "unknown_tenant",
+            roots.scope("initech", &other).unwrap_err().code()
+        );
+
+        let dir = roots.scope("acme", roots.root("acme").unwrap())?;
+        assert_eq!(path, dir.join(RelativePath::try_new("a.txt")?)?);
+        assert_eq!(
+            "outside_tenant",
+            dir.join(RelativePath::try_new("../globex/a.txt")?)
+                .unwrap_err()
+                .code()
+        );
+
+        roots.insert("acme", AbsolutePathBuf::try_new("/srv/acme")?)?;
+        assert!(roots.scope("acme", &path).is_err());
+        roots.insert(
+            "initech",
+            AbsolutePathBuf::try_new("/srv/tenants/acme/sub")?,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test_serde {
+    use serde::de::DeserializeSeed;
+
+    use crate::AbsolutePathBuf;
+    use crate::TenantRoots;
+
+    #[test]
+    fn revalidates_when_deserializing() -> anyhow::Result<()> {
+        let mut roots = TenantRoots::new();
+        roots.insert("acme", AbsolutePathBuf::try_new("/srv/acme")?)?;
+        let path = roots.scope("acme", &AbsolutePathBuf::try_new("/srv/acme/a.txt")?)?;
+        let json = serde_json::to_string(&path)?;
+        assert_eq!(r#"{"tenant":"acme","path":"/srv/acme/a.txt"}"#, json);
+
+        let deserialized = roots.deserialize(&mut serde_json::Deserializer::from_str(&json))?;
+        assert_eq!(path, deserialized);
+
+        roots.insert("acme", AbsolutePathBuf::try_new("/srv/moved")?)?;
+        assert!(roots
+            .deserialize(&mut serde_json::Deserializer::from_str(&json))
+            .is_err());
+        Ok(())
+    }
+}
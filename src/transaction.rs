@@ -0,0 +1,384 @@
+use std::sync::Arc;
+
+use crate::fs::AuditHook;
+use crate::fs::Mode;
+use crate::fs::Operation;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+
+/// A group of file operations that either all take effect, or are all undone.
+///
+/// Operations are applied immediately, and enough is recorded to reverse them. Files that are
+/// replaced or deleted are moved (or hard linked) to a backup beside them, so that
+/// [`FsTransaction::rollback`] can put them back, and [`FsTransaction::commit`] removes the
+/// backups. Dropping a transaction without committing it rolls it back.
+///
+/// This does not protect against other processes changing the same files at the same time, and
+/// if the process exits before committing or rolling back, the backups are left behind.
+///
+/// A transaction created with [`FsTransaction::dry_run`] makes no changes, but still records
+/// what it would do in [`FsTransaction::operations`].
+#[derive(Default)]
+pub struct FsTransaction {
+    mode: Mode,
+    operations: Vec<Operation>,
+    undo: Vec<Undo>,
+    audit_hook: Option<Arc<dyn AuditHook>>,
+}
+
+#[derive(Debug)]
+enum Undo {
+    /// `path` did not exist before the transaction.
+    Remove(AbsolutePathBuf),
+    /// `path` was replaced or deleted, and its original is at `backup`.
+    Restore {
+        path: AbsolutePathBuf,
+        backup: AbsolutePathBuf,
+    },
+    /// `from` was renamed to `to`.
+    Rename {
+        from: AbsolutePathBuf,
+        to: AbsolutePathBuf,
+    },
+}
+
+impl FsTransaction {
+    /// Start a transaction with no operations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a transaction that only records the operations it would perform.
+    ///
+    /// Operations still fail if their sources are missing, or if the directory they write to
+    /// does not exist.
+    pub fn dry_run() -> Self {
+        Self {
+            mode: Mode::DryRun,
+            operations: vec![],
+            undo: vec![],
+            audit_hook: None,
+        }
+    }
+
+    /// Call `hook` before each operation in this transaction, after any hook set with
+    /// [`crate::fs::set_audit_hook`]. If it returns an error, the operation fails.
+    ///
+    /// Rolling back is not audited, so that vetoes cannot leave a transaction half undone.
+    pub fn with_audit_hook(mut self, hook: Arc<dyn AuditHook>) -> Self {
+        self.audit_hook = Some(hook);
+        self
+    }
+
+    /// The operations that have been performed (or would have been, for a dry run) so far, in
+    /// order.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Atomically write `contents` to `path`, as [`crate::fs::write_atomic`] does.
+    pub fn write(
+        &mut self,
+        path: &AbsolutePath,
+        contents: impl AsRef<[u8]>,
+    ) -> std::io::Result<()> {
+        let operation = Operation::Write(path.into());
+        if self.mode == Mode::DryRun {
+            require_exists(path.parent().unwrap_or(path))?;
+            self.operations.push(operation);
+            return Ok(());
+        }
+        crate::fs::audit(&operation, self.audit_hook.as_deref())?;
+        let undo = match self.backup_file(path)? {
+            Some(backup) => Undo::Restore {
+                path: path.into(),
+                backup,
+            },
+            None => Undo::Remove(path.into()),
+        };
+        match crate::fs::write_atomic_unaudited(path, contents.as_ref()) {
+            Ok(()) => {
+                self.undo.push(undo);
+                self.operations.push(operation);
+                Ok(())
+            }
+            Err(e) => {
+                if let Undo::Restore { backup, .. } = undo {
+                    let _ = std::fs::remove_file(backup);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Rename `from` to `to`, replacing any file that is already at `to`.
+    pub fn rename(&mut self, from: &AbsolutePath, to: &AbsolutePath) -> std::io::Result<()> {
+        let operation = Operation::Rename {
+            from: from.into(),
+            to: to.into(),
+        };
+        if self.mode == Mode::DryRun {
+            require_exists(from)?;
+            self.operations.push(operation);
+            return Ok(());
+        }
+        crate::fs::audit(&operation, self.audit_hook.as_deref())?;
+        let backup = self.backup_file(to)?;
+        if let Err(e) = std::fs::rename(from, to) {
+            if let Some(backup) = backup {
+                let _ = std::fs::remove_file(backup);
+            }
+            return Err(e);
+        }
+        if let Some(backup) = backup {
+            self.undo.push(Undo::Restore {
+                path: to.into(),
+                backup,
+            });
+        }
+        self.undo.push(Undo::Rename {
+            from: from.into(),
+            to: to.into(),
+        });
+        self.operations.push(operation);
+        Ok(())
+    }
+
+    /// Delete the file or directory at `path`.
+    ///
+    /// It is moved aside until the transaction is committed.
+    pub fn delete(&mut self, path: &AbsolutePath) -> std::io::Result<()> {
+        let operation = Operation::Remove(path.into());
+        if self.mode == Mode::DryRun {
+            require_exists(path)?;
+        } else {
+            crate::fs::audit(&operation, self.audit_hook.as_deref())?;
+            let backup = backup_path(path)?;
+            std::fs::rename(path, &backup)?;
+            self.undo.push(Undo::Restore {
+                path: path.into(),
+                backup,
+            });
+        }
+        self.operations.push(operation);
+        Ok(())
+    }
+
+    /// Keep all of the changes, and remove the backups.
+    ///
+    /// Errors removing backups are returned, but the changes are kept regardless.
+    pub fn commit(mut self) -> std::io::Result<()> {
+        let mut result = Ok(());
+        for undo in std::mem::take(&mut self.undo) {
+            if let Undo::Restore { backup, .. } = undo {
+                let removed = match backup.symlink_metadata() {
+                    Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(&backup),
+                    Ok(_) => std::fs::remove_file(&backup),
+                    Err(e) => Err(e),
+                };
+                if result.is_ok() {
+                    result = removed;
+                }
+            }
+        }
+        result
+    }
+
+    /// Undo all of the changes, most recent first.
+    ///
+    /// Every change is attempted even if some fail, and the first error is returned.
+    pub fn rollback(mut self) -> std::io::Result<()> {
+        self.undo_all()
+    }
+
+    fn undo_all(&mut self) -> std::io::Result<()> {
+        let mut result = Ok(());
+        while let Some(undo) = self.undo.pop() {
+            let undone = match undo {
+                Undo::Remove(path) => std::fs::remove_file(path),
+                Undo::Restore { path, backup } => std::fs::rename(backup, path),
+                Undo::Rename { from, to } => std::fs::rename(to, from),
+            };
+            if result.is_ok() {
+                result = undone;
+            }
+        }
+        result
+    }
+
+    /// If there is a file at `path`, link (or copy) it to a backup and return the backup's path.
+    fn backup_file(&self, path: &AbsolutePath) -> std::io::Result<Option<AbsolutePathBuf>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let backup = backup_path(path)?;
+        if std::fs::hard_link(path, &backup).is_err() {
+            std::fs::copy(path, &backup)?;
+        }
+        Ok(Some(backup))
+    }
+}
+
+impl std::fmt::Debug for FsTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FsTransaction")
+            .field("mode", &self.mode)
+            .field("operations", &self.operations)
+            .field("undo", &self.undo)
+            .field("audit_hook", &self.audit_hook.is_some())
+            .finish()
+    }
+}
+
+impl Drop for FsTransaction {
+    fn drop(&mut self) {
+        let _ = self.undo_all();
+    }
+}
+
+fn require_exists(path: &AbsolutePath) -> std::io::Result<()> {
+    std::fs::symlink_metadata(path).map(|_| ())
+}
+
+/// A path to move `path` to that is beside it, so that it is on the same filesystem.
+fn backup_path(path: &AbsolutePath) -> std::io::Result<AbsolutePathBuf> {
+    let parent = path.parent().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "cannot back up the filesystem root",
+        )
+    })?;
+    Ok(parent
+        .join(crate::unique_file_name(".bak-"))
+        .expect("a plain file name can always be joined"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use crate::fs::AuditHook;
+    use crate::fs::Operation;
+    use crate::AbsolutePath;
+    use crate::AbsolutePathBuf;
+    use crate::FsTransaction;
+
+    fn list(dir: &AbsolutePathBuf) -> anyhow::Result<Vec<String>> {
+        let mut names = std::fs::read_dir(dir)?
+            .map(|e| Ok(e?.file_name().to_string_lossy().into_owned()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    #[test]
+    fn commits_and_rolls_back() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let setup = || -> anyhow::Result<()> {
+            std::fs::write(root.join("existing.txt")?, "old")?;
+            std::fs::write(root.join("from.txt")?, "from")?;
+            std::fs::write(root.join("to.txt")?, "to")?;
+            std::fs::create_dir_all(root.join("dir/nested")?)?;
+            std::fs::write(root.join("dir/nested/file.txt")?, "nested")?;
+            Ok(())
+        };
+        let apply = |tx: &mut FsTransaction| -> anyhow::Result<()> {
+            tx.write(&root.join("new.txt")?, "new")?;
+            tx.write(&root.join("existing.txt")?, "replaced")?;
+            tx.rename(&root.join("from.txt")?, &root.join("to.txt")?)?;
+            tx.delete(&root.join("dir")?)?;
+            assert!(tx.write(&root.join("missing/file.txt")?, "").is_err());
+            assert!(tx
+                .rename(&root.join("missing.txt")?, &root.join("existing.txt")?)
+                .is_err());
+            Ok(())
+        };
+        let original = || -> anyhow::Result<()> {
+            assert_eq!(
+                vec!["dir", "existing.txt", "from.txt", "to.txt"],
+                list(&root)?
+            );
+            assert_eq!("old", std::fs::read_to_string(root.join("existing.txt")?)?);
+            assert_eq!("from", std::fs::read_to_string(root.join("from.txt")?)?);
+            assert_eq!("to", std::fs::read_to_string(root.join("to.txt")?)?);
+            assert_eq!(
+                "nested",
+                std::fs::read_to_string(root.join("dir/nested/file.txt")?)?
+            );
+            Ok(())
+        };
+
+        setup()?;
+        let mut dry_run = FsTransaction::dry_run();
+        apply(&mut dry_run)?;
+        original()?;
+        assert_eq!(
+            vec![
+                Operation::Write(root.join("new.txt")?),
+                Operation::Write(root.join("existing.txt")?),
+                Operation::Rename {
+                    from: root.join("from.txt")?,
+                    to: root.join("to.txt")?,
+                },
+                Operation::Remove(root.join("dir")?),
+            ],
+            dry_run.operations()
+        );
+
+        let mut tx = FsTransaction::new();
+        apply(&mut tx)?;
+        assert_eq!(dry_run.operations(), tx.operations());
+        tx.rollback()?;
+        original()?;
+
+        let mut tx = FsTransaction::new();
+        apply(&mut tx)?;
+        drop(tx);
+        original()?;
+
+        let mut tx = FsTransaction::new();
+        apply(&mut tx)?;
+        tx.commit()?;
+        assert_eq!(vec!["existing.txt", "new.txt", "to.txt"], list(&root)?);
+        assert_eq!(
+            "replaced",
+            std::fs::read_to_string(root.join("existing.txt")?)?
+        );
+        assert_eq!("new", std::fs::read_to_string(root.join("new.txt")?)?);
+        assert_eq!("from", std::fs::read_to_string(root.join("to.txt")?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn vetoes_operations() -> anyhow::Result<()> {
+        struct ReadOnly;
+
+        impl AuditHook for ReadOnly {
+            fn before(&self, operation: &Operation, _: &AbsolutePath) -> std::io::Result<()> {
+                match operation {
+                    Operation::Remove(_) | Operation::Rename { .. } => Err(std::io::Error::new(
+                        std::io::ErrorKind::PermissionDenied,
+                        "read only",
+                    )),
+                    _ => Ok(()),
+                }
+            }
+        }
+
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        std::fs::write(root.join("a.txt")?, "a")?;
+
+        let mut tx = FsTransaction::new().with_audit_hook(Arc::new(ReadOnly));
+        tx.write(&root.join("b.txt")?, "b")?;
+        assert!(tx.delete(&root.join("a.txt")?).is_err());
+        assert!(tx
+            .rename(&root.join("a.txt")?, &root.join("c.txt")?)
+            .is_err());
+        assert_eq!(&[Operation::Write(root.join("b.txt")?)], tx.operations());
+        tx.commit()?;
+        assert_eq!(vec!["a.txt", "b.txt"], list(&root)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::fs::Mode;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::RelativePathBuf;
+
+/// Creates a tree of directories and files beneath a root, e.g. for test fixtures or scaffolding
+/// commands.
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// # let temp = tempfile::tempdir()?;
+/// # let root = paths::AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+/// let created = paths::TreeBuilder::new(&root)
+///     .dir("src", |d| d.file("main.rs", "fn main() {}").dir("bin", |d| d))
+///     .file("Cargo.toml", "[package]")
+///     .finish()?;
+/// assert!(root.join("src/main.rs")?.is_file());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Entries are created as they are added, with the helpers in [`crate::fs`], so the audit hooks
+/// are called for every change. After the first error, later entries are skipped, and the error
+/// is returned by [`TreeBuilder::finish`].
+#[derive(Debug)]
+pub struct TreeBuilder {
+    root: AbsolutePathBuf,
+    mode: Mode,
+    /// The directory that entries are currently being added to, relative to `root`.
+    current: RelativePathBuf,
+    created: BTreeMap<RelativePathBuf, AbsolutePathBuf>,
+    error: Option<std::io::Error>,
+}
+
+impl TreeBuilder {
+    /// Start building beneath `root`, which is created if it does not exist.
+    pub fn new(root: &AbsolutePath) -> Self {
+        Self::with_mode(root, Mode::Execute)
+    }
+
+    /// Like [`TreeBuilder::new`], but in [`Mode::DryRun`] nothing is created, and
+    /// [`TreeBuilder::finish`] returns the entries that would have been.
+    pub fn with_mode(root: &AbsolutePath, mode: Mode) -> Self {
+        Self {
+            root: root.into(),
+            mode,
+            current: RelativePathBuf::new_unchecked(""),
+            created: BTreeMap::new(),
+            error: crate::fs::create_dir_all(root, mode).err(),
+        }
+    }
+
+    /// Create the directory `name` (which may have several components), then add the entries
+    /// from `contents` inside of it.
+    pub fn dir<P, F>(&mut self, name: P, contents: F) -> &mut Self
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut Self) -> &mut Self,
+    {
+        let (relative, path) = match self.resolve(name.as_ref()) {
+            Some(resolved) => resolved,
+            None => return self,
+        };
+        if let Err(e) = crate::fs::create_dir_all(&path, self.mode) {
+            self.error = Some(e);
+            return self;
+        }
+        self.created.insert(relative.clone(), path);
+
+        let parent = std::mem::replace(&mut self.current, relative);
+        contents(self);
+        self.current = parent;
+        self
+    }
+
+    /// Write a file named `name` with `contents`, creating any missing parent directories.
+    pub fn file<P, C>(&mut self, name: P, contents: C) -> &mut Self
+    where
+        P: AsRef<Path>,
+        C: AsRef<[u8]>,
+    {
+        let (relative, path) = match self.resolve(name.as_ref()) {
+            Some(resolved) => resolved,
+            None => return self,
+        };
+        let parent = path.parent().expect("a path beneath the root has a parent");
+        let written = crate::fs::create_dir_all(parent, self.mode).and_then(|_| match self.mode {
+            Mode::Execute => crate::fs::write_atomic(&path, contents),
+            Mode::DryRun => Ok(()),
+        });
+        match written {
+            Ok(()) => {
+                self.created.insert(relative, path);
+            }
+            Err(e) => self.error = Some(e),
+        }
+        self
+    }
+
+    /// Get every directory and file that was added, keyed by their path relative to the root,
+    /// or the first error that happened.
+    pub fn finish(&mut self) -> std::io::Result<BTreeMap<RelativePathBuf, AbsolutePathBuf>> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(std::mem::take(&mut self.created)),
+        }
+    }
+
+    /// The paths of `name` in the current directory, or `None` if there was an error.
+    fn resolve(&mut self, name: &Path) -> Option<(RelativePathBuf, AbsolutePathBuf)> {
+        if self.error.is_some() {
+            return None;
+        }
+        let resolved = self
+            .current
+            .join(name)
+            .ok()
+            .and_then(|relative| Some((relative.clone(), self.root.join_relative(&relative).ok()?)))
+            .filter(|(_, path)| path.starts_with(&self.root) && path != &self.root);
+        if resolved.is_none() {
+            self.error = Some(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "`{}` is not a path beneath `{}`",
+                    name.display(),
+                    self.root.as_path().join(self.current.as_path()).display()
+                ),
+            ));
+        }
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fs::Mode;
+    use crate::AbsolutePathBuf;
+    use crate::RelativePathBuf;
+    use crate::TreeBuilder;
+
+    #[test]
+    fn builds_trees() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?.join("project")?;
+
+        let created = TreeBuilder::new(&root)
+            .dir("a", |d| {
+                d.file("b.txt", "hi").dir("c/d", |d| d.file("e", ""))
+            })
+            .file("f/g.txt", b"bytes")
+            .finish()?;
+
+        assert_eq!("hi", std::fs::read_to_string(root.join("a/b.txt")?)?);
+        assert_eq!("bytes", std::fs::read_to_string(root.join("f/g.txt")?)?);
+        assert!(root.join("a/c/d/e")?.is_file());
+        assert_eq!(
+            vec!["a", "a/b.txt", "a/c/d", "a/c/d/e", "f/g.txt"],
+            created
+                .keys()
+                .map(|k| k.to_lossy_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(&root.join("a/c/d")?),
+            created.get(&RelativePathBuf::try_new("a/c/d")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stops_at_first_error() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+
+        let err = TreeBuilder::new(&root)
+            .dir("a", |d| d.file("../../escape", ""))
+            .file("after", "")
+            .finish()
+            .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+        assert!(!root.join("after")?.exists());
+
+        assert!(TreeBuilder::new(&root).file("/abs", "").finish().is_err());
+        assert!(TreeBuilder::new(&root)
+            .file("x", "")
+            .dir("x/y", |d| d)
+            .finish()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn supports_dry_runs() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?.join("project")?;
+
+        let created = TreeBuilder::with_mode(&root, Mode::DryRun)
+            .dir("a", |d| d.file("b.txt", "hi"))
+            .finish()?;
+        assert_eq!(2, created.len());
+        assert!(!root.exists());
+        Ok(())
+    }
+}
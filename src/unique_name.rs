@@ -0,0 +1,179 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use crate::fs::Operation;
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::InvalidFileName;
+
+/// How many names [`AbsolutePath::child_unique`] tries before giving up, in case the directory is
+/// nearly full of names from a small alphabet.
+const MAX_ATTEMPTS: usize = 100;
+
+/// The shape of the random names that [`AbsolutePath::child_unique`] generates.
+///
+/// By default, names are 16 lowercase letters and digits, with no prefix or suffix.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UniqueName {
+    prefix: String,
+    suffix: String,
+    alphabet: Vec<char>,
+    length: usize,
+}
+
+impl Default for UniqueName {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UniqueName {
+    /// 16 lowercase letters and digits, with no prefix or suffix.
+    pub fn new() -> Self {
+        Self {
+            prefix: String::new(),
+            suffix: String::new(),
+            alphabet: "abcdefghijklmnopqrstuvwxyz0123456789".chars().collect(),
+            length: 16,
+        }
+    }
+
+    /// Start names with `prefix`, e.g. `upload-`.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_owned();
+        self
+    }
+
+    /// End names with `suffix`, e.g. an extension like `.json`.
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_owned();
+        self
+    }
+
+    /// Pick the random characters from `alphabet`.
+    pub fn alphabet(mut self, alphabet: &str) -> Self {
+        self.alphabet = alphabet.chars().collect();
+        self
+    }
+
+    /// Use `length` random characters.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Create an empty file with a new random name in `dir`, and return its path.
+    pub(crate) fn create_in(&self, dir: &AbsolutePath) -> std::io::Result<AbsolutePathBuf> {
+        self.check()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        for _ in 0..MAX_ATTEMPTS {
+            let path = dir
+                .join(self.generate())
+                .expect("the name was checked to be a file name");
+            crate::fs::audit(&Operation::Write(path.clone()), None)?;
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(_) => return Ok(path),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+                "could not find an unused name in `{}` after {} attempts",
+                dir.display(),
+                MAX_ATTEMPTS
+            ),
+        ))
+    }
+
+    /// Check that every generated name will be a portable file name.
+    fn check(&self) -> Result<(), InvalidFileName> {
+        let alphabet: String = self.alphabet.iter().collect();
+        if self.alphabet.is_empty() || self.length == 0 {
+            return Err(InvalidFileName(format!(
+                "{}{}{}",
+                self.prefix, alphabet, self.suffix
+            )));
+        }
+        crate::check_portable_file_name(&format!("{}{}{}", self.prefix, alphabet, self.suffix))?;
+        // Names made of dots are only possible if the alphabet is just dots, e.g. `.` or `..`.
+        crate::check_portable_file_name(&format!(
+            "{}{}{}",
+            self.prefix,
+            self.alphabet[0].to_string().repeat(self.length),
+            self.suffix
+        ))
+    }
+
+    fn generate(&self) -> String {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let state = RandomState::new();
+        let seed = (
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            std::time::SystemTime::now(),
+        );
+        let random: String = (0..self.length)
+            .map(|i| {
+                let index = state.hash_one((seed, i)) % self.alphabet.len() as u64;
+                self.alphabet[index as usize]
+            })
+            .collect();
+        format!("{}{}{}", self.prefix, random, self.suffix)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePathBuf;
+    use crate::UniqueName;
+
+    #[test]
+    fn creates_unique_children() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+
+        let name = UniqueName::new().prefix("upload-").suffix(".json");
+        let first = root.child_unique(&name)?;
+        let second = root.child_unique(&name)?;
+        assert_ne!(first, second);
+        for path in [&first, &second] {
+            assert!(path.is_file());
+            assert_eq!(Some(root.as_absolute_path()), path.parent());
+            let file_name = path.file_name().unwrap().to_str().unwrap();
+            assert_eq!(28, file_name.len());
+            assert!(file_name.starts_with("upload-") && file_name.ends_with(".json"));
+        }
+
+        // With only two possible names, the third attempt has to fail.
+        let tiny = UniqueName::new().alphabet("ab").length(1);
+        let mut created = vec![root.child_unique(&tiny)?, root.child_unique(&tiny)?];
+        created.sort();
+        assert_eq!(vec![root.join("a")?, root.join("b")?], created);
+        assert_eq!(
+            std::io::ErrorKind::AlreadyExists,
+            root.child_unique(&tiny).unwrap_err().kind()
+        );
+
+        for invalid in [
+            UniqueName::new().alphabet(""),
+            UniqueName::new().length(0),
+            UniqueName::new().alphabet("a/b"),
+            UniqueName::new().alphabet(".").length(2),
+            UniqueName::new().prefix("a:"),
+        ] {
+            assert_eq!(
+                std::io::ErrorKind::InvalidInput,
+                root.child_unique(&invalid).unwrap_err().kind()
+            );
+        }
+        Ok(())
+    }
+}
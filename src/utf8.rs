@@ -0,0 +1,516 @@
+use std::borrow::Borrow;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use ref_cast::RefCast;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::AbsolutePathBufNewError;
+use crate::AbsolutePathNewError;
+use crate::NotRelative;
+use crate::NotUtf8;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// Define a borrowed and owned pair of UTF-8 path types, wrapping `str` and `String`, that
+/// deref to the existing path types.
+macro_rules! utf8_path_types {
+    (
+        $borrowed:ident($inner:ident, $new_error:ident, $as_inner:ident),
+        $owned:ident($inner_owned:ident, $buf_new_error:ident, $as_borrowed:ident),
+    ) => {
+        #[doc = concat!("A [`", stringify!($inner), "`] that is guaranteed to be valid UTF-8, so it can be used as a `str` without")]
+        /// checking, e.g. for databases and other text formats.
+        #[derive(Debug, RefCast)]
+        #[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression))]
+        #[cfg_attr(feature="diesel", diesel(sql_type = diesel::sql_types::Text, not_sized))]
+        #[repr(transparent)]
+        pub struct $borrowed(str);
+
+        impl $borrowed {
+            #[doc = concat!("Try to create a new [`", stringify!($borrowed), "`], per [`", stringify!($inner), "::try_new`].")]
+            pub fn try_new<S: AsRef<str> + ?Sized>(path: &S) -> Result<&Self, $new_error> {
+                $inner::try_new(path.as_ref())?;
+                Ok(Self::ref_cast(path.as_ref()))
+            }
+
+            #[doc = concat!("Create a [`", stringify!($borrowed), "`] per [`", stringify!($borrowed), "::try_new`] that panics on an invalid path.")]
+            pub fn new_unchecked<S: AsRef<str> + ?Sized>(path: &S) -> &Self {
+                Self::try_new(path).expect("a valid path")
+            }
+
+            /// Get the path as a `str`.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Get a reference to the path as a `Path`.
+            pub fn as_path(&self) -> &Path {
+                Path::new(&self.0)
+            }
+
+            #[doc = concat!("Get the path as a [`", stringify!($inner), "`].")]
+            pub fn $as_inner(&self) -> &$inner {
+                $inner::ref_cast(Path::new(&self.0))
+            }
+        }
+
+        impl<'a> TryFrom<&'a $inner> for &'a $borrowed {
+            type Error = NotUtf8;
+
+            fn try_from(value: &'a $inner) -> Result<Self, Self::Error> {
+                value
+                    .as_path()
+                    .to_str()
+                    .map($borrowed::ref_cast)
+                    .ok_or_else(|| NotUtf8(value.display().to_string()))
+            }
+        }
+
+        /// Paths are compared like `Path`s, by component, so e.g. `foo/./bar` is equal to `foo/bar`.
+        impl PartialEq for $borrowed {
+            fn eq(&self, other: &Self) -> bool {
+                self.as_path() == other.as_path()
+            }
+        }
+
+        impl Eq for $borrowed {}
+
+        impl PartialOrd for $borrowed {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $borrowed {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.as_path().cmp(other.as_path())
+            }
+        }
+
+        impl std::hash::Hash for $borrowed {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.as_path().hash(state)
+            }
+        }
+
+        impl AsRef<str> for $borrowed {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<Path> for $borrowed {
+            fn as_ref(&self) -> &Path {
+                self.as_path()
+            }
+        }
+
+        impl AsRef<$inner> for $borrowed {
+            fn as_ref(&self) -> &$inner {
+                self.$as_inner()
+            }
+        }
+
+        impl Deref for $borrowed {
+            type Target = $inner;
+
+            fn deref(&self) -> &Self::Target {
+                self.$as_inner()
+            }
+        }
+
+        impl ToOwned for $borrowed {
+            type Owned = $owned;
+
+            fn to_owned(&self) -> Self::Owned {
+                $owned(canonical_string(self.as_path()))
+            }
+        }
+
+        #[cfg(feature = "display")]
+        impl std::fmt::Display for $borrowed {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $borrowed {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                self.0.serialize(serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de: 'a, 'a> serde::Deserialize<'de> for &'a $borrowed {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                use serde::de::Error;
+                let path = <&str>::deserialize(deserializer)?;
+                $borrowed::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
+            }
+        }
+
+        #[cfg(feature = "diesel")]
+        impl<DB> diesel::serialize::ToSql<diesel::sql_types::Text, DB> for $borrowed
+        where
+            DB: diesel::backend::Backend,
+            str: diesel::serialize::ToSql<diesel::sql_types::Text, DB>,
+        {
+            fn to_sql<'b>(
+                &'b self,
+                out: &mut diesel::serialize::Output<'b, '_, DB>,
+            ) -> diesel::serialize::Result {
+                self.0.to_sql(out)
+            }
+        }
+
+        #[doc = concat!("The owned version of [`", stringify!($borrowed), "`]. This normalizes the path on instantiation, like [`", stringify!($inner_owned), "`].")]
+        #[derive(Debug, Clone)]
+        #[cfg_attr(
+            feature = "diesel",
+            derive(diesel::expression::AsExpression, diesel::FromSqlRow)
+        )]
+        #[cfg_attr(feature="diesel", diesel(sql_type = diesel::sql_types::Text))]
+        pub struct $owned(String);
+
+        impl $owned {
+            #[doc = concat!("Try to create a new [`", stringify!($owned), "`], per [`", stringify!($inner_owned), "::try_new`].")]
+            pub fn try_new<S: Into<String>>(path: S) -> Result<Self, $buf_new_error> {
+                let path = $inner_owned::try_new(path.into())?;
+                Ok($owned(canonical_string(path.as_path())))
+            }
+
+            #[doc = concat!("Get a new [`", stringify!($borrowed), "`] referencing the internal string.")]
+            pub fn $as_borrowed(&self) -> &$borrowed {
+                $borrowed::ref_cast(self.0.as_str())
+            }
+
+            /// Get the path as a `str`.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Take ownership of the path as a `String`.
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl TryFrom<$inner_owned> for $owned {
+            type Error = NotUtf8;
+
+            fn try_from(value: $inner_owned) -> Result<Self, Self::Error> {
+                let borrowed: &$borrowed = value.$as_inner().try_into()?;
+                Ok(borrowed.to_owned())
+            }
+        }
+
+        impl From<$owned> for $inner_owned {
+            fn from(value: $owned) -> Self {
+                $inner_owned::from(value.$as_inner())
+            }
+        }
+
+        impl From<&$borrowed> for $owned {
+            fn from(value: &$borrowed) -> Self {
+                value.to_owned()
+            }
+        }
+
+        impl FromStr for $owned {
+            type Err = $buf_new_error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $owned::try_new(s)
+            }
+        }
+
+        impl PartialEq for $owned {
+            fn eq(&self, other: &Self) -> bool {
+                self.$as_borrowed() == other.$as_borrowed()
+            }
+        }
+
+        impl Eq for $owned {}
+
+        impl PartialOrd for $owned {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $owned {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.$as_borrowed().cmp(other.$as_borrowed())
+            }
+        }
+
+        impl std::hash::Hash for $owned {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.$as_borrowed().hash(state)
+            }
+        }
+
+        impl AsRef<str> for $owned {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<Path> for $owned {
+            fn as_ref(&self) -> &Path {
+                self.as_path()
+            }
+        }
+
+        impl AsRef<$inner> for $owned {
+            fn as_ref(&self) -> &$inner {
+                self.$as_inner()
+            }
+        }
+
+        impl Borrow<$borrowed> for $owned {
+            fn borrow(&self) -> &$borrowed {
+                self.$as_borrowed()
+            }
+        }
+
+        impl Deref for $owned {
+            type Target = $borrowed;
+
+            fn deref(&self) -> &Self::Target {
+                self.$as_borrowed()
+            }
+        }
+
+        #[cfg(feature = "display")]
+        impl std::fmt::Display for $owned {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $owned {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                self.0.serialize(serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $owned {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                use serde::de::Error;
+                let path = String::deserialize(deserializer)?;
+                $owned::try_new(path).map_err(|e| D::Error::custom(format!("{}", e)))
+            }
+        }
+
+        #[cfg(feature = "diesel")]
+        impl<DB> diesel::serialize::ToSql<diesel::sql_types::Text, DB> for $owned
+        where
+            DB: diesel::backend::Backend,
+            str: diesel::serialize::ToSql<diesel::sql_types::Text, DB>,
+        {
+            fn to_sql<'b>(
+                &'b self,
+                out: &mut diesel::serialize::Output<'b, '_, DB>,
+            ) -> diesel::serialize::Result {
+                self.0.as_str().to_sql(out)
+            }
+        }
+
+        #[cfg(feature = "diesel")]
+        impl<DB> diesel::deserialize::FromSql<diesel::sql_types::Text, DB> for $owned
+        where
+            DB: diesel::backend::Backend,
+            String: diesel::deserialize::FromSql<diesel::sql_types::Text, DB>,
+        {
+            fn from_sql(bytes: <DB as diesel::backend::Backend>::RawValue<'_>) -> diesel::deserialize::Result<Self> {
+                String::from_sql(bytes).and_then(|s| Ok($owned::try_new(s)?))
+            }
+        }
+    };
+}
+
+/// The string form of `path` with its components rejoined, so that e.g. repeated separators and
+/// `.` components in the middle of the path are removed. `path` must be valid UTF-8.
+fn canonical_string(path: &Path) -> String {
+    path.components()
+        .collect::<PathBuf>()
+        .into_os_string()
+        .into_string()
+        .expect("rejoining a UTF-8 path keeps it UTF-8")
+}
+
+utf8_path_types! {
+    Utf8AbsolutePath(AbsolutePath, AbsolutePathNewError, as_absolute_path),
+    Utf8AbsolutePathBuf(AbsolutePathBuf, AbsolutePathBufNewError, as_utf8_absolute_path),
+}
+
+utf8_path_types! {
+    Utf8RelativePath(RelativePath, NotRelative, as_relative_path),
+    Utf8RelativePathBuf(RelativePathBuf, NotRelative, as_utf8_relative_path),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::AbsolutePath;
+    use crate::AbsolutePathBuf;
+    use crate::RelativePathBuf;
+    use crate::Utf8AbsolutePath;
+    use crate::Utf8AbsolutePathBuf;
+    use crate::Utf8RelativePath;
+    use crate::Utf8RelativePathBuf;
+
+    #[test]
+    fn validates_utf8_paths() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let cwd_str = cwd.as_path().to_str().unwrap();
+
+        let absolute = Utf8AbsolutePathBuf::try_new(format!("{}/foo/./bar/../baz", cwd_str))?;
+        assert_eq!(cwd.join("foo/baz")?.as_path(), absolute.as_path());
+        assert_eq!(
+            absolute.as_str(),
+            cwd.join("foo/baz")?.as_path().to_str().unwrap()
+        );
+        assert!(absolute.starts_with(&cwd));
+        assert_eq!(
+            AbsolutePathBuf::from(absolute.clone()),
+            cwd.join("foo/baz")?
+        );
+        assert!(Utf8AbsolutePathBuf::try_new("foo").is_err());
+        assert!(Utf8AbsolutePath::try_new(&format!("{}/foo/../baz", cwd_str)).is_err());
+        assert_eq!(
+            absolute.as_utf8_absolute_path(),
+            <&Utf8AbsolutePath>::try_from(absolute.as_absolute_path())?
+        );
+
+        let relative = Utf8RelativePathBuf::try_new("foo/./bar")?;
+        assert_eq!("foo/bar", relative.as_str());
+        assert_eq!(
+            Utf8RelativePath::try_new("foo/bar")?,
+            Utf8RelativePath::try_new("foo/./bar")?
+        );
+        assert_eq!(Some("bar"), relative.file_name().and_then(|n| n.to_str()));
+        assert_eq!(
+            relative,
+            Utf8RelativePathBuf::try_from(RelativePathBuf::try_new("foo/bar")?)?
+        );
+        assert_eq!(
+            relative.as_utf8_relative_path(),
+            Utf8RelativePath::try_new("foo/bar")?
+        );
+        assert!(Utf8RelativePath::try_new(cwd_str).is_err());
+        assert!("/foo".parse::<Utf8RelativePathBuf>().is_err());
+
+        let _: &AbsolutePath = &absolute;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_non_utf8_paths() -> anyhow::Result<()> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"caf\xe9.txt");
+        let path = AbsolutePathBuf::current_dir().join(name)?;
+        assert_eq!(
+            "not_utf8",
+            Utf8AbsolutePathBuf::try_from(path).unwrap_err().code()
+        );
+        assert!(Utf8RelativePathBuf::try_from(RelativePathBuf::try_new(name)?).is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test_serde {
+    use crate::Utf8RelativePath;
+    use crate::Utf8RelativePathBuf;
+
+    #[test]
+    fn round_trips() -> anyhow::Result<()> {
+        let path = Utf8RelativePathBuf::try_new("foo/bar")?;
+        assert_eq!("\"foo/bar\"", serde_json::to_string(&path)?);
+        assert_eq!(
+            path,
+            serde_json::from_str::<Utf8RelativePathBuf>("\"foo/./bar\"")?
+        );
+        assert_eq!(
+            path.as_utf8_relative_path(),
+            serde_json::from_str::<&Utf8RelativePath>("\"foo/bar\"")?
+        );
+        assert!(serde_json::from_str::<Utf8RelativePathBuf>("\"/foo\"").is_err());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "diesel"))]
+mod test_diesel {
+    use diesel::RunQueryDsl;
+
+    use crate::diesel::QueryDsl;
+    use crate::diesel_helpers::create_table;
+    use crate::Utf8RelativePath;
+    use crate::Utf8RelativePathBuf;
+
+    #[derive(Queryable, Insertable, Clone, Debug, Eq, PartialEq)]
+    #[diesel(table_name = crate::diesel_helpers::schema::test_files)]
+    struct TestFile {
+        id: i32,
+        x: Utf8RelativePathBuf,
+        y: Option<Utf8RelativePathBuf>,
+    }
+
+    #[derive(Insertable, Clone, Debug, Eq, PartialEq)]
+    #[diesel(table_name = crate::diesel_helpers::schema::test_files)]
+    struct TestFileLog<'a> {
+        id: i32,
+        x: &'a Utf8RelativePath,
+        y: Option<&'a Utf8RelativePath>,
+    }
+
+    #[test]
+    fn round_trips() -> anyhow::Result<()> {
+        use crate::diesel_helpers::schema::test_files::dsl::*;
+
+        let mut connection = create_table()?;
+        let file = TestFile {
+            id: 1,
+            x: Utf8RelativePathBuf::try_new("foo/bar")?,
+            y: None,
+        };
+        diesel::insert_into(test_files)
+            .values(&file)
+            .execute(&mut connection)?;
+        diesel::insert_into(test_files)
+            .values(&TestFileLog {
+                id: 2,
+                x: Utf8RelativePath::try_new("baz")?,
+                y: Some(Utf8RelativePath::try_new("quz")?),
+            })
+            .execute(&mut connection)?;
+
+        let files = test_files.order_by(id).load::<TestFile>(&mut connection)?;
+        assert_eq!(file, files[0]);
+        assert_eq!(Some(Utf8RelativePathBuf::try_new("quz")?), files[1].y);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use crate::AbsolutePathBuf;
+use crate::BulkError;
+
+/// Convert every path in `paths` to an [`AbsolutePathBuf`], as [`AbsolutePathBuf::try_new`]
+/// does.
+///
+/// Unlike collecting into a `Result`, this does not stop at the first invalid path, so e.g. a
+/// list of paths from a config file can have all of its problems reported at once.
+pub fn validate_all<I>(paths: I) -> Result<Vec<AbsolutePathBuf>, BulkError>
+where
+    I: IntoIterator,
+    I::Item: Into<PathBuf>,
+{
+    let mut valid = vec![];
+    let mut failures = vec![];
+    for (i, path) in paths.into_iter().enumerate() {
+        match AbsolutePathBuf::try_new(path) {
+            Ok(path) => valid.push(path),
+            Err(e) => failures.push((i, e)),
+        }
+    }
+    if failures.is_empty() {
+        Ok(valid)
+    } else {
+        Err(BulkError(failures))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::validate_all;
+    use crate::AbsolutePathBuf;
+    use crate::BulkError;
+    use crate::NotAbsolute;
+
+    #[test]
+    fn validates_all_paths() -> anyhow::Result<()> {
+        let cwd = AbsolutePathBuf::current_dir();
+        let foo = cwd.join("foo")?;
+        let bar = cwd.join("bar")?;
+
+        assert_eq!(
+            vec![foo.clone(), bar.clone()],
+            validate_all([cwd.as_path().join("foo"), cwd.as_path().join("baz/../bar")])?
+        );
+        assert!(validate_all(Vec::<String>::new())?.is_empty());
+
+        let err =
+            validate_all([foo.to_lossy_string(), "a".to_owned(), "b".to_owned()]).unwrap_err();
+        assert_eq!(
+            BulkError(vec![
+                (1, NotAbsolute("a".to_owned()).into()),
+                (2, NotAbsolute("b".to_owned()).into()),
+            ]),
+            err
+        );
+        assert_eq!(
+            "2 invalid paths: [1] `a` was not an absolute path; [2] `b` was not an absolute path",
+            err.to_string()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::AbsolutePathBuf;
+
+/// Filesystem types on linux that are mounted for the kernel's use, rather than for storing
+/// files.
+#[cfg(target_os = "linux")]
+const VIRTUAL_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "securityfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// List the roots of the mounted volumes, e.g. to seed navigation in a file picker.
+///
+/// On windows these are drive roots like `C:\`. On linux they are the mount points in
+/// `/proc/mounts`, leaving out virtual filesystems like `/proc`, and on macOS they are the
+/// mount points that Finder shows. Elsewhere (or if the mounts cannot be read), this is just
+/// `/`. The result is sorted, and never has duplicates.
+pub fn list_volumes() -> Vec<AbsolutePathBuf> {
+    let mut volumes = volumes();
+    if cfg!(unix) && volumes.is_empty() {
+        volumes.push(AbsolutePathBuf::new_unchecked("/"));
+    }
+    volumes.sort();
+    volumes.dedup();
+    volumes
+}
+
+#[cfg(target_os = "linux")]
+fn volumes() -> Vec<AbsolutePathBuf> {
+    match std::fs::read_to_string("/proc/mounts") {
+        Ok(mounts) => parse_mounts(&mounts),
+        Err(_) => vec![],
+    }
+}
+
+/// Get the mount points from the contents of `/proc/mounts`.
+#[cfg(target_os = "linux")]
+fn parse_mounts(mounts: &str) -> Vec<AbsolutePathBuf> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_device, mount_point, fs_type) = (fields.next()?, fields.next()?, fields.next()?);
+            if VIRTUAL_FILESYSTEMS.contains(&fs_type) {
+                return None;
+            }
+            AbsolutePathBuf::try_new(unescape_mount_point(mount_point)).ok()
+        })
+        .collect()
+}
+
+/// `/proc/mounts` escapes spaces, tabs, newlines and backslashes as three digit octal.
+#[cfg(target_os = "linux")]
+fn unescape_mount_point(escaped: &str) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = escaped.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match octal {
+            Some(b) => {
+                unescaped.push(b);
+                i += 4;
+            }
+            None => {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    std::ffi::OsString::from_vec(unescaped)
+}
+
+#[cfg(target_os = "macos")]
+fn volumes() -> Vec<AbsolutePathBuf> {
+    use std::ffi::CStr;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut mounts: *mut libc::statfs = std::ptr::null_mut();
+    // SAFETY: `mounts` is a valid out pointer. The buffer it is set to is owned by libc.
+    let count = unsafe { libc::getmntinfo(&mut mounts, libc::MNT_NOWAIT) };
+    if count <= 0 || mounts.is_null() {
+        return vec![];
+    }
+    // SAFETY: `getmntinfo` returned `count` entries at `mounts`.
+    let mounts = unsafe { std::slice::from_raw_parts(mounts, count as usize) };
+    mounts
+        .iter()
+        .filter(|m| m.f_flags & libc::MNT_DONTBROWSE as u32 == 0)
+        .filter_map(|m| {
+            // SAFETY: `f_mntonname` is nul terminated.
+            let name = unsafe { CStr::from_ptr(m.f_mntonname.as_ptr()) };
+            AbsolutePathBuf::try_new(OsStr::from_bytes(name.to_bytes())).ok()
+        })
+        .collect()
+}
+
+/// Drive letters that have something mounted on them.
+#[cfg(windows)]
+fn volumes() -> Vec<AbsolutePathBuf> {
+    (b'A'..=b'Z')
+        .map(|letter| format!("{}:\\", letter as char))
+        .filter(|root| std::path::Path::new(root).exists())
+        .filter_map(|root| AbsolutePathBuf::try_new(root).ok())
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn volumes() -> Vec<AbsolutePathBuf> {
+    vec![]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::list_volumes;
+    #[cfg(target_os = "linux")]
+    use crate::volumes::parse_mounts;
+
+    #[test]
+    fn lists_volumes() {
+        let volumes = list_volumes();
+        assert!(!volumes.is_empty());
+        let cwd = std::env::current_dir().unwrap();
+        assert!(volumes.iter().any(|v| cwd.starts_with(v)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_proc_mounts() {
+        let mounts = "\
+            /dev/sda1 / ext4 rw,relatime 0 0\n\
+            proc /proc proc rw,nosuid 0 0\n\
+            /dev/sdb1 /mnt/my\\040disk ext4 rw 0 0\n\
+            tmpfs /tmp tmpfs rw 0 0\n";
+        assert_eq!(
+            vec!["/", "/mnt/my disk", "/tmp"],
+            parse_mounts(mounts)
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+}
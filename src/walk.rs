@@ -0,0 +1,234 @@
+use std::fs::FileType;
+
+use crate::AbsolutePath;
+use crate::AbsolutePathBuf;
+use crate::IgnoreRules;
+use crate::RelativePath;
+use crate::RelativePathBuf;
+
+/// An entry found by [`walk_project`].
+#[derive(Debug, Clone)]
+pub struct WalkEntry {
+    path: AbsolutePathBuf,
+    relative_path: RelativePathBuf,
+    file_type: FileType,
+    depth: usize,
+}
+
+impl WalkEntry {
+    /// The absolute path to this entry.
+    pub fn path(&self) -> &AbsolutePath {
+        self.path.as_absolute_path()
+    }
+
+    /// The path to this entry, relative to the directory being walked.
+    pub fn relative_path(&self) -> &RelativePath {
+        self.relative_path.as_relative_path()
+    }
+
+    /// The type of this entry. Symlinks are not followed.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// Whether this entry is a directory. Symlinks to directories are not.
+    pub fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    /// How many directories deep this entry is, starting at 1 for the walked directory's
+    /// children.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Take ownership of the absolute path to this entry.
+    pub fn into_path(self) -> AbsolutePathBuf {
+        self.path
+    }
+}
+
+/// Walk everything beneath `root`, skipping `.git` directories and ignored paths, like
+/// ripgrep and similar tools do.
+///
+/// `.ignore` files are always honored. If `root` is inside of a git repository, `.gitignore`
+/// files (including those in the ancestors of `root` within the repository) and
+/// `.git/info/exclude` are also honored. Entries are returned depth first in sorted order, with
+/// each directory before its contents. Symlinks are returned but not followed, and directories
+/// that cannot be read are skipped.
+pub fn walk_project(root: &AbsolutePath) -> impl Iterator<Item = WalkEntry> {
+    ProjectWalk::new(root)
+}
+
+struct ProjectWalk {
+    root: AbsolutePathBuf,
+    /// The directory that ignore rules are relative to: the repository root if there is one.
+    rules_root: AbsolutePathBuf,
+    in_repo: bool,
+    rules: IgnoreRules,
+    pending: Vec<WalkEntry>,
+}
+
+impl ProjectWalk {
+    fn new(root: &AbsolutePath) -> Self {
+        let repo_root = crate::find_git_root(root);
+        let mut walk = Self {
+            root: root.into(),
+            rules_root: repo_root.unwrap_or(root).into(),
+            in_repo: repo_root.is_some(),
+            rules: IgnoreRules::new(),
+            pending: vec![],
+        };
+        if let Some(repo_root) = repo_root {
+            if let Ok(exclude) = repo_root.join(".git/info/exclude") {
+                let _ = walk
+                    .rules
+                    .add_file(RelativePath::new_unchecked(""), &exclude);
+            }
+            let mut dirs: Vec<_> = root
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(repo_root))
+                .collect();
+            dirs.reverse();
+            for dir in dirs {
+                walk.add_rules(dir);
+            }
+        }
+        walk.push_children(root, 1);
+        walk
+    }
+
+    fn add_rules(&mut self, dir: &AbsolutePath) {
+        let relative = match dir.strip_prefix(&self.rules_root) {
+            Ok(relative) => relative,
+            Err(_) => return,
+        };
+        let mut names = vec![".ignore"];
+        if self.in_repo {
+            names.insert(0, ".gitignore");
+        }
+        for name in names {
+            if let Ok(file) = dir.join(name) {
+                let _ = self.rules.add_file(relative, &file);
+            }
+        }
+    }
+
+    /// Load the ignore rules in `dir`, and queue its entries that are not ignored.
+    fn push_children(&mut self, dir: &AbsolutePath, depth: usize) {
+        self.add_rules(dir);
+        let read_dir = match std::fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return,
+        };
+        let mut children: Vec<_> = read_dir
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                if entry.file_name() == ".git" {
+                    return None;
+                }
+                let path = AbsolutePathBuf::new_unchecked(entry.path());
+                let file_type = entry.file_type().ok()?;
+                let for_rules = path.strip_prefix(&self.rules_root).ok()?;
+                if self.rules.is_ignored_entry(for_rules, file_type.is_dir()) {
+                    return None;
+                }
+                let relative_path = path.strip_prefix(&self.root).ok()?.into();
+                Some(WalkEntry {
+                    path,
+                    relative_path,
+                    file_type,
+                    depth,
+                })
+            })
+            .collect();
+        children.sort_by(|a, b| b.path.cmp(&a.path));
+        self.pending.extend(children);
+    }
+}
+
+impl Iterator for ProjectWalk {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.pending.pop()?;
+        if entry.is_dir() {
+            self.push_children(&entry.path.clone(), entry.depth + 1);
+        }
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::walk_project;
+    use crate::AbsolutePathBuf;
+
+    #[test]
+    fn walks_project() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let root = AbsolutePathBuf::try_new(temp.path().canonicalize()?)?;
+        let repo = root.join("repo")?;
+        for file in [
+            ".git/HEAD",
+            ".git/info/exclude",
+            ".gitignore",
+            "README.md",
+            "secret.key",
+            "src/.ignore",
+            "src/lib.rs",
+            "src/gen.rs",
+            "src/nested/.gitignore",
+            "src/nested/mod.rs",
+            "src/nested/out.log",
+            "src/nested/keep.log",
+            "target/debug/app",
+        ] {
+            let path = repo.join(file)?;
+            path.ensure_parent_exists()?;
+            std::fs::write(path, "")?;
+        }
+        std::fs::write(repo.join(".git/info/exclude")?, "*.key\n")?;
+        std::fs::write(repo.join(".gitignore")?, "target/\n*.log\n")?;
+        std::fs::write(repo.join("src/.ignore")?, "gen.rs\n")?;
+        std::fs::write(repo.join("src/nested/.gitignore")?, "!keep.log\n")?;
+
+        let walked = |dir: &AbsolutePathBuf| -> Vec<(String, usize)> {
+            walk_project(dir)
+                .map(|e| (e.relative_path().to_lossy_string(), e.depth()))
+                .collect()
+        };
+        assert_eq!(
+            vec![
+                (".gitignore".to_owned(), 1),
+                ("README.md".to_owned(), 1),
+                ("src".to_owned(), 1),
+                ("src/.ignore".to_owned(), 2),
+                ("src/lib.rs".to_owned(), 2),
+                ("src/nested".to_owned(), 2),
+                ("src/nested/.gitignore".to_owned(), 3),
+                ("src/nested/keep.log".to_owned(), 3),
+                ("src/nested/mod.rs".to_owned(), 3),
+            ],
+            walked(&repo)
+        );
+        // Rules from the repository root still apply when walking a subdirectory.
+        assert_eq!(
+            vec![
+                (".gitignore".to_owned(), 1),
+                ("keep.log".to_owned(), 1),
+                ("mod.rs".to_owned(), 1),
+            ],
+            walked(&repo.join("src/nested")?)
+        );
+
+        // Outside of a repository, `.gitignore` files are not used.
+        std::fs::remove_dir_all(repo.join(".git")?)?;
+        let paths: Vec<_> = walked(&repo).into_iter().map(|(p, _)| p).collect();
+        assert!(paths.contains(&"target/debug/app".to_owned()));
+        assert!(paths.contains(&"src/nested/out.log".to_owned()));
+        assert!(!paths.contains(&"src/gen.rs".to_owned()));
+        Ok(())
+    }
+}
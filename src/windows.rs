@@ -0,0 +1,292 @@
+//! Helpers for calling into the Win32 API.
+
+#[cfg(feature = "short-names")]
+pub(crate) fn long_path_name(path: &std::path::Path) -> std::io::Result<std::ffi::OsString> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::ffi::OsStringExt;
+
+    use windows_sys::Win32::Storage::FileSystem::GetLongPathNameW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut buf = vec![0u16; wide.len().max(260)];
+    loop {
+        // SAFETY: `wide` is nul terminated, and `buf.len()` is the real size of `buf`.
+        let len =
+            unsafe { GetLongPathNameW(wide.as_ptr(), buf.as_mut_ptr(), buf.len() as u32) } as usize;
+        if len == 0 {
+            return Err(std::io::Error::last_os_error());
+        } else if len < buf.len() {
+            buf.truncate(len);
+            return Ok(OsString::from_wide(&buf));
+        } else {
+            // `len` is the required size, including the nul terminator.
+            buf.resize(len, 0);
+        }
+    }
+}
+
+/// The bytes available to the current user, and the total bytes, on the volume containing `path`.
+pub(crate) fn disk_space(path: &std::path::Path) -> std::io::Result<(u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+
+    // Declared here, rather than taken from `windows-sys`, as that is optional.
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            available: *mut u64,
+            total: *mut u64,
+            free: *mut u64,
+        ) -> i32;
+    }
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let (mut available, mut total) = (0, 0);
+    // SAFETY: `wide` is nul terminated, and the sizes are valid out pointers. The free space
+    // may be null.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            &mut total,
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok((available, total))
+    }
+}
+
+/// Whether `path` itself (not following symlinks) is a reparse point.
+pub(crate) fn is_reparse_point(path: &std::path::Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    // `FILE_ATTRIBUTE_REPARSE_POINT`, which is spelled out as `windows-sys` is optional.
+    const REPARSE_POINT: u32 = 0x400;
+    path.symlink_metadata()
+        .is_ok_and(|m| m.file_attributes() & REPARSE_POINT != 0)
+}
+
+/// Whether `path` is a reparse point with the `IO_REPARSE_TAG_MOUNT_POINT` tag.
+#[cfg(feature = "junctions")]
+pub(crate) fn is_junction(path: &std::path::Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::FindClose;
+    use windows_sys::Win32::Storage::FileSystem::FindFirstFileW;
+    use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_REPARSE_POINT;
+    use windows_sys::Win32::Storage::FileSystem::WIN32_FIND_DATAW;
+    use windows_sys::Win32::System::SystemServices::IO_REPARSE_TAG_MOUNT_POINT;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut data = WIN32_FIND_DATAW::default();
+    // SAFETY: `wide` is nul terminated, and `data` is a valid `WIN32_FIND_DATAW`.
+    let handle = unsafe { FindFirstFileW(wide.as_ptr(), &mut data) };
+    if handle == INVALID_HANDLE_VALUE {
+        return false;
+    }
+    // SAFETY: `handle` is a valid search handle, which is not used again.
+    unsafe { FindClose(handle) };
+    // For reparse points, `dwReserved0` is the reparse tag.
+    data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT != 0
+        && data.dwReserved0 == IO_REPARSE_TAG_MOUNT_POINT
+}
+
+/// Create an empty directory at `link`, and make it a junction to `target`.
+#[cfg(feature = "junctions")]
+pub(crate) fn create_junction(
+    link: &std::path::Path,
+    target: &std::path::Path,
+) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+
+    use windows_sys::Win32::Storage::FileSystem::FILE_FLAG_BACKUP_SEMANTICS;
+    use windows_sys::Win32::Storage::FileSystem::FILE_FLAG_OPEN_REPARSE_POINT;
+    use windows_sys::Win32::System::Ioctl::FSCTL_SET_REPARSE_POINT;
+    use windows_sys::Win32::System::SystemServices::IO_REPARSE_TAG_MOUNT_POINT;
+    use windows_sys::Win32::System::IO::DeviceIoControl;
+
+    // Junctions store an NT path, so verbatim `\\?\` prefixes are replaced with `\??\`.
+    let target = target.as_os_str().to_string_lossy();
+    let print_name = target.strip_prefix(r"\\?\").unwrap_or(&target);
+    let substitute_name: Vec<u16> = format!(r"\??\{}", print_name).encode_utf16().collect();
+    let print_name: Vec<u16> = std::ffi::OsStr::new(print_name).encode_wide().collect();
+
+    // A `REPARSE_DATA_BUFFER` with a `MountPointReparseBuffer`. Each name is nul terminated, but
+    // the lengths do not include the terminators.
+    let path_buffer_len = (substitute_name.len() + print_name.len() + 2) * 2;
+    let mut buffer: Vec<u8> = Vec::with_capacity(16 + path_buffer_len);
+    buffer.extend(IO_REPARSE_TAG_MOUNT_POINT.to_le_bytes());
+    buffer.extend(((8 + path_buffer_len) as u16).to_le_bytes());
+    buffer.extend(0u16.to_le_bytes());
+    buffer.extend(0u16.to_le_bytes());
+    buffer.extend(((substitute_name.len() * 2) as u16).to_le_bytes());
+    buffer.extend((((substitute_name.len() + 1) * 2) as u16).to_le_bytes());
+    buffer.extend(((print_name.len() * 2) as u16).to_le_bytes());
+    for c in substitute_name
+        .iter()
+        .chain(&[0])
+        .chain(&print_name)
+        .chain(&[0])
+    {
+        buffer.extend(c.to_le_bytes());
+    }
+
+    std::fs::create_dir(link)?;
+    let set = std::fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OPEN_REPARSE_POINT)
+        .open(link)
+        .and_then(|dir| {
+            let mut returned = 0;
+            // SAFETY: `buffer` is a valid reparse data buffer of the given length, and `dir` is
+            // an open handle to the directory.
+            let ok = unsafe {
+                DeviceIoControl(
+                    dir.as_raw_handle(),
+                    FSCTL_SET_REPARSE_POINT,
+                    buffer.as_ptr().cast(),
+                    buffer.len() as u32,
+                    std::ptr::null_mut(),
+                    0,
+                    &mut returned,
+                    std::ptr::null_mut(),
+                )
+            };
+            if ok == 0 {
+                Err(std::io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        });
+    if set.is_err() {
+        let _ = std::fs::remove_dir(link);
+    }
+    set
+}
+
+/// Whether the current process's token is granted generic read, write and execute access to
+/// `path`, according to its security descriptor.
+#[cfg(feature = "acl")]
+pub(crate) fn effective_access(path: &std::path::Path) -> std::io::Result<(bool, bool, bool)> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use std::os::windows::io::OwnedHandle;
+
+    use windows_sys::Win32::Security::AccessCheck;
+    use windows_sys::Win32::Security::DuplicateToken;
+    use windows_sys::Win32::Security::GetFileSecurityW;
+    use windows_sys::Win32::Security::MapGenericMask;
+    use windows_sys::Win32::Security::SecurityImpersonation;
+    use windows_sys::Win32::Security::DACL_SECURITY_INFORMATION;
+    use windows_sys::Win32::Security::GENERIC_MAPPING;
+    use windows_sys::Win32::Security::GROUP_SECURITY_INFORMATION;
+    use windows_sys::Win32::Security::OWNER_SECURITY_INFORMATION;
+    use windows_sys::Win32::Security::PRIVILEGE_SET;
+    use windows_sys::Win32::Security::TOKEN_DUPLICATE;
+    use windows_sys::Win32::Security::TOKEN_QUERY;
+    use windows_sys::Win32::Storage::FileSystem::FILE_ALL_ACCESS;
+    use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_EXECUTE;
+    use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_READ;
+    use windows_sys::Win32::Storage::FileSystem::FILE_GENERIC_WRITE;
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+    use windows_sys::Win32::System::Threading::OpenProcessToken;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let info = OWNER_SECURITY_INFORMATION | GROUP_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION;
+    let mut len = 0;
+    // SAFETY: `wide` is nul terminated, and a zero length buffer is allowed to query the length.
+    unsafe { GetFileSecurityW(wide.as_ptr(), info, std::ptr::null_mut(), 0, &mut len) };
+    if len == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // `u64`s so that the descriptor is aligned.
+    let mut descriptor = vec![0u64; (len as usize).div_ceil(8)];
+    // SAFETY: `descriptor` is at least `len` bytes.
+    if unsafe {
+        GetFileSecurityW(
+            wide.as_ptr(),
+            info,
+            descriptor.as_mut_ptr().cast(),
+            len,
+            &mut len,
+        )
+    } == 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // `AccessCheck` needs an impersonation token, rather than the process's primary token.
+    let mut process_token = std::ptr::null_mut();
+    // SAFETY: `process_token` is a valid out pointer.
+    if unsafe {
+        OpenProcessToken(
+            GetCurrentProcess(),
+            TOKEN_DUPLICATE | TOKEN_QUERY,
+            &mut process_token,
+        )
+    } == 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `process_token` is an open handle that nothing else owns.
+    let process_token = unsafe { OwnedHandle::from_raw_handle(process_token) };
+    let mut token = std::ptr::null_mut();
+    // SAFETY: `process_token` is open with `TOKEN_DUPLICATE`, and `token` is a valid out pointer.
+    if unsafe {
+        DuplicateToken(
+            std::os::windows::io::AsRawHandle::as_raw_handle(&process_token),
+            SecurityImpersonation,
+            &mut token,
+        )
+    } == 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: `token` is an open handle that nothing else owns.
+    let token = unsafe { OwnedHandle::from_raw_handle(token) };
+
+    let mapping = GENERIC_MAPPING {
+        GenericRead: FILE_GENERIC_READ,
+        GenericWrite: FILE_GENERIC_WRITE,
+        GenericExecute: FILE_GENERIC_EXECUTE,
+        GenericAll: FILE_ALL_ACCESS,
+    };
+    let check = |desired: u32| -> std::io::Result<bool> {
+        let mut mask = desired;
+        let mut privileges = PRIVILEGE_SET::default();
+        let mut privileges_len = std::mem::size_of::<PRIVILEGE_SET>() as u32;
+        let (mut granted, mut status) = (0, 0);
+        // SAFETY: `mask` and `mapping` are valid, and the rest are valid out pointers.
+        unsafe { MapGenericMask(&mut mask, &mapping) };
+        // SAFETY: `descriptor` was filled in by `GetFileSecurityW`, `token` is an open
+        // impersonation token with `TOKEN_QUERY`, and the rest are valid out pointers.
+        if unsafe {
+            AccessCheck(
+                descriptor.as_ptr() as *mut _,
+                std::os::windows::io::AsRawHandle::as_raw_handle(&token),
+                mask,
+                &mapping,
+                &mut privileges,
+                &mut privileges_len,
+                &mut granted,
+                &mut status,
+            )
+        } == 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(status != 0)
+    };
+    Ok((
+        check(FILE_GENERIC_READ)?,
+        check(FILE_GENERIC_WRITE)?,
+        check(FILE_GENERIC_EXECUTE)?,
+    ))
+}